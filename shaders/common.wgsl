@@ -212,7 +212,6 @@ fn combine_state(a: LogicStateAtom, b: LogicStateAtom) -> CombineResult {
 }
 
 const MIN_WIRE_WIDTH = 1u;
-const MAX_WIRE_WIDTH = 256u;
 
 const ATOM_BITS = 32u;
 const MAX_ATOM_COUNT = MAX_WIRE_WIDTH / ATOM_BITS;
@@ -229,6 +228,7 @@ struct WireDriver {
     width: u32,
     output_state_offset: u32,
     next_driver: u32,
+    bit_offset: u32,
 }
 
 @group(0) @binding(2) 
@@ -240,7 +240,10 @@ struct Wire {
     drive_offset: u32,
     first_driver_width: u32,
     first_driver_offset: u32,
+    first_driver_bit_offset: u32,
     driver_list: u32,
+    pull: LogicStateAtom,
+    forced: u32,
 }
 
 @group(0) @binding(3) 
@@ -298,6 +301,25 @@ const COMPONENT_KIND_CMPSLT = 26u;
 const COMPONENT_KIND_CMPSGT = 27u;
 const COMPONENT_KIND_CMPSLE = 28u;
 const COMPONENT_KIND_CMPSGE = 29u;
+const COMPONENT_KIND_LUT    = 30u;
+const COMPONENT_KIND_CLKDIV = 31u;
+const COMPONENT_KIND_NOP    = 32u;
+const COMPONENT_KIND_SATADD = 33u;
+const COMPONENT_KIND_SATSUB = 34u;
+const COMPONENT_KIND_ZEXT   = 35u;
+const COMPONENT_KIND_SEXT   = 36u;
+const COMPONENT_KIND_OE     = 37u;
+const COMPONENT_KIND_BIN2GRAY = 38u;
+const COMPONENT_KIND_GRAY2BIN = 39u;
+const COMPONENT_KIND_ABS    = 40u;
+const COMPONENT_KIND_UMIN   = 41u;
+const COMPONENT_KIND_UMAX   = 42u;
+const COMPONENT_KIND_SMIN   = 43u;
+const COMPONENT_KIND_SMAX   = 44u;
+const COMPONENT_KIND_COUNTER = 45u;
+const COMPONENT_KIND_ADDFLAGS = 46u;
+const COMPONENT_KIND_SUBFLAGS = 47u;
+const COMPONENT_KIND_CMPCONST = 48u;
 
 struct PackedComponent {
     kind_output_count_input_count: u32,
@@ -316,6 +338,7 @@ struct ListData {
     components_changed: atomic<u32>,
     conflict_list_len: atomic<u32>,
     has_conflicts: atomic<u32>,
+    step_count: atomic<u32>,
 }
 
 @group(0) @binding(9) 
@@ -324,7 +347,14 @@ var<storage, read_write> list_data: ListData;
 @group(0) @binding(10) 
 var<storage, read_write> conflict_list: array<u32>;
 
-var<push_constant> reset_changed: u32;
+@group(0) @binding(11) 
+var<storage, read_write> changed_wires: array<atomic<u32>>;
+
+@group(0) @binding(12) 
+var<storage, read> wire_forces: array<LogicStateAtom>;
+
+@group(0) @binding(13) 
+var<storage, read> evaluation_order: array<u32>;
 
 struct Component {
     kind: u32,
@@ -157,11 +157,367 @@ fn add_impl(component: Component) -> bool {
     return state_changed;
 }
 
+// Drives a 1 bit output with `flag`.
+fn write_flag(c_output: ComponentOutput, flag: LogicBitState) -> bool {
+    let atom = LogicStateAtom(select(0u, 1u, flag.state), select(0u, 1u, flag.valid));
+
+    let dst = &output_states[c_output.state_offset];
+    if !logic_state_equal(*dst, atom) {
+        *dst = atom;
+        return true;
+    }
+
+    return false;
+}
+
+fn add_flags_impl(component: Component) -> bool {
+    let sum_output = outputs[component.output_offset_or_first_output];
+    let input_a = inputs[component.first_input];
+    let input_b = inputs[component.first_input + 1u];
+    let is_sub = component.kind == COMPONENT_KIND_SUBFLAGS;
+
+    var state_changed = false;
+    var carry = LogicBitState(is_sub, true);
+    var any_one = false;
+    var any_unknown = false;
+    var sign_a: LogicBitState;
+    var sign_b: LogicBitState;
+    var sign_sum: LogicBitState;
+    for (var bit_index = 0u; bit_index < sum_output.width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var atom_a: LogicStateAtom;
+        if bit_index < input_a.width {
+            atom_a = wire_states[input_a.wire_state_offset + index];
+        } else {
+            atom_a = HIGH_Z;
+        }
+
+        var atom_b: LogicStateAtom;
+        if bit_index < input_b.width {
+            atom_b = wire_states[input_b.wire_state_offset + index];
+        } else {
+            atom_b = HIGH_Z;
+        }
+
+        if is_sub {
+            atom_b.state = ~atom_b.state;
+        }
+
+        let result = logic_add(atom_a, atom_b, carry);
+        carry = result.carry;
+
+        var mask = 0xFFFFFFFFu;
+        let remaining = sum_output.width - bit_index;
+        if remaining < ATOM_BITS {
+            mask = 0xFFFFFFFFu >> (ATOM_BITS - remaining);
+        }
+        any_one = any_one || ((result.sum.state & result.sum.valid & mask) != 0u);
+        any_unknown = any_unknown || ((~result.sum.valid & mask) != 0u);
+
+        if remaining <= ATOM_BITS {
+            // Signed overflow happens if both operands have the same sign and the result does not.
+            let msb_index = remaining - 1u;
+            sign_a = get_bit_state(atom_a, msb_index);
+            sign_b = get_bit_state(atom_b, msb_index);
+            sign_sum = get_bit_state(result.sum, msb_index);
+        }
+
+        let dst = &output_states[sum_output.state_offset + index];
+        if !logic_state_equal(*dst, result.sum) {
+            *dst = result.sum;
+            state_changed = true;
+        }
+    }
+
+    // A single known 1 bit decides the zero flag, even if other bits are unknown.
+    var zero = LogicBitState(false, true);
+    if !any_one {
+        zero = LogicBitState(true, !any_unknown);
+    }
+
+    var overflow = LogicBitState(true, false);
+    if sign_a.valid && sign_b.valid && sign_sum.valid {
+        overflow = LogicBitState((sign_a.state == sign_b.state) && (sign_sum.state != sign_a.state), true);
+    }
+
+    let first_flag = component.output_offset_or_first_output + 1u;
+    state_changed = write_flag(outputs[first_flag], zero) || state_changed;
+    state_changed = write_flag(outputs[first_flag + 1u], sign_sum) || state_changed;
+    state_changed = write_flag(outputs[first_flag + 2u], overflow) || state_changed;
+    return state_changed;
+}
+
+fn sat_add_impl(component: Component) -> bool {
+    let input_a = inputs[component.first_input];
+    let input_b = inputs[component.first_input + 1u];
+    let is_sub = component.kind == COMPONENT_KIND_SATSUB;
+
+    var sum: array<LogicStateAtom, MAX_ATOM_COUNT>;
+    var carry = LogicBitState(is_sub, true);
+    for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var atom_a: LogicStateAtom;
+        if bit_index < input_a.width {
+            atom_a = wire_states[input_a.wire_state_offset + index];
+        } else {
+            atom_a = HIGH_Z;
+        }
+
+        var atom_b: LogicStateAtom;
+        if bit_index < input_b.width {
+            atom_b = wire_states[input_b.wire_state_offset + index];
+        } else {
+            atom_b = HIGH_Z;
+        }
+
+        if is_sub {
+            atom_b.state = ~atom_b.state;
+        }
+
+        let remaining = component.output_width - bit_index;
+        if remaining < ATOM_BITS {
+            // Clear the bits past the output width, so the carry out of the last bit
+            // ends up in the first of them.
+            let mask = 0xFFFFFFFFu >> (ATOM_BITS - remaining);
+            atom_a = LogicStateAtom(atom_a.state & mask, atom_a.valid | ~mask);
+            atom_b = LogicStateAtom(atom_b.state & mask, atom_b.valid | ~mask);
+
+            let result = logic_add(atom_a, atom_b, carry);
+            sum[index] = result.sum;
+            carry = get_bit_state(result.sum, remaining);
+        } else {
+            let result = logic_add(atom_a, atom_b, carry);
+            sum[index] = result.sum;
+            carry = result.carry;
+        }
+    }
+
+    // An addition overflows if the carry is set, a subtraction underflows if it is cleared.
+    let saturate = carry.state != is_sub;
+
+    var state_changed = false;
+    for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var atom: LogicStateAtom;
+        if !carry.valid {
+            atom = UNDEFINED;
+        } else if saturate && is_sub {
+            atom = LOGIC_0;
+        } else if saturate {
+            atom = LOGIC_1;
+        } else {
+            atom = sum[index];
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+fn ext_impl(component: Component) -> bool {
+    let c_input = inputs[component.first_input];
+
+    // The value of all bits past the input.
+    var fill = LOGIC_0;
+    if component.kind == COMPONENT_KIND_SEXT {
+        let msb_index = c_input.width - 1u;
+        let msb_atom = wire_states[c_input.wire_state_offset + (msb_index / ATOM_BITS)];
+        let msb = get_bit_state(msb_atom, msb_index % ATOM_BITS);
+        fill = LogicStateAtom(
+            select(0x00000000u, 0xFFFFFFFFu, msb.state),
+            select(0x00000000u, 0xFFFFFFFFu, msb.valid),
+        );
+    }
+
+    var state_changed = false;
+    for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var atom = fill;
+        if bit_index < c_input.width {
+            var mask = 0xFFFFFFFFu;
+            if (c_input.width - bit_index) < ATOM_BITS {
+                mask = 0xFFFFFFFFu >> (ATOM_BITS - (c_input.width - bit_index));
+            }
+
+            let input_atom = wire_states[c_input.wire_state_offset + index];
+            atom = LogicStateAtom(
+                (input_atom.state & mask) | (fill.state & ~mask),
+                (input_atom.valid & mask) | (fill.valid & ~mask),
+            );
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+// Reads an atom of an input extended or truncated to `width` bits.
+// Bits past the input are Z and bits past `width` are 0.
+fn input_atom_at_width(c_input: ComponentInput, width: u32, index: u32) -> LogicStateAtom {
+    let bit_index = index * ATOM_BITS;
+    if bit_index >= width {
+        return LOGIC_0;
+    }
+
+    var atom = HIGH_Z;
+    if bit_index < c_input.width {
+        atom = wire_states[c_input.wire_state_offset + index];
+        if (c_input.width - bit_index) < ATOM_BITS {
+            let input_mask = 0xFFFFFFFFu >> (ATOM_BITS - (c_input.width - bit_index));
+            atom = LogicStateAtom(atom.state & input_mask, atom.valid & input_mask);
+        }
+    }
+
+    if (width - bit_index) < ATOM_BITS {
+        let width_mask = 0xFFFFFFFFu >> (ATOM_BITS - (width - bit_index));
+        atom = LogicStateAtom(atom.state & width_mask, atom.valid | ~width_mask);
+    }
+
+    return atom;
+}
+
+fn gray_impl(component: Component) -> bool {
+    let c_input = inputs[component.first_input];
+    let atom_count = (component.output_width + ATOM_BITS - 1u) / ATOM_BITS;
+
+    var state_changed = false;
+
+    // Both directions are computed from the most significant atom downwards,
+    // `carry` holds the decoded bit right above the current atom.
+    var carry = LogicBitState(false, true);
+    for (var i = atom_count; i > 0u; i -= 1u) {
+        let index = i - 1u;
+        let input_atom = input_atom_at_width(c_input, component.output_width, index);
+
+        var atom: LogicStateAtom;
+        if component.kind == COMPONENT_KIND_BIN2GRAY {
+            let next_atom = input_atom_at_width(c_input, component.output_width, index + 1u);
+            let shifted = LogicStateAtom(
+                (input_atom.state >> 1u) | (next_atom.state << 31u),
+                (input_atom.valid >> 1u) | (next_atom.valid << 31u),
+            );
+            atom = logic_xor(input_atom, shifted);
+        } else {
+            // Every binary bit is the parity of the gray code bits at and above it.
+            var parity = input_atom.state;
+            parity ^= parity >> 1u;
+            parity ^= parity >> 2u;
+            parity ^= parity >> 4u;
+            parity ^= parity >> 8u;
+            parity ^= parity >> 16u;
+            parity ^= select(0x00000000u, 0xFFFFFFFFu, carry.state);
+
+            var valid = input_atom.valid;
+            valid &= (valid >> 1u) | 0x80000000u;
+            valid &= (valid >> 2u) | 0xC0000000u;
+            valid &= (valid >> 4u) | 0xF0000000u;
+            valid &= (valid >> 8u) | 0xFF000000u;
+            valid &= (valid >> 16u) | 0xFFFF0000u;
+            valid &= select(0x00000000u, 0xFFFFFFFFu, carry.valid);
+
+            atom = LogicStateAtom(parity | ~valid, valid);
+            carry = get_bit_state(atom, 0u);
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+fn min_max_impl(component: Component) -> bool {
+    let input_a = inputs[component.first_input];
+    let input_b = inputs[component.first_input + 1u];
+    let atom_count = (component.output_width + ATOM_BITS - 1u) / ATOM_BITS;
+    let is_signed = (component.kind == COMPONENT_KIND_SMIN) || (component.kind == COMPONENT_KIND_SMAX);
+    let is_max = (component.kind == COMPONENT_KIND_UMAX) || (component.kind == COMPONENT_KIND_SMAX);
+
+    // Flipping the sign bits turns the signed comparison into an unsigned one.
+    let msb_index = component.output_width - 1u;
+    var sign_flip = 0u;
+    if is_signed {
+        sign_flip = 1u << (msb_index % ATOM_BITS);
+    }
+
+    // The most significant differing atom decides the comparison,
+    // but all atoms have to be checked for undefined bits.
+    var valid = true;
+    var decided = false;
+    var a_less = false;
+    for (var i = atom_count; i > 0u; i -= 1u) {
+        let index = i - 1u;
+        let atom_a = input_atom_at_width(input_a, component.output_width, index);
+        let atom_b = input_atom_at_width(input_b, component.output_width, index);
+
+        if (atom_a.valid & atom_b.valid) != 0xFFFFFFFFu {
+            valid = false;
+        }
+
+        var state_a = atom_a.state;
+        var state_b = atom_b.state;
+        if index == (msb_index / ATOM_BITS) {
+            state_a ^= sign_flip;
+            state_b ^= sign_flip;
+        }
+
+        if !decided && (state_a != state_b) {
+            a_less = state_a < state_b;
+            decided = true;
+        }
+    }
+
+    let select_a = a_less != is_max;
+
+    var state_changed = false;
+    for (var index = 0u; index < atom_count; index++) {
+        var atom = UNDEFINED;
+        if valid && select_a {
+            atom = input_atom_at_width(input_a, component.output_width, index);
+        } else if valid {
+            atom = input_atom_at_width(input_b, component.output_width, index);
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
 fn neg_impl(component: Component) -> bool {
     let c_input = inputs[component.first_input];
 
+    // The absolute value only negates the input if its sign bit is set.
+    var negate = LogicBitState(true, true);
+    if component.kind == COMPONENT_KIND_ABS {
+        let msb_index = c_input.width - 1u;
+        let msb_atom = wire_states[c_input.wire_state_offset + (msb_index / ATOM_BITS)];
+        negate = get_bit_state(msb_atom, msb_index % ATOM_BITS);
+    }
+
     var state_changed = false;
-    var carry = LogicBitState(true, true);
+    var carry = LogicBitState(negate.state, true);
     for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
         let index = bit_index / ATOM_BITS;
 
@@ -172,12 +528,21 @@ fn neg_impl(component: Component) -> bool {
             atom = HIGH_Z;
         }
 
-        let result = logic_add(LOGIC_0, logic_not(atom), carry);
+        if negate.state {
+            atom = logic_not(atom);
+        }
+
+        let result = logic_add(LOGIC_0, atom, carry);
         carry = result.carry;
 
+        var sum = result.sum;
+        if !negate.valid {
+            sum = UNDEFINED;
+        }
+
         let dst = &output_states[component.output_offset_or_first_output + index];
-        if !logic_state_equal(*dst, result.sum) {
-            *dst = result.sum;
+        if !logic_state_equal(*dst, sum) {
+            *dst = sum;
             state_changed = true;
         }
     }
@@ -194,10 +559,287 @@ fn rsh_impl(component: Component) -> bool {
 }
 
 fn hgate_impl(component: Component) -> bool {
+    let c_input = inputs[component.first_input];
+
+    // Reduce every atom of the input individually and accumulate the results,
+    // so the reduction is correct across atom boundaries.
+    var any_zero = false;
+    var any_one = false;
+    var any_invalid = false;
+    var parity = 0u;
+    for (var bit_index = 0u; bit_index < c_input.width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var mask = 0xFFFFFFFFu;
+        if (c_input.width - bit_index) < ATOM_BITS {
+            mask = 0xFFFFFFFFu >> (ATOM_BITS - (c_input.width - bit_index));
+        }
+
+        let atom = wire_states[c_input.wire_state_offset + index];
+        any_zero |= (~atom.state & atom.valid & mask) != 0u;
+        any_one |= (atom.state & atom.valid & mask) != 0u;
+        any_invalid |= (~atom.valid & mask) != 0u;
+        parity ^= countOneBits(atom.state & mask) & 0x1u;
+    }
+
+    var result: LogicStateAtom;
+    switch component.kind {
+        case COMPONENT_KIND_HAND, COMPONENT_KIND_HNAND: {
+            if any_zero {
+                result = LOGIC_0;
+            } else if any_invalid {
+                result = UNDEFINED;
+            } else {
+                result = LOGIC_1;
+            }
+        }
+        case COMPONENT_KIND_HOR, COMPONENT_KIND_HNOR: {
+            if any_one {
+                result = LOGIC_1;
+            } else if any_invalid {
+                result = UNDEFINED;
+            } else {
+                result = LOGIC_0;
+            }
+        }
+        case COMPONENT_KIND_HXOR, COMPONENT_KIND_HXNOR: {
+            if any_invalid {
+                result = UNDEFINED;
+            } else if parity != 0u {
+                result = LOGIC_1;
+            } else {
+                result = LOGIC_0;
+            }
+        }
+        default: {}
+    }
+
+    switch component.kind {
+        case COMPONENT_KIND_HNAND, COMPONENT_KIND_HNOR, COMPONENT_KIND_HXNOR: {
+            result = logic_not(result);
+        }
+        default: {}
+    }
+
+    var state_changed = false;
+    for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        // The result is placed in the first bit, all other bits are not driven.
+        var atom = HIGH_Z;
+        if index == 0u {
+            atom = LogicStateAtom(result.state & 0x1u, result.valid & 0x1u);
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+fn lut_impl(component: Component) -> bool {
+    var table_index = 0u;
+    var index_valid = true;
+    var shift = 0u;
+    for (var input_index = 0u; input_index < component.input_count; input_index++) {
+        let c_input = inputs[component.first_input + input_index];
+
+        // The total input width is less than 32 bits, so every input fits into a single atom.
+        let mask = 0xFFFFFFFFu >> (ATOM_BITS - c_input.width);
+        let atom = wire_states[c_input.wire_state_offset];
+        if (atom.valid & mask) != mask {
+            index_valid = false;
+        }
+
+        table_index |= (atom.state & mask) << shift;
+        shift += c_input.width;
+    }
+
+    let entry_size = (component.output_width + ATOM_BITS - 1u) / ATOM_BITS;
+    let entry_offset = component.memory_offset + (table_index * entry_size);
+
+    var state_changed = false;
+    for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var atom: LogicStateAtom;
+        if index_valid {
+            atom = memory[entry_offset + index];
+        } else {
+            atom = UNDEFINED;
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+fn cmp_const_impl(component: Component) -> bool {
+    let c_input = inputs[component.first_input];
+
+    var any_unknown = false;
+    var any_different = false;
+    for (var bit_index = 0u; bit_index < c_input.width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        var mask = 0xFFFFFFFFu;
+        if (c_input.width - bit_index) < ATOM_BITS {
+            mask = 0xFFFFFFFFu >> (ATOM_BITS - (c_input.width - bit_index));
+        }
+
+        let atom = wire_states[c_input.wire_state_offset + index];
+        let constant = memory[component.memory_offset + index];
+        any_unknown = any_unknown || ((~(atom.valid & constant.valid) & mask) != 0u);
+        any_different = any_different || (((atom.state ^ constant.state) & mask) != 0u);
+    }
+
+    // The result is placed in the first bit, all other bits are not driven.
+    var atom = LogicStateAtom(select(1u, 0u, any_different), 1u);
+    if any_unknown {
+        atom = LogicStateAtom(1u, 0u);
+    }
+
+    let dst = &output_states[component.output_offset_or_first_output];
+    if !logic_state_equal(*dst, atom) {
+        *dst = atom;
+        return true;
+    }
+
     return false;
 }
 
-@compute @workgroup_size(64, 1, 1) 
+fn clock_div_impl(component: Component) -> bool {
+    let last_clock_ptr = &memory[component.memory_offset];
+    let edge_count_ptr = &memory[component.memory_offset + 1u];
+    let clock_out_ptr = &memory[component.memory_offset + 2u];
+    let divisor = memory[component.memory_offset + 3u].state;
+
+    let c_input = inputs[component.first_input];
+    let clock = wire_states[c_input.wire_state_offset];
+    let last_clock = *last_clock_ptr;
+    *last_clock_ptr = clock;
+
+    let clock_high = ((clock.valid & clock.state) & 0x1u) != 0u;
+    let last_clock_low = ((last_clock.valid & ~last_clock.state) & 0x1u) != 0u;
+    if last_clock_low && clock_high {
+        let edge_count = (*edge_count_ptr).state + 1u;
+        if edge_count >= divisor {
+            *edge_count_ptr = LOGIC_0;
+            *clock_out_ptr = logic_not(*clock_out_ptr);
+        } else {
+            *edge_count_ptr = LogicStateAtom(edge_count, 0xFFFFFFFFu);
+        }
+    }
+
+    let clock_out = *clock_out_ptr;
+
+    var state_changed = false;
+    for (var bit_index = 0u; bit_index < component.output_width; bit_index += ATOM_BITS) {
+        let index = bit_index / ATOM_BITS;
+
+        // The clock is placed in the first bit, all other bits are not driven.
+        var atom = HIGH_Z;
+        if index == 0u {
+            atom = LogicStateAtom(clock_out.state & 0x1u, clock_out.valid & 0x1u);
+        }
+
+        let dst = &output_states[component.output_offset_or_first_output + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+fn counter_impl(component: Component) -> bool {
+    let count_output = outputs[component.output_offset_or_first_output];
+    let wrap_output = outputs[component.output_offset_or_first_output + 1u];
+    let count_atom_count = (count_output.width + ATOM_BITS - 1u) / ATOM_BITS;
+
+    let last_clock_ptr = &memory[component.memory_offset];
+    let wrap_ptr = &memory[component.memory_offset + 1u];
+    let count_offset = component.memory_offset + 2u;
+
+    let clock = wire_states[inputs[component.first_input].wire_state_offset];
+    let count_enable = wire_states[inputs[component.first_input + 1u].wire_state_offset];
+    let reset = wire_states[inputs[component.first_input + 2u].wire_state_offset];
+    let last_clock = *last_clock_ptr;
+    *last_clock_ptr = clock;
+
+    let clock_high = ((clock.valid & clock.state) & 0x1u) != 0u;
+    let last_clock_low = ((last_clock.valid & ~last_clock.state) & 0x1u) != 0u;
+    let enable_high = ((count_enable.valid & count_enable.state) & 0x1u) != 0u;
+    let reset_high = ((reset.valid & reset.state) & 0x1u) != 0u;
+    if last_clock_low && clock_high {
+        if reset_high {
+            for (var index = 0u; index < count_atom_count; index++) {
+                memory[count_offset + index] = LOGIC_0;
+            }
+            *wrap_ptr = LOGIC_0;
+        } else if enable_high {
+            // The count only wraps around if the carry makes it through all atoms.
+            var carry = 1u;
+            for (var index = 0u; index < count_atom_count; index++) {
+                var mask = 0xFFFFFFFFu;
+                let remaining = count_output.width - index * ATOM_BITS;
+                if remaining < ATOM_BITS {
+                    mask = 0xFFFFFFFFu >> (ATOM_BITS - remaining);
+                }
+
+                let count = (memory[count_offset + index].state + carry) & mask;
+                if count != 0u {
+                    carry = 0u;
+                }
+                memory[count_offset + index] = LogicStateAtom(count, 0xFFFFFFFFu);
+            }
+            *wrap_ptr = LogicStateAtom(carry, 0xFFFFFFFFu);
+        } else {
+            *wrap_ptr = LOGIC_0;
+        }
+    }
+
+    var state_changed = false;
+    for (var index = 0u; index < count_atom_count; index++) {
+        let atom = memory[count_offset + index];
+
+        let dst = &output_states[count_output.state_offset + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    let wrap_atom_count = (wrap_output.width + ATOM_BITS - 1u) / ATOM_BITS;
+    for (var index = 0u; index < wrap_atom_count; index++) {
+        // The wrap flag is placed in the first bit, all other bits are not driven.
+        var atom = HIGH_Z;
+        if index == 0u {
+            let wrap = *wrap_ptr;
+            atom = LogicStateAtom(wrap.state & 0x1u, wrap.valid & 0x1u);
+        }
+
+        let dst = &output_states[wrap_output.state_offset + index];
+        if !logic_state_equal(*dst, atom) {
+            *dst = atom;
+            state_changed = true;
+        }
+    }
+
+    return state_changed;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, 1, 1) 
 fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let wires_changed = atomicLoad(&list_data.wires_changed);
     let has_conflicts = atomicLoad(&list_data.has_conflicts);
@@ -205,7 +847,10 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
         return;
     }
 
-    let component_index = id.x;
+    if id.x >= arrayLength(&evaluation_order) {
+        return;
+    }
+    let component_index = evaluation_order[id.x];
     if component_index >= arrayLength(&components) {
         return;
     }
@@ -220,15 +865,30 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
         case COMPONENT_KIND_NOT: {
             state_changed = not_impl(component);
         }
-        case COMPONENT_KIND_BUFFER: {
+        case COMPONENT_KIND_BUFFER, COMPONENT_KIND_OE: {
             state_changed = buffer_impl(component);
         }
         case COMPONENT_KIND_ADD, COMPONENT_KIND_SUB: {
             state_changed = add_impl(component);
         }
-        case COMPONENT_KIND_NEG: {
+        case COMPONENT_KIND_ADDFLAGS, COMPONENT_KIND_SUBFLAGS: {
+            state_changed = add_flags_impl(component);
+        }
+        case COMPONENT_KIND_SATADD, COMPONENT_KIND_SATSUB: {
+            state_changed = sat_add_impl(component);
+        }
+        case COMPONENT_KIND_UMIN, COMPONENT_KIND_UMAX, COMPONENT_KIND_SMIN, COMPONENT_KIND_SMAX: {
+            state_changed = min_max_impl(component);
+        }
+        case COMPONENT_KIND_NEG, COMPONENT_KIND_ABS: {
             state_changed = neg_impl(component);
         }
+        case COMPONENT_KIND_ZEXT, COMPONENT_KIND_SEXT: {
+            state_changed = ext_impl(component);
+        }
+        case COMPONENT_KIND_BIN2GRAY, COMPONENT_KIND_GRAY2BIN: {
+            state_changed = gray_impl(component);
+        }
         case COMPONENT_KIND_LSH: {
             state_changed = lsh_impl(component);
         }
@@ -239,6 +899,19 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
              COMPONENT_KIND_HNAND, COMPONENT_KIND_HNOR, COMPONENT_KIND_HXNOR: {
             state_changed = hgate_impl(component);
         }
+        case COMPONENT_KIND_LUT: {
+            state_changed = lut_impl(component);
+        }
+        case COMPONENT_KIND_CMPCONST: {
+            state_changed = cmp_const_impl(component);
+        }
+        case COMPONENT_KIND_CLKDIV: {
+            state_changed = clock_div_impl(component);
+        }
+        case COMPONENT_KIND_COUNTER: {
+            state_changed = counter_impl(component);
+        }
+        case COMPONENT_KIND_NOP: {}
         default: {}
     }
 
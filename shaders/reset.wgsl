@@ -1,17 +1,68 @@
 const RESET_WIRES_CHANGED      = 0x1u;
 const RESET_COMPONENTS_CHANGED = 0x2u;
 
+override reset_changed: u32;
+override detect_conflicts: bool;
+
+struct DispatchArgs {
+    x: u32,
+    y: u32,
+    z: u32,
+}
+
+const WIRE_DISPATCH           = 0u;
+const COMPONENT_DISPATCH      = 1u;
+const FULL_WIRE_DISPATCH      = 2u;
+const FULL_COMPONENT_DISPATCH = 3u;
+
+// The arguments of the indirect wire and component dispatches following this shader,
+// and the arguments that cover all wires and components.
+// Bound separately, because a buffer cannot be written and used for an indirect dispatch
+// by the same dispatch.
+@group(1) @binding(0)
+var<storage, read_write> dispatch_args: array<DispatchArgs, 4>;
+
 @compute @workgroup_size(1, 1, 1) 
 fn main() {
     if (reset_changed & RESET_WIRES_CHANGED) != 0u {
         atomicStore(&list_data.wires_changed, 0u);
+
+        // The wires are only updated in this step if a component changed in the previous one.
+        let components_changed = atomicLoad(&list_data.components_changed);
+        let has_conflicts = atomicLoad(&list_data.has_conflicts);
+        if (components_changed != 0u) && (has_conflicts == 0u) {
+            atomicAdd(&list_data.step_count, 1u);
+        }
     }
     
     if (reset_changed & RESET_COMPONENTS_CHANGED) != 0u {
         atomicStore(&list_data.components_changed, 0u);
     }
 
-    let conflict_list_len = atomicLoad(&list_data.conflict_list_len);
-    let has_conflicts = u32(conflict_list_len > 0u);
-    atomicStore(&list_data.has_conflicts, has_conflicts);
+    if detect_conflicts {
+        let conflict_list_len = atomicLoad(&list_data.conflict_list_len);
+        let has_conflicts = u32(conflict_list_len > 0u);
+        atomicStore(&list_data.has_conflicts, has_conflicts);
+    }
+
+    // Once nothing is left to update the following pass dispatches no workgroups at all,
+    // so the steps submitted after the circuit settled cost next to nothing.
+    let has_conflicts = atomicLoad(&list_data.has_conflicts) != 0u;
+    if (reset_changed & RESET_WIRES_CHANGED) != 0u {
+        let components_changed = atomicLoad(&list_data.components_changed) != 0u;
+        if components_changed && !has_conflicts {
+            dispatch_args[WIRE_DISPATCH] = dispatch_args[FULL_WIRE_DISPATCH];
+        } else {
+            dispatch_args[WIRE_DISPATCH] = DispatchArgs(0u, 1u, 1u);
+        }
+    }
+
+    if (reset_changed & RESET_COMPONENTS_CHANGED) != 0u {
+        let wires_changed = atomicLoad(&list_data.wires_changed) != 0u;
+        if wires_changed && !has_conflicts {
+            dispatch_args[COMPONENT_DISPATCH] = dispatch_args[FULL_COMPONENT_DISPATCH];
+        } else {
+            dispatch_args[COMPONENT_DISPATCH] = DispatchArgs(0u, 1u, 1u);
+        }
+    }
 }
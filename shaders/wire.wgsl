@@ -1,3 +1,5 @@
+override detect_conflicts: bool;
+
 fn mark_conflict(wire_index: u32) {
     let list_index: u32 = atomicAdd(&list_data.conflict_list_len, 1u);
     if list_index < arrayLength(&conflict_list) {
@@ -5,7 +7,51 @@ fn mark_conflict(wire_index: u32) {
     }
 }
 
-@compute @workgroup_size(64, 1, 1) 
+// Reads the bits of a driver that fall into atom `atom_index` of the driven wire.
+// Bits outside of the driver are high-Z.
+fn driver_atom(output_state_offset: u32, width: u32, bit_offset: u32, atom_index: u32) -> LogicStateAtom {
+    let atom_start = atom_index * ATOM_BITS;
+    var atom = HIGH_Z;
+    var mask = 0u;
+    if atom_start >= bit_offset {
+        let driver_bit = atom_start - bit_offset;
+        if driver_bit >= width {
+            return HIGH_Z;
+        }
+
+        let driver_index = driver_bit / ATOM_BITS;
+        let shift = driver_bit % ATOM_BITS;
+        let low = output_states[output_state_offset + driver_index];
+        atom.state = low.state >> shift;
+        atom.valid = low.valid >> shift;
+        if (shift != 0u) && (driver_bit - shift + ATOM_BITS < width) {
+            let high = output_states[output_state_offset + driver_index + 1u];
+            atom.state |= high.state << (ATOM_BITS - shift);
+            atom.valid |= high.valid << (ATOM_BITS - shift);
+        }
+
+        let remaining = width - driver_bit;
+        mask = select(0xFFFFFFFFu, (1u << remaining) - 1u, remaining < ATOM_BITS);
+    } else {
+        let shift = bit_offset - atom_start;
+        if shift >= ATOM_BITS {
+            return HIGH_Z;
+        }
+
+        let low = output_states[output_state_offset];
+        atom.state = low.state << shift;
+        atom.valid = low.valid << shift;
+
+        let end = shift + width;
+        mask = select(0xFFFFFFFFu, (1u << end) - 1u, end < ATOM_BITS) & (0xFFFFFFFFu << shift);
+    }
+
+    atom.state &= mask;
+    atom.valid &= mask;
+    return atom;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE, 1, 1) 
 fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let components_changed = atomicLoad(&list_data.components_changed);
     let has_conflicts = atomicLoad(&list_data.has_conflicts);
@@ -19,18 +65,26 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     }
     let wire = wires[wire_index];
 
+    // A forced wire ignores its drive and drivers until it is released.
+    let forced = wire.forced != 0u;
+
     var new_state: array<LogicStateAtom, MAX_ATOM_COUNT>;
     for (var bit_index = 0u; bit_index < wire.width; bit_index += ATOM_BITS) {
         let index = bit_index / ATOM_BITS;
-        new_state[index] = wire_drives[wire.drive_offset + index];
+        if forced {
+            new_state[index] = wire_forces[wire.drive_offset + index];
+        } else {
+            new_state[index] = wire_drives[wire.drive_offset + index];
+        }
     }
 
     var has_conflict = false;
-    if wire.first_driver_offset != INVALID_INDEX {
-        for (var bit_index = 0u; bit_index < min(wire.width, wire.first_driver_width); bit_index += ATOM_BITS) {
+    if !forced && (wire.first_driver_offset != INVALID_INDEX) {
+        let first_driver_end = min(wire.width, wire.first_driver_bit_offset + wire.first_driver_width);
+        for (var bit_index = wire.first_driver_bit_offset & ~(ATOM_BITS - 1u); bit_index < first_driver_end; bit_index += ATOM_BITS) {
             let index = bit_index / ATOM_BITS;
 
-            let output_state = output_states[wire.first_driver_offset + index];
+            let output_state = driver_atom(wire.first_driver_offset, wire.first_driver_width, wire.first_driver_bit_offset, index);
             let combine_result = combine_state(new_state[index], output_state);
             has_conflict |= combine_result.conflict;
             new_state[index] = combine_result.atom;
@@ -41,10 +95,11 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
             let driver = wire_drivers[next_driver];
             next_driver = driver.next_driver;
 
-            for (var bit_index = 0u; bit_index < min(wire.width, driver.width); bit_index += ATOM_BITS) {
+            let driver_end = min(wire.width, driver.bit_offset + driver.width);
+            for (var bit_index = driver.bit_offset & ~(ATOM_BITS - 1u); bit_index < driver_end; bit_index += ATOM_BITS) {
                 let index = bit_index / ATOM_BITS;
 
-                let output_state = output_states[driver.output_state_offset + index];
+                let output_state = driver_atom(driver.output_state_offset, driver.width, driver.bit_offset, index);
                 let combine_result = combine_state(new_state[index], output_state);
                 has_conflict |= combine_result.conflict;
                 new_state[index] = combine_result.atom;
@@ -56,8 +111,13 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     for (var bit_index = 0u; bit_index < wire.width; bit_index += ATOM_BITS) {
         let index = bit_index / ATOM_BITS;
 
+        // Bits that are not driven by anything take on the value of the pull.
+        var src = new_state[index];
+        let high_z_mask = select(~(src.state | src.valid), 0u, forced);
+        src.state |= wire.pull.state & high_z_mask;
+        src.valid |= wire.pull.valid & high_z_mask;
+
         let dst = &wire_states[wire.state_offset + index];
-        let src = new_state[index];
         if !logic_state_equal(*dst, src) {
             *dst = src;
             state_changed = true;
@@ -66,9 +126,10 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
 
     if state_changed {
         atomicAdd(&list_data.wires_changed, 1u);
+        atomicOr(&changed_wires[wire_index / 32u], 1u << (wire_index % 32u));
     }
 
-    if has_conflict {
+    if detect_conflicts && has_conflict {
         mark_conflict(wire_index);
     }
 }
@@ -3,6 +3,8 @@ use bytemuck::{Pod, Zeroable};
 use std::cmp;
 use std::fmt;
 use std::marker::PhantomData;
+use std::mem;
+use std::slice;
 
 pub trait BufferState {}
 
@@ -18,6 +20,31 @@ impl BufferState for Finalized {}
 #[derive(Debug, Clone)]
 pub enum BufferPushError {
     OutOfMemory,
+    /// The buffer would grow past the largest index or offset representable in 32 bits
+    OffsetSpaceExhausted,
+}
+
+/// Creates a GPU buffer holding `data`, or only `placeholder` if `data` is empty
+fn create_gpu_buffer<T: Pod>(
+    device: &wgpu::Device,
+    label: Option<&str>,
+    data: &[T],
+    placeholder: T,
+) -> wgpu::Buffer {
+    use wgpu::util::{BufferInitDescriptor, DeviceExt};
+    use wgpu::BufferUsages;
+
+    let data = if !data.is_empty() {
+        data
+    } else {
+        slice::from_ref(&placeholder)
+    };
+
+    device.create_buffer_init(&BufferInitDescriptor {
+        label,
+        contents: bytemuck::cast_slice(data),
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+    })
 }
 
 #[repr(transparent)]
@@ -33,7 +60,7 @@ impl<Marker: ?Sized + 'static> Index<Marker> {
     };
 
     #[inline]
-    const fn new(value: u32) -> Option<Self> {
+    pub const fn new(value: u32) -> Option<Self> {
         if value == u32::MAX {
             None
         } else {
@@ -50,7 +77,7 @@ impl<Marker: ?Sized + 'static> Index<Marker> {
     }
 
     #[inline]
-    const fn get(self) -> Option<u32> {
+    pub const fn get(self) -> Option<u32> {
         if self.is_invalid() {
             None
         } else {
@@ -136,6 +163,11 @@ impl<T: Pod + 'static, S: BufferState> Buffer<T, S> {
     pub fn iter_indices(&self) -> impl Iterator<Item = Index<T>> {
         (0..self.len()).map(|index| Index::new(index).unwrap())
     }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
 }
 
 impl<T: fmt::Debug + Pod + 'static, S: BufferState> fmt::Debug for Buffer<T, S> {
@@ -154,6 +186,14 @@ impl<T: Pod + 'static> Buffer<T, Building> {
         }
     }
 
+    #[inline]
+    pub fn from_vec(data: Vec<T>) -> Self {
+        Self {
+            data,
+            state: Building,
+        }
+    }
+
     #[inline]
     pub fn get_mut(&mut self, index: Index<T>) -> Option<&mut T> {
         let index = index.get()? as usize;
@@ -166,29 +206,61 @@ impl<T: Pod + 'static> Buffer<T, Building> {
             .data
             .len()
             .try_into()
-            .map_err(|_| BufferPushError::OutOfMemory)?;
+            .map_err(|_| BufferPushError::OffsetSpaceExhausted)?;
 
-        let index = Index::new(index).ok_or(BufferPushError::OutOfMemory)?;
+        let index = Index::new(index).ok_or(BufferPushError::OffsetSpaceExhausted)?;
+        self.data
+            .try_reserve(1)
+            .map_err(|_| BufferPushError::OutOfMemory)?;
         self.data.push(value);
         Ok(index)
     }
 
-    pub fn build(self, device: &wgpu::Device) -> Buffer<T, Finalized> {
-        use wgpu::util::{BufferInitDescriptor, DeviceExt};
-        use wgpu::BufferUsages;
-
-        let data = if self.data.len() > 0 {
-            self.data.as_slice()
+    /// Removes the element at `index` by moving the last element into its place
+    ///
+    /// The previously last element is afterwards found at `index`.
+    #[cfg(test)]
+    #[inline]
+    pub fn swap_remove(&mut self, index: Index<T>) -> Option<T> {
+        let index = index.get()? as usize;
+        if index < self.data.len() {
+            Some(self.data.swap_remove(index))
         } else {
-            &[T::zeroed()]
-        };
+            None
+        }
+    }
 
+    /// Removes all elements for which `keep` returns `false`, preserving the order of the others
+    ///
+    /// Returns a table that maps every old index to its new one,
+    /// or to [`Index::INVALID`] if the element was removed.
+    #[cfg(test)]
+    pub fn compact<F>(&mut self, mut keep: F) -> Vec<Index<T>>
+    where
+        F: FnMut(Index<T>, &T) -> bool,
+    {
+        let mut remap = Vec::with_capacity(self.data.len());
+        let mut new_len = 0;
+        for index in self.iter_indices() {
+            let value = self.get(index).expect("invalid index");
+            if keep(index, value) {
+                remap.push(Index::new(new_len).expect("invalid index"));
+                new_len += 1;
+            } else {
+                remap.push(Index::INVALID);
+            }
+        }
+
+        let mut remap_iter = remap.iter();
+        self.data
+            .retain(|_| !remap_iter.next().expect("invalid index").is_invalid());
+
+        remap
+    }
+
+    pub fn build(self, device: &wgpu::Device, label: Option<&str>) -> Buffer<T, Finalized> {
         let state = Finalized {
-            gpu_buffer: device.create_buffer_init(&BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(data),
-                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
-            }),
+            gpu_buffer: create_gpu_buffer(device, label, &self.data, T::zeroed()),
             requires_update: false,
         };
 
@@ -206,6 +278,16 @@ impl<T: Pod + 'static> Default for Buffer<T, Building> {
     }
 }
 
+impl<T: Pod + 'static> Clone for Buffer<T, Building> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            state: Building,
+        }
+    }
+}
+
 impl<T: Pod + 'static> Buffer<T, Finalized> {
     #[inline]
     pub fn get_mut(&mut self, index: Index<T>) -> Option<&mut T> {
@@ -214,6 +296,21 @@ impl<T: Pod + 'static> Buffer<T, Finalized> {
         self.data.get_mut(index)
     }
 
+    /// Creates an independent copy of the buffer with its own GPU buffer
+    ///
+    /// The shaders never write to these buffers, so the data on the CPU side is always current.
+    pub fn duplicate(&self, device: &wgpu::Device, label: Option<&str>) -> Self {
+        let state = Finalized {
+            gpu_buffer: create_gpu_buffer(device, label, &self.data, T::zeroed()),
+            requires_update: false,
+        };
+
+        Buffer {
+            data: self.data.clone(),
+            state,
+        }
+    }
+
     #[inline]
     pub fn slice(&self) -> wgpu::BufferSlice<'_> {
         self.state.gpu_buffer.slice(..)
@@ -253,7 +350,7 @@ impl<Marker: ?Sized + 'static> Offset<Marker> {
     }
 
     #[inline]
-    const fn get(self) -> Option<u32> {
+    pub const fn get(self) -> Option<u32> {
         self.0.get()
     }
 }
@@ -310,6 +407,8 @@ unsafe impl<Marker: ?Sized + 'static> Pod for Offset<Marker> {}
 
 pub struct LogicStateBuffer<Marker: ?Sized + 'static, S: BufferState> {
     data: Vec<LogicStateAtom>,
+    /// The contents at build time, restored on reset
+    initial_data: Vec<LogicStateAtom>,
     state: S,
     _marker: PhantomData<&'static Marker>,
 }
@@ -326,6 +425,11 @@ impl<Marker: ?Sized + 'static, S: BufferState> LogicStateBuffer<Marker, S> {
         let end = start + (count as usize);
         self.data.get(start..end)
     }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[LogicStateAtom] {
+        &self.data
+    }
 }
 
 impl<Marker: ?Sized + 'static, S: BufferState> fmt::Debug for LogicStateBuffer<Marker, S> {
@@ -340,6 +444,17 @@ impl<Marker: ?Sized + 'static> LogicStateBuffer<Marker, Building> {
     pub const fn new() -> Self {
         Self {
             data: Vec::new(),
+            initial_data: Vec::new(),
+            state: Building,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn from_vec(data: Vec<LogicStateAtom>) -> Self {
+        Self {
+            data,
+            initial_data: Vec::new(),
             state: Building,
             _marker: PhantomData,
         }
@@ -358,37 +473,32 @@ impl<Marker: ?Sized + 'static> LogicStateBuffer<Marker, Building> {
             .data
             .len()
             .try_into()
-            .map_err(|_| BufferPushError::OutOfMemory)?;
+            .map_err(|_| BufferPushError::OffsetSpaceExhausted)?;
 
         let new_len = offset
             .checked_add(count)
-            .ok_or(BufferPushError::OutOfMemory)?;
+            .ok_or(BufferPushError::OffsetSpaceExhausted)?;
 
-        let offset = Offset::new(offset).ok_or(BufferPushError::OutOfMemory)?;
+        let offset = Offset::new(offset).ok_or(BufferPushError::OffsetSpaceExhausted)?;
+        self.data
+            .try_reserve(count as usize)
+            .map_err(|_| BufferPushError::OutOfMemory)?;
         self.data.resize(new_len as usize, LogicStateAtom::HIGH_Z);
         Ok(offset)
     }
 
-    pub fn build(self, device: &wgpu::Device) -> LogicStateBuffer<Marker, Finalized> {
-        use wgpu::util::{BufferInitDescriptor, DeviceExt};
-        use wgpu::BufferUsages;
-
-        let data = if self.data.len() > 0 {
-            self.data.as_slice()
-        } else {
-            &[LogicStateAtom::HIGH_Z]
-        };
-
+    pub fn build(
+        self,
+        device: &wgpu::Device,
+        label: Option<&str>,
+    ) -> LogicStateBuffer<Marker, Finalized> {
         let state = Finalized {
-            gpu_buffer: device.create_buffer_init(&BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(data),
-                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
-            }),
+            gpu_buffer: create_gpu_buffer(device, label, &self.data, LogicStateAtom::HIGH_Z),
             requires_update: false,
         };
 
         LogicStateBuffer {
+            initial_data: self.data.clone(),
             data: self.data,
             state,
             _marker: PhantomData,
@@ -403,6 +513,18 @@ impl<Marker: ?Sized + 'static> Default for LogicStateBuffer<Marker, Building> {
     }
 }
 
+impl<Marker: ?Sized + 'static> Clone for LogicStateBuffer<Marker, Building> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            initial_data: self.initial_data.clone(),
+            state: Building,
+            _marker: PhantomData,
+        }
+    }
+}
+
 impl<Marker: ?Sized + 'static> LogicStateBuffer<Marker, Finalized> {
     #[inline]
     pub fn get_mut(&mut self, offset: Offset<Marker>, count: u32) -> Option<&mut [LogicStateAtom]> {
@@ -412,12 +534,120 @@ impl<Marker: ?Sized + 'static> LogicStateBuffer<Marker, Finalized> {
         self.data.get_mut(start..end)
     }
 
+    /// The size of the GPU buffer in bytes
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.state.gpu_buffer.size()
+    }
+
+    #[inline]
+    pub fn gpu_buffer(&self) -> &wgpu::Buffer {
+        &self.state.gpu_buffer
+    }
+
     #[inline]
     pub fn reset(&mut self) {
+        self.data.copy_from_slice(&self.initial_data);
+        self.state.requires_update = true;
+    }
+
+    /// Sets every atom to high-Z, regardless of the contents at build time
+    #[inline]
+    pub fn clear(&mut self) {
         self.data.fill(LogicStateAtom::HIGH_Z);
         self.state.requires_update = true;
     }
 
+    /// Sets `count` atoms starting at `offset` to high-Z and writes only those to the GPU buffer
+    ///
+    /// Unlike [`clear`](Self::clear) this keeps whatever the shaders wrote to the rest of the buffer.
+    pub fn clear_range(
+        &mut self,
+        queue: &wgpu::Queue,
+        offset: Offset<Marker>,
+        count: u32,
+    ) -> Option<()> {
+        let start = offset.get()? as usize;
+        let end = start + (count as usize);
+        let atoms = self.data.get_mut(start..end)?;
+        atoms.fill(LogicStateAtom::HIGH_Z);
+
+        let byte_offset = (start * mem::size_of::<LogicStateAtom>()) as u64;
+        queue.write_buffer(
+            &self.state.gpu_buffer,
+            byte_offset,
+            bytemuck::cast_slice(atoms),
+        );
+        Some(())
+    }
+
+    /// Overwrites the atoms starting at `offset` with `atoms`, both in the current contents and
+    /// in the contents restored by [`reset`](Self::reset), and writes only those to the GPU buffer
+    ///
+    /// Like [`clear_range`](Self::clear_range) this keeps whatever the shaders wrote to the rest of the buffer.
+    pub fn write_initial_range(
+        &mut self,
+        queue: &wgpu::Queue,
+        offset: Offset<Marker>,
+        atoms: &[LogicStateAtom],
+    ) -> Option<()> {
+        let start = offset.get()? as usize;
+        let end = start + atoms.len();
+        self.data.get_mut(start..end)?.copy_from_slice(atoms);
+        self.initial_data
+            .get_mut(start..end)?
+            .copy_from_slice(atoms);
+
+        let byte_offset = (start * mem::size_of::<LogicStateAtom>()) as u64;
+        queue.write_buffer(
+            &self.state.gpu_buffer,
+            byte_offset,
+            bytemuck::cast_slice(atoms),
+        );
+        Some(())
+    }
+
+    /// Creates an independent copy of the buffer with its own GPU buffer
+    ///
+    /// Pending changes on the CPU side are carried over as pending changes. Otherwise the
+    /// contents of the GPU buffer are copied using `encoder`, since the shaders may have
+    /// written to it.
+    pub fn duplicate(
+        &self,
+        device: &wgpu::Device,
+        label: Option<&str>,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> Self {
+        let gpu_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label,
+            size: self.state.gpu_buffer.size(),
+            usage: self.state.gpu_buffer.usage(),
+            mapped_at_creation: false,
+        });
+
+        if !self.state.requires_update {
+            encoder.copy_buffer_to_buffer(
+                &self.state.gpu_buffer,
+                0,
+                &gpu_buffer,
+                0,
+                gpu_buffer.size(),
+            );
+        }
+
+        let state = Finalized {
+            gpu_buffer,
+            requires_update: self.state.requires_update,
+        };
+
+        LogicStateBuffer {
+            data: self.data.clone(),
+            initial_data: self.initial_data.clone(),
+            state,
+            _marker: PhantomData,
+        }
+    }
+
     #[inline]
     pub fn slice(&self) -> wgpu::BufferSlice<'_> {
         self.state.gpu_buffer.slice(..)
@@ -451,4 +681,22 @@ impl<Marker: ?Sized + 'static> LogicStateBuffer<Marker, Finalized> {
             staging_buffer,
         );
     }
+
+    #[cfg(feature = "wasm")]
+    #[inline]
+    pub async fn sync_async(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        staging_buffer: &mut Option<wgpu::Buffer>,
+    ) {
+        crate::gpu::read_buffer_async(
+            &self.state.gpu_buffer,
+            &mut self.data,
+            device,
+            queue,
+            staging_buffer,
+        )
+        .await;
+    }
 }
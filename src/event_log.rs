@@ -0,0 +1,276 @@
+use crate::*;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A single call that changed the inputs of a simulation or ran it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Event {
+    /// [`Simulator::set_wire_drive`], also recorded for [`Simulator::set_wire_drive_bits`]
+    SetWireDrive {
+        #[serde(with = "wire_id")]
+        wire: WireId,
+        #[serde(with = "logic_state")]
+        drive: LogicState,
+    },
+    /// [`Simulator::set_wire_drive_range`]
+    SetWireDriveRange {
+        #[serde(with = "wire_id")]
+        wire: WireId,
+        offset: u32,
+        #[serde(with = "logic_state")]
+        drive: LogicState,
+        width: u32,
+    },
+    /// [`Simulator::force_wire`]
+    ForceWire {
+        #[serde(with = "wire_id")]
+        wire: WireId,
+        #[serde(with = "logic_state")]
+        state: LogicState,
+    },
+    /// [`Simulator::release_wire`]
+    ReleaseWire {
+        #[serde(with = "wire_id")]
+        wire: WireId,
+    },
+    /// [`Simulator::run`]
+    Run { max_steps: u64 },
+    /// [`Simulator::reset`]
+    Reset,
+    /// [`Simulator::reset_to_initial`]
+    ResetToInitial,
+    /// [`Simulator::reset_wires`]
+    ResetWires {
+        #[serde(with = "wire_ids")]
+        wires: Vec<WireId>,
+    },
+    /// [`Simulator::set_component_constant`]
+    SetComponentConstant {
+        #[serde(with = "component_id")]
+        component: ComponentId,
+        #[serde(with = "logic_state")]
+        value: LogicState,
+    },
+}
+
+/// The calls made on a simulator while it was recording, in order
+///
+/// Recording starts with [`Simulator::start_recording`]. Replaying the log with
+/// [`Simulator::replay`] on a simulator built from the same circuit, in the state the
+/// recording simulator was in when recording started, reproduces all runs exactly.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EventLog {
+    events: Vec<Event>,
+}
+
+impl EventLog {
+    #[inline]
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    #[inline]
+    pub(crate) fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, index: usize, event: Event) {
+        self.events.insert(index, event);
+    }
+}
+
+mod wire_id {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(wire: &WireId, serializer: S) -> Result<S::Ok, S::Error> {
+        wire.0.get().unwrap_or(u32::MAX).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<WireId, D::Error> {
+        let index = u32::deserialize(deserializer)?;
+        Ok(WireId(Index::new(index).unwrap_or(Index::INVALID)))
+    }
+}
+
+mod component_id {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        component: &ComponentId,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        component.0.get().unwrap_or(u32::MAX).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ComponentId, D::Error> {
+        let index = u32::deserialize(deserializer)?;
+        Ok(ComponentId(Index::new(index).unwrap_or(Index::INVALID)))
+    }
+}
+
+mod wire_ids {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(wires: &[WireId], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(wires.iter().map(|wire| wire.0.get().unwrap_or(u32::MAX)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<WireId>, D::Error> {
+        let indices = Vec::<u32>::deserialize(deserializer)?;
+        Ok(indices
+            .into_iter()
+            .map(|index| WireId(Index::new(index).unwrap_or(Index::INVALID)))
+            .collect())
+    }
+}
+
+/// Stores a state as pairs of (state, valid) bit planes, leaving out the high-Z atoms at the end
+mod logic_state {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(state: &LogicState, serializer: S) -> Result<S::Ok, S::Error> {
+        let atom_count = state
+            .0
+            .iter()
+            .rposition(|atom| (atom.state() != 0) || (atom.valid() != 0))
+            .map_or(0, |index| index + 1);
+
+        serializer.collect_seq(
+            state.0[..atom_count]
+                .iter()
+                .map(|atom| (atom.state(), atom.valid())),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LogicState, D::Error> {
+        let atoms = Vec::<(u32, u32)>::deserialize(deserializer)?;
+
+        let mut state = LogicState::HIGH_Z;
+        if atoms.len() > state.0.len() {
+            return Err(D::Error::invalid_length(
+                atoms.len(),
+                &"at most as many atoms as the widest wire",
+            ));
+        }
+
+        for (dst, (bits, valid)) in state.0.iter_mut().zip(atoms) {
+            *dst = LogicStateAtom::new(bits, valid);
+        }
+
+        Ok(state)
+    }
+}
+
+/// An error produced by [`Simulator::replay`]
+#[derive(Debug, Clone)]
+pub enum ReplayError {
+    InvalidWireId {
+        event_index: usize,
+    },
+    /// A drive range extended past the end of its wire
+    OutOfRange {
+        event_index: usize,
+    },
+    /// A component ID was invalid or referred to a component without a constant
+    InvalidComponent {
+        event_index: usize,
+    },
+}
+
+impl Simulator {
+    #[inline]
+    pub(crate) fn record_event(&mut self, event: Event) {
+        if let Some(event_log) = &mut self.event_log {
+            event_log.push(event);
+        }
+    }
+
+    /// The number of events recorded so far, or 0 if the simulator is not recording
+    #[inline]
+    pub(crate) fn recorded_event_count(&self) -> usize {
+        self.event_log
+            .as_ref()
+            .map_or(0, |event_log| event_log.events.len())
+    }
+
+    /// Records `event` in front of all events recorded since
+    /// [`recorded_event_count`](Self::recorded_event_count) returned `index`
+    #[inline]
+    pub(crate) fn record_event_at(&mut self, index: usize, event: Event) {
+        if let Some(event_log) = &mut self.event_log {
+            event_log.insert(index, event);
+        }
+    }
+
+    /// Starts recording all calls that change the inputs of the simulation or run it
+    ///
+    /// Any log recorded before is discarded.
+    #[inline]
+    pub fn start_recording(&mut self) {
+        self.event_log = Some(EventLog::default());
+    }
+
+    /// Stops recording and returns everything recorded since [`start_recording`](Self::start_recording)
+    ///
+    /// Returns `None` if the simulator was not recording.
+    #[inline]
+    pub fn take_event_log(&mut self) -> Option<EventLog> {
+        self.event_log.take()
+    }
+
+    /// Applies all events of `log` in order and returns the results of the recorded runs
+    ///
+    /// Replaying stops at the first event that does not fit the circuit of this simulator.
+    pub fn replay(&mut self, log: &EventLog) -> Result<Vec<SimulationRunResult>, ReplayError> {
+        let mut run_results = Vec::new();
+
+        for (event_index, event) in log.events.iter().enumerate() {
+            let invalid_wire_id = |_| ReplayError::InvalidWireId { event_index };
+
+            match event {
+                Event::SetWireDrive { wire, drive } => {
+                    self.set_wire_drive(*wire, drive).map_err(invalid_wire_id)?;
+                }
+                Event::SetWireDriveRange {
+                    wire,
+                    offset,
+                    drive,
+                    width,
+                } => {
+                    self.set_wire_drive_range(*wire, *offset, drive, *width)
+                        .map_err(|err| match err {
+                            SetWireDriveRangeError::InvalidWireId => {
+                                ReplayError::InvalidWireId { event_index }
+                            }
+                            SetWireDriveRangeError::OutOfRange => {
+                                ReplayError::OutOfRange { event_index }
+                            }
+                        })?;
+                }
+                Event::ForceWire { wire, state } => {
+                    self.force_wire(*wire, state).map_err(invalid_wire_id)?;
+                }
+                Event::ReleaseWire { wire } => {
+                    self.release_wire(*wire).map_err(invalid_wire_id)?;
+                }
+                Event::Run { max_steps } => run_results.push(self.run(*max_steps)),
+                Event::Reset => self.reset(),
+                Event::ResetToInitial => self.reset_to_initial(),
+                Event::ResetWires { wires } => {
+                    self.reset_wires(wires).map_err(invalid_wire_id)?;
+                }
+                Event::SetComponentConstant { component, value } => {
+                    self.set_component_constant(*component, value)
+                        .map_err(|_| ReplayError::InvalidComponent { event_index })?;
+                }
+            }
+        }
+
+        Ok(run_results)
+    }
+}
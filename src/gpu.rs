@@ -1,8 +1,9 @@
 use crate::*;
 use bytemuck::Pod;
 use std::mem;
+use std::ops::Deref;
 use std::slice;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use wgpu::Buffer;
 use wgpu::*;
 
@@ -10,17 +11,31 @@ use wgpu::*;
 pub enum CreateDeviceError {
     AdapterNotFound,
     DeviceNotSupported,
+    TimestampQueryNotSupported,
 }
 
-async fn create_device() -> Result<(Device, Queue), CreateDeviceError> {
+#[cfg(not(target_arch = "wasm32"))]
+const BACKENDS: Backends = Backends::VULKAN.union(Backends::METAL);
+#[cfg(target_arch = "wasm32")]
+const BACKENDS: Backends = Backends::BROWSER_WEBGPU;
+
+async fn create_device(options: GpuOptions) -> Result<GpuContext, CreateDeviceError> {
     let instance_desc = InstanceDescriptor {
-        backends: Backends::VULKAN | Backends::METAL,
+        backends: BACKENDS,
         ..Default::default()
     };
     let instance = Instance::new(instance_desc);
 
+    create_device_on(&instance, options).await
+}
+
+#[cfg_attr(feature = "tracing-spans", tracing::instrument(skip_all, fields(?options)))]
+async fn create_device_on(
+    instance: &Instance,
+    options: GpuOptions,
+) -> Result<GpuContext, CreateDeviceError> {
     let adapter_opts = RequestAdapterOptions {
-        power_preference: PowerPreference::HighPerformance,
+        power_preference: options.power_preference,
         ..Default::default()
     };
     let adapter = instance
@@ -33,7 +48,6 @@ async fn create_device() -> Result<(Device, Queue), CreateDeviceError> {
         max_bind_groups: 2,
         max_bindings_per_bind_group: 16,
         max_storage_buffers_per_shader_stage: 16,
-        max_push_constant_size: 128,
 
         max_storage_buffer_binding_size: adapter_limits.max_storage_buffer_binding_size,
         max_compute_invocations_per_workgroup: adapter_limits.max_compute_invocations_per_workgroup,
@@ -48,7 +62,8 @@ async fn create_device() -> Result<(Device, Queue), CreateDeviceError> {
 
     let device_desc = DeviceDescriptor {
         required_limits: device_limits,
-        required_features: Features::PUSH_CONSTANTS,
+        // Only needed for GPU timing, which is rejected when building a simulator if missing.
+        required_features: adapter.features() & Features::TIMESTAMP_QUERY,
         ..Default::default()
     };
     let (device, queue) = adapter
@@ -56,49 +71,120 @@ async fn create_device() -> Result<(Device, Queue), CreateDeviceError> {
         .await
         .map_err(|_| CreateDeviceError::DeviceNotSupported)?;
 
-    Ok((device, queue))
+    let shaders = create_shaders(&device, choose_workgroup_size(&device.limits()));
+
+    Ok(GpuContext {
+        device,
+        queue,
+        shaders,
+        pipelines: [OnceLock::new(), OnceLock::new()],
+    })
+}
+
+/// The device together with everything that never changes between simulators
+pub struct GpuContext {
+    pub device: Device,
+    pub queue: Queue,
+    pub shaders: Shaders,
+    /// One set of pipelines for each conflict detection setting, created on first use
+    pipelines: [OnceLock<Pipelines>; 2],
 }
 
-fn device() -> Result<&'static (Device, Queue), CreateDeviceError> {
-    static DEVICE: OnceLock<Result<(Device, Queue), CreateDeviceError>> = OnceLock::new();
+/// The device a simulator runs on
+#[derive(Clone)]
+pub enum GpuContextHandle {
+    /// One of the shared devices
+    Shared(&'static GpuContext),
+    /// A device requested from a user supplied instance, dropped with the last simulator using it
+    Owned(Arc<GpuContext>),
+}
 
-    DEVICE
-        .get_or_init(|| pollster::block_on(create_device()))
+impl Deref for GpuContextHandle {
+    type Target = GpuContext;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Shared(context) => context,
+            Self::Owned(context) => context,
+        }
+    }
+}
+
+/// One shared device for each power preference, created on first use
+static DEVICES: [OnceLock<Result<GpuContext, CreateDeviceError>>; 3] =
+    [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+
+fn shared_device(options: GpuOptions) -> &'static OnceLock<Result<GpuContext, CreateDeviceError>> {
+    &DEVICES[options.power_preference as usize]
+}
+
+fn device(options: GpuOptions) -> Result<&'static GpuContext, CreateDeviceError> {
+    shared_device(options)
+        .get_or_init(|| pollster::block_on(create_device(options)))
         .as_ref()
         .map_err(Clone::clone)
 }
 
-pub fn read_buffer<T: Pod>(
+#[cfg(feature = "wasm")]
+async fn device_async(options: GpuOptions) -> Result<&'static GpuContext, CreateDeviceError> {
+    let device = shared_device(options);
+    if device.get().is_none() {
+        let result = create_device(options).await;
+
+        // Another caller may have initialized the device while we were waiting,
+        // in which case we simply use theirs.
+        let _ = device.set(result);
+    }
+
+    device.get().unwrap().as_ref().map_err(Clone::clone)
+}
+
+pub fn create_staging_buffer(device: &Device, size: u64) -> Buffer {
+    device.create_buffer(&BufferDescriptor {
+        label: None,
+        size,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    })
+}
+
+/// Copies the part of `buffer` that backs `dst` into the staging buffer
+///
+/// The staging buffer is only reallocated if it is too small, so after the first read of
+/// the largest buffer all further reads reuse the same allocation.
+fn copy_to_staging_buffer<'a, T: Pod>(
     buffer: &Buffer,
-    dst: &mut [T],
+    dst: &[T],
     device: &Device,
     queue: &Queue,
-    staging_buffer: &mut Option<Buffer>,
-) {
-    assert!(buffer.size() >= (dst.len() * mem::size_of::<T>()) as u64);
+    staging_buffer: &'a mut Option<Buffer>,
+) -> (&'a Buffer, u64) {
+    let dst_size = mem::size_of_val(dst) as u64;
+    assert!(buffer.size() >= dst_size);
+
+    let copy_size = dst_size
+        .next_multiple_of(COPY_BUFFER_ALIGNMENT)
+        .min(buffer.size());
 
-    if !staging_buffer
+    if staging_buffer
         .as_ref()
-        .is_some_and(|staging_buffer| staging_buffer.size() >= buffer.size())
+        .is_none_or(|staging_buffer| staging_buffer.size() < copy_size)
     {
-        *staging_buffer = Some(device.create_buffer(&BufferDescriptor {
-            label: None,
-            size: buffer.size() * 2,
-            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        }));
+        *staging_buffer = Some(create_staging_buffer(device, copy_size));
     }
 
     let staging_buffer = staging_buffer.as_ref().unwrap();
 
     let mut encoder = device.create_command_encoder(&Default::default());
-    encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, buffer.size());
+    encoder.copy_buffer_to_buffer(buffer, 0, staging_buffer, 0, copy_size);
     queue.submit(Some(encoder.finish()));
 
-    let staging_slice = staging_buffer.slice(..buffer.size());
-    staging_slice.map_async(MapMode::Read, |result| result.unwrap());
-    device.poll(Maintain::wait()).panic_on_timeout();
+    (staging_buffer, copy_size)
+}
 
+fn copy_from_staging_buffer<T: Pod>(dst: &mut [T], staging_buffer: &Buffer, copy_size: u64) {
+    let staging_slice = staging_buffer.slice(..copy_size);
     let staging_view = staging_slice.get_mapped_range();
     let dst: &mut [u8] = bytemuck::cast_slice_mut(dst);
     let src: &[u8] = &staging_view[..dst.len()];
@@ -108,6 +194,107 @@ pub fn read_buffer<T: Pod>(
     staging_buffer.unmap();
 }
 
+#[cfg_attr(feature = "tracing-spans", tracing::instrument(skip_all, fields(len = dst.len())))]
+pub fn read_buffer<T: Pod>(
+    buffer: &Buffer,
+    dst: &mut [T],
+    device: &Device,
+    queue: &Queue,
+    staging_buffer: &mut Option<Buffer>,
+) {
+    if dst.is_empty() {
+        return;
+    }
+
+    let (staging_buffer, copy_size) =
+        copy_to_staging_buffer(buffer, dst, device, queue, staging_buffer);
+
+    let staging_slice = staging_buffer.slice(..copy_size);
+    staging_slice.map_async(MapMode::Read, |result| result.unwrap());
+    device.poll(Maintain::wait()).panic_on_timeout();
+
+    copy_from_staging_buffer(dst, staging_buffer, copy_size);
+}
+
+#[cfg(feature = "wasm")]
+#[derive(Default)]
+struct MapState {
+    done: bool,
+    waker: Option<std::task::Waker>,
+}
+
+#[cfg(feature = "wasm")]
+struct MapFuture<'a> {
+    device: &'a Device,
+    state: std::sync::Arc<std::sync::Mutex<MapState>>,
+}
+
+#[cfg(feature = "wasm")]
+impl std::future::Future for MapFuture<'_> {
+    type Output = ();
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        use std::task::Poll;
+
+        // On the web this is a no-op and the map callback is invoked by the browser,
+        // natively it drives the callback without blocking.
+        self.device.poll(Maintain::Poll);
+
+        let mut state = self.state.lock().unwrap();
+        if state.done {
+            Poll::Ready(())
+        } else {
+            state.waker = Some(cx.waker().clone());
+
+            // Nothing will wake us up natively, so we have to keep polling the device.
+            #[cfg(not(target_arch = "wasm32"))]
+            cx.waker().wake_by_ref();
+
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[cfg_attr(feature = "tracing-spans", tracing::instrument(skip_all, fields(len = dst.len())))]
+pub async fn read_buffer_async<T: Pod>(
+    buffer: &Buffer,
+    dst: &mut [T],
+    device: &Device,
+    queue: &Queue,
+    staging_buffer: &mut Option<Buffer>,
+) {
+    use std::sync::{Arc, Mutex};
+
+    if dst.is_empty() {
+        return;
+    }
+
+    let (staging_buffer, copy_size) =
+        copy_to_staging_buffer(buffer, dst, device, queue, staging_buffer);
+
+    let state = Arc::new(Mutex::new(MapState::default()));
+    let callback_state = Arc::clone(&state);
+
+    let staging_slice = staging_buffer.slice(..copy_size);
+    staging_slice.map_async(MapMode::Read, move |result| {
+        result.unwrap();
+
+        let mut state = callback_state.lock().unwrap();
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+
+    MapFuture { device, state }.await;
+
+    copy_from_staging_buffer(dst, staging_buffer, copy_size);
+}
+
 const BIND_GROUP_ENTRIES: &[BindGroupLayoutEntry] = &[
     BindGroupLayoutEntry {
         binding: 0,
@@ -219,61 +406,488 @@ const BIND_GROUP_ENTRIES: &[BindGroupLayoutEntry] = &[
         },
         count: None,
     },
+    BindGroupLayoutEntry {
+        binding: 11,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: BufferSize::new(mem::size_of::<u32>() as u64),
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 12,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: BufferSize::new(mem::size_of::<LogicStateAtom>() as u64),
+        },
+        count: None,
+    },
+    BindGroupLayoutEntry {
+        binding: 13,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: BufferSize::new(mem::size_of::<Index<Component>>() as u64),
+        },
+        count: None,
+    },
 ];
 
+/// The indirect dispatch arguments written by the reset shader, bound as a group of their own
+const DISPATCH_BIND_GROUP_ENTRIES: &[BindGroupLayoutEntry] = &[BindGroupLayoutEntry {
+    binding: 0,
+    visibility: ShaderStages::COMPUTE,
+    ty: BindingType::Buffer {
+        ty: BufferBindingType::Storage { read_only: false },
+        has_dynamic_offset: false,
+        min_binding_size: BufferSize::new(mem::size_of::<DispatchArgs>() as u64 * 4),
+    },
+    count: None,
+}];
+
 const COMMON_SHADER_SOURCE: &str = include_str!("../shaders/common.wgsl");
 
-macro_rules! include_shader {
+macro_rules! shader_source {
     ($name:literal) => {{
         const SHADER_SOURCE: &str = include_str!(concat!("../shaders/", $name));
-        const FULL_SHADER_SOURCE: &str =
-            const_format::concatcp!(COMMON_SHADER_SOURCE, SHADER_SOURCE);
+        const FULL_SHADER_SOURCE: &str = const_format::concatcp!(
+            "const MAX_WIRE_WIDTH = ",
+            MAX_WIRE_WIDTH,
+            "u;\n",
+            COMMON_SHADER_SOURCE,
+            SHADER_SOURCE,
+        );
 
-        ShaderModuleDescriptor {
-            label: Some($name),
-            source: ShaderSource::Wgsl(FULL_SHADER_SOURCE.into()),
-        }
+        FULL_SHADER_SOURCE
     }};
 }
 
-pub fn create_simulator(builder: SimulatorBuilder) -> Result<Simulator, CreateDeviceError> {
+/// The full WGSL source of every shader, as it is passed to the device
+pub const SHADER_SOURCES: [(&str, &str); 3] = [
+    ("wire.wgsl", shader_source!("wire.wgsl")),
+    ("component.wgsl", shader_source!("component.wgsl")),
+    ("reset.wgsl", shader_source!("reset.wgsl")),
+];
+
+/// The workgroup size used if the device does not report its subgroup size
+const DEFAULT_WORKGROUP_SIZE: u32 = 64;
+
+/// Picks the number of invocations per workgroup of the wire and component shaders
+///
+/// A multiple of the subgroup size keeps every subgroup fully occupied.
+fn choose_workgroup_size(limits: &Limits) -> u32 {
+    let max_workgroup_size = limits
+        .max_compute_workgroup_size_x
+        .min(limits.max_compute_invocations_per_workgroup);
+
+    let workgroup_size = if limits.max_subgroup_size > 0 {
+        DEFAULT_WORKGROUP_SIZE.next_multiple_of(limits.max_subgroup_size)
+    } else {
+        DEFAULT_WORKGROUP_SIZE
+    };
+
+    workgroup_size.min(max_workgroup_size).max(1)
+}
+
+/// Completes a shader source from [`SHADER_SOURCES`] with the device dependent constants
+///
+/// Our version of naga does not support pipeline-overridable constants in
+/// `@workgroup_size`, so the workgroup size is declared as a regular constant instead.
+pub fn full_shader_source(source: &str, workgroup_size: u32) -> String {
+    format!("{source}\nconst WORKGROUP_SIZE = {workgroup_size}u;\n")
+}
+
+/// The shader modules and layouts shared by all pipelines
+pub struct Shaders {
+    pub bind_group_layout: BindGroupLayout,
+    pub pipeline_layout: PipelineLayout,
+    pub dispatch_bind_group_layout: BindGroupLayout,
+    /// Only the reset shader writes the dispatch arguments, the other shaders must not see them
+    pub reset_pipeline_layout: PipelineLayout,
+    pub wire_shader: ShaderModule,
+    pub component_shader: ShaderModule,
+    pub reset_shader: ShaderModule,
+    pub workgroup_size: u32,
+}
+
+fn create_shaders(device: &Device, workgroup_size: u32) -> Shaders {
+    let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: None,
+        entries: BIND_GROUP_ENTRIES,
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let dispatch_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: None,
+        entries: DISPATCH_BIND_GROUP_ENTRIES,
+    });
+
+    let reset_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[&bind_group_layout, &dispatch_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let [wire_shader, component_shader, reset_shader] = SHADER_SOURCES.map(|(name, source)| {
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(name),
+            source: ShaderSource::Wgsl(full_shader_source(source, workgroup_size).into()),
+        })
+    });
+
+    Shaders {
+        bind_group_layout,
+        pipeline_layout,
+        dispatch_bind_group_layout,
+        reset_pipeline_layout,
+        wire_shader,
+        component_shader,
+        reset_shader,
+        workgroup_size,
+    }
+}
+
+/// The compute pipelines shared by all simulators
+pub struct Pipelines {
+    pub wire_pipeline: ComputePipeline,
+    pub component_pipeline: ComputePipeline,
+    pub reset_wires_pipeline: ComputePipeline,
+    pub reset_components_pipeline: ComputePipeline,
+    /// The number of invocations per workgroup of the wire and component pipelines
+    pub workgroup_size: u32,
+}
+
+impl GpuContext {
+    pub fn pipelines(&self, detect_conflicts: bool) -> &Pipelines {
+        self.pipelines[detect_conflicts as usize]
+            .get_or_init(|| create_pipelines(self, detect_conflicts))
+    }
+}
+
+fn create_pipelines(context: &GpuContext, detect_conflicts: bool) -> Pipelines {
+    use std::collections::HashMap;
+
+    let device = &context.device;
+    let shaders = &context.shaders;
+    let detect_conflicts = if detect_conflicts { 1.0 } else { 0.0 };
+
+    let wire_constants = HashMap::from([("detect_conflicts".to_owned(), detect_conflicts)]);
+    let wire_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: None,
+        layout: Some(&shaders.pipeline_layout),
+        module: &shaders.wire_shader,
+        entry_point: "main",
+        compilation_options: PipelineCompilationOptions {
+            constants: &wire_constants,
+            ..Default::default()
+        },
+    });
+
+    let component_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: None,
+        layout: Some(&shaders.pipeline_layout),
+        module: &shaders.component_shader,
+        entry_point: "main",
+        compilation_options: Default::default(),
+    });
+
+    let create_reset_pipeline = |reset_changed: u32| {
+        let constants = HashMap::from([
+            ("reset_changed".to_owned(), reset_changed as f64),
+            ("detect_conflicts".to_owned(), detect_conflicts),
+        ]);
+
+        device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&shaders.reset_pipeline_layout),
+            module: &shaders.reset_shader,
+            entry_point: "main",
+            compilation_options: PipelineCompilationOptions {
+                constants: &constants,
+                ..Default::default()
+            },
+        })
+    };
+
+    let reset_wires_pipeline = create_reset_pipeline(RESET_WIRES_CHANGED);
+    let reset_components_pipeline = create_reset_pipeline(RESET_COMPONENTS_CHANGED);
+
+    Pipelines {
+        wire_pipeline,
+        component_pipeline,
+        reset_wires_pipeline,
+        reset_components_pipeline,
+        workgroup_size: shaders.workgroup_size,
+    }
+}
+
+pub fn create_simulator(
+    builder: SimulatorBuilder,
+    options: GpuOptions,
+) -> Result<Simulator, CreateDeviceError> {
+    let context = device(options)?;
+    create_simulator_on(builder, GpuContextHandle::Shared(context))
+}
+
+/// Creates a simulator on a new device requested from `instance`
+///
+/// The device is only shared with forks of the simulator and is dropped together with the
+/// last of them.
+pub fn create_simulator_with_instance(
+    builder: SimulatorBuilder,
+    instance: &Instance,
+    options: GpuOptions,
+) -> Result<Simulator, CreateDeviceError> {
+    let context = pollster::block_on(create_device_on(instance, options))?;
+    create_simulator_on(builder, GpuContextHandle::Owned(Arc::new(context)))
+}
+
+#[cfg(feature = "wasm")]
+pub async fn create_simulator_async(
+    builder: SimulatorBuilder,
+    options: GpuOptions,
+) -> Result<Simulator, CreateDeviceError> {
+    let context = device_async(options).await?;
+    create_simulator_on(builder, GpuContextHandle::Shared(context))
+}
+
+/// Creates the label of a GPU buffer from the prefix set with [`SimulatorBuilder::set_debug_label`]
+fn buffer_label(prefix: Option<&str>, name: &str) -> Option<String> {
+    prefix.map(|prefix| format!("{prefix}:{name}"))
+}
+
+/// The buffers describing a circuit and its state
+struct SimulatorBuffers {
+    /// The prefix of the labels of all GPU buffers of the simulator
+    debug_label: Option<String>,
+    /// The number of wires the conflict list has room for
+    max_reported_conflicts: u32,
+
+    wire_states: LogicStateBuffer<WireState, Finalized>,
+    wire_drives: LogicStateBuffer<WireBaseDrive, Finalized>,
+    wire_forces: LogicStateBuffer<WireBaseDrive, Finalized>,
+    wire_drivers: crate::buffer::Buffer<WireDriver, Finalized>,
+    wires: crate::buffer::Buffer<Wire, Finalized>,
+
+    output_states: LogicStateBuffer<OutputState, Finalized>,
+    outputs: crate::buffer::Buffer<ComponentOutput, Finalized>,
+    inputs: crate::buffer::Buffer<ComponentInput, Finalized>,
+    memory: LogicStateBuffer<Memory, Finalized>,
+    components: crate::buffer::Buffer<Component, Finalized>,
+    /// The indices of all components in the order the component shader evaluates them
+    evaluation_order: crate::buffer::Buffer<Index<Component>, Finalized>,
+}
+
+#[cfg_attr(
+    feature = "tracing-spans",
+    tracing::instrument(skip_all, fields(label = builder.debug_label.as_deref()))
+)]
+fn create_simulator_on(
+    builder: SimulatorBuilder,
+    context: GpuContextHandle,
+) -> Result<Simulator, CreateDeviceError> {
+    let device = &context.device;
+
+    if builder.enable_gpu_timing && !device.features().contains(Features::TIMESTAMP_QUERY) {
+        return Err(CreateDeviceError::TimestampQueryNotSupported);
+    }
+
+    let prefix = builder.debug_label.as_deref();
+    let label = |name| buffer_label(prefix, name);
+    let buffers = SimulatorBuffers {
+        wire_states: builder
+            .wire_states
+            .build(device, label("wire_states").as_deref()),
+        wire_drives: builder
+            .wire_drives
+            .build(device, label("wire_drives").as_deref()),
+        wire_forces: builder
+            .wire_forces
+            .build(device, label("wire_forces").as_deref()),
+        wire_drivers: builder
+            .wire_drivers
+            .build(device, label("wire_drivers").as_deref()),
+        wires: builder.wires.build(device, label("wires").as_deref()),
+
+        output_states: builder
+            .output_states
+            .build(device, label("output_states").as_deref()),
+        outputs: builder.outputs.build(device, label("outputs").as_deref()),
+        inputs: builder.inputs.build(device, label("inputs").as_deref()),
+        memory: builder.memory.build(device, label("memory").as_deref()),
+        components: builder
+            .components
+            .build(device, label("components").as_deref()),
+        evaluation_order: builder
+            .evaluation_order
+            .build(device, label("evaluation_order").as_deref()),
+
+        debug_label: builder.debug_label.clone(),
+        max_reported_conflicts: builder.max_reported_conflicts,
+    };
+
+    let run_profile = builder.enable_profiling.then(RunProfile::default);
+
+    let mut simulator = create_simulator_from_buffers(
+        context.clone(),
+        buffers,
+        builder.detect_conflicts,
+        run_profile,
+    );
+
+    simulator.has_initial_wire_states = builder.has_initial_wire_states;
+    simulator.strict_unknown = builder.strict_unknown;
+    simulator.initial_wire_states_pending = builder.has_initial_wire_states;
+    simulator.pass_timer = builder
+        .enable_gpu_timing
+        .then(|| PassTimer::new(device, prefix));
+    Ok(simulator)
+}
+
+/// Creates a copy of `simulator` that shares no GPU state with it
+pub fn fork_simulator(simulator: &Simulator) -> Simulator {
+    let device = &simulator.context.device;
+    let queue = &simulator.context.queue;
+
+    let prefix = simulator.debug_label.as_deref();
+    let label = |name| buffer_label(prefix, name);
+    let mut encoder = device.create_command_encoder(&Default::default());
+    let buffers = SimulatorBuffers {
+        wire_states: simulator.wire_states.duplicate(
+            device,
+            label("wire_states").as_deref(),
+            &mut encoder,
+        ),
+        wire_drives: simulator.wire_drives.duplicate(
+            device,
+            label("wire_drives").as_deref(),
+            &mut encoder,
+        ),
+        wire_forces: simulator.wire_forces.duplicate(
+            device,
+            label("wire_forces").as_deref(),
+            &mut encoder,
+        ),
+        wire_drivers: simulator
+            .wire_drivers
+            .duplicate(device, label("wire_drivers").as_deref()),
+        wires: simulator.wires.duplicate(device, label("wires").as_deref()),
+
+        output_states: simulator.output_states.duplicate(
+            device,
+            label("output_states").as_deref(),
+            &mut encoder,
+        ),
+        outputs: simulator
+            .outputs
+            .duplicate(device, label("outputs").as_deref()),
+        inputs: simulator
+            .inputs
+            .duplicate(device, label("inputs").as_deref()),
+        memory: simulator
+            .memory
+            .duplicate(device, label("memory").as_deref(), &mut encoder),
+        components: simulator
+            .components
+            .duplicate(device, label("components").as_deref()),
+        evaluation_order: simulator
+            .evaluation_order
+            .duplicate(device, label("evaluation_order").as_deref()),
+
+        debug_label: simulator.debug_label.clone(),
+        max_reported_conflicts: simulator.max_reported_conflicts,
+    };
+    queue.submit(Some(encoder.finish()));
+
+    let run_profile = simulator
+        .run_profile
+        .as_ref()
+        .map(|_| RunProfile::default());
+
+    let mut fork = create_simulator_from_buffers(
+        simulator.context.clone(),
+        buffers,
+        simulator.detect_conflicts,
+        run_profile,
+    );
+
+    // The states on the CPU side are copied as they are, so they are exactly as outdated as
+    // the ones of the original simulator.
+    fork.wire_states_need_sync = simulator.wire_states_need_sync;
+    fork.has_initial_wire_states = simulator.has_initial_wire_states;
+    fork.strict_unknown = simulator.strict_unknown;
+    fork.initial_wire_states_pending = simulator.initial_wire_states_pending;
+    fork.pass_timer = simulator
+        .pass_timer
+        .as_ref()
+        .map(|_| PassTimer::new(device, prefix));
+    fork
+}
+
+fn create_simulator_from_buffers(
+    context: GpuContextHandle,
+    buffers: SimulatorBuffers,
+    detect_conflicts: bool,
+    run_profile: Option<RunProfile>,
+) -> Simulator {
     use wgpu::util::{BufferInitDescriptor, DeviceExt};
     use wgpu::*;
 
-    let (device, queue) = device()?;
+    let device = &context.device;
+    let shaders = &context.shaders;
+    let pipelines = context.pipelines(detect_conflicts);
+
+    let SimulatorBuffers {
+        debug_label,
+        max_reported_conflicts,
+        wire_states,
+        wire_drives,
+        wire_forces,
+        wire_drivers,
+        wires,
+        output_states,
+        outputs,
+        inputs,
+        memory,
+        components,
+        evaluation_order,
+    } = buffers;
 
     let list_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
-        label: None,
+        label: buffer_label(debug_label.as_deref(), "list_data").as_deref(),
         contents: bytemuck::cast_slice(slice::from_ref(&ListData::zeroed())),
         usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
     });
 
+    // Bindings cannot be empty, so the list always has room for at least one wire.
     let conflict_list_buffer = device.create_buffer(&BufferDescriptor {
-        label: None,
-        size: (256 * mem::size_of::<WireId>()) as u64,
+        label: buffer_label(debug_label.as_deref(), "conflict_list").as_deref(),
+        size: (max_reported_conflicts.max(1) as usize * mem::size_of::<WireId>()) as u64,
         usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
         mapped_at_creation: false,
     });
 
-    let wire_states = builder.wire_states.build(&device);
-    let wire_drives = builder.wire_drives.build(&device);
-    let wire_drivers = builder.wire_drivers.build(&device);
-    let wires = builder.wires.build(&device);
-
-    let output_states = builder.output_states.build(&device);
-    let outputs = builder.outputs.build(&device);
-    let inputs = builder.inputs.build(&device);
-    let memory = builder.memory.build(&device);
-    let components = builder.components.build(&device);
-
-    let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-        label: None,
-        entries: BIND_GROUP_ENTRIES,
+    // One bit per wire, set whenever the state of the wire changes during a run.
+    let changed_wires_buffer = device.create_buffer(&BufferDescriptor {
+        label: buffer_label(debug_label.as_deref(), "changed_wires").as_deref(),
+        size: (wires.len().div_ceil(u32::BITS).max(1) as usize * mem::size_of::<u32>()) as u64,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
     });
 
     let bind_group = device.create_bind_group(&BindGroupDescriptor {
         label: None,
-        layout: &bind_group_layout,
+        layout: &shaders.bind_group_layout,
         entries: &[
             BindGroupEntry {
                 binding: 0,
@@ -319,60 +933,71 @@ pub fn create_simulator(builder: SimulatorBuilder) -> Result<Simulator, CreateDe
                 binding: 10,
                 resource: conflict_list_buffer.as_entire_binding(),
             },
+            BindGroupEntry {
+                binding: 11,
+                resource: changed_wires_buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 12,
+                resource: wire_forces.binding(),
+            },
+            BindGroupEntry {
+                binding: 13,
+                resource: evaluation_order.binding(),
+            },
         ],
     });
 
-    let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-        label: None,
-        bind_group_layouts: &[&bind_group_layout],
-        push_constant_ranges: &[PushConstantRange {
-            stages: ShaderStages::COMPUTE,
-            range: 0..4,
-        }],
-    });
-
-    let wire_shader_desc = include_shader!("wire.wgsl");
-    let wire_shader = device.create_shader_module(wire_shader_desc);
-
-    let wire_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-        label: None,
-        layout: Some(&pipeline_layout),
-        module: &wire_shader,
-        entry_point: "main",
-        compilation_options: Default::default(),
+    let dispatch_workgroups = |item_count: u32| item_count.div_ceil(pipelines.workgroup_size);
+    let dispatch_args = [
+        DispatchArgs::EMPTY,
+        DispatchArgs::EMPTY,
+        DispatchArgs::new(dispatch_workgroups(wires.len())),
+        DispatchArgs::new(dispatch_workgroups(components.len())),
+    ];
+    let dispatch_args_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: buffer_label(debug_label.as_deref(), "dispatch_args").as_deref(),
+        contents: bytemuck::cast_slice(&dispatch_args),
+        usage: BufferUsages::STORAGE | BufferUsages::INDIRECT,
     });
 
-    let component_shader_desc = include_shader!("component.wgsl");
-    let component_shader = device.create_shader_module(component_shader_desc);
-
-    let component_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+    let dispatch_bind_group = device.create_bind_group(&BindGroupDescriptor {
         label: None,
-        layout: Some(&pipeline_layout),
-        module: &component_shader,
-        entry_point: "main",
-        compilation_options: Default::default(),
+        layout: &shaders.dispatch_bind_group_layout,
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: dispatch_args_buffer.as_entire_binding(),
+        }],
     });
 
-    let reset_shader_desc = include_shader!("reset.wgsl");
-    let reset_shader = device.create_shader_module(reset_shader_desc);
+    let output_state_owners = graph::output_state_owners(&outputs, &components);
+    let wire_aliases = graph::wire_aliases(&wires);
 
-    let reset_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-        label: None,
-        layout: Some(&pipeline_layout),
-        module: &reset_shader,
-        entry_point: "main",
-        compilation_options: Default::default(),
-    });
+    // Sized for the largest buffer we ever read back, so reads never have to reallocate it.
+    let staging_buffer_size = [
+        list_data_buffer.size(),
+        conflict_list_buffer.size(),
+        changed_wires_buffer.size(),
+        wire_states.size(),
+        memory.size(),
+    ]
+    .into_iter()
+    .max()
+    .unwrap();
+    let staging_buffer = create_staging_buffer(device, staging_buffer_size);
 
-    Ok(Simulator {
-        device,
-        queue,
+    Simulator {
+        context,
 
         list_data_buffer,
         conflict_list_buffer,
+        max_reported_conflicts,
+        detect_conflicts,
+        changed_wires_buffer,
 
         wire_states,
         wire_drives,
+        wire_forces,
         wire_drivers,
         wires,
 
@@ -381,17 +1006,125 @@ pub fn create_simulator(builder: SimulatorBuilder) -> Result<Simulator, CreateDe
         inputs,
         memory,
         components,
+        evaluation_order,
 
         bind_group,
-        _wire_shader: wire_shader,
-        wire_pipeline,
-        _component_shader: component_shader,
-        component_pipeline,
-        _reset_shader: reset_shader,
-        reset_pipeline,
+        dispatch_args_buffer,
+        dispatch_bind_group,
 
-        staging_buffer: None,
+        staging_buffer: Some(staging_buffer),
         wire_states_need_sync: false,
-        memory_needs_sync: false,
-    })
+        wire_state_snapshot: None,
+        wire_state_reader: None,
+        wire_state_back_buffer: None,
+        changed_wires: Vec::new(),
+        changed_wires_need_sync: false,
+        max_steps_observed: 0,
+        run_profile,
+        debug_label,
+        output_state_owners,
+        wire_aliases,
+        has_initial_wire_states: false,
+        strict_unknown: false,
+        initial_wire_states_pending: false,
+        run_batches_done: Arc::new(AtomicBool::new(true)),
+        pass_timer: None,
+        #[cfg(feature = "event-log")]
+        event_log: None,
+    }
+}
+
+/// The number of timestamps written in every step: the start and end of the wire pass,
+/// followed by the start and end of the component pass
+const TIMESTAMPS_PER_STEP: u32 = 4;
+
+/// The timestamp queries written around the wire and component passes of a run
+pub struct PassTimer {
+    query_set: QuerySet,
+    pub resolve_buffer: Buffer,
+    /// The number of steps whose timestamps have not been read back yet
+    pub pending_steps: u32,
+    pub timings: PassTimings,
+}
+
+impl PassTimer {
+    /// The highest number of steps submitted between two readbacks
+    const MAX_STEPS: u32 = MAX_BATCHES_PER_READBACK * STEPS_PER_BATCH;
+
+    fn new(device: &Device, label_prefix: Option<&str>) -> Self {
+        let query_count = Self::MAX_STEPS * TIMESTAMPS_PER_STEP;
+
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: buffer_label(label_prefix, "pass_timestamps").as_deref(),
+            ty: QueryType::Timestamp,
+            count: query_count,
+        });
+
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: buffer_label(label_prefix, "pass_timestamps").as_deref(),
+            size: query_count as u64 * QUERY_SIZE as u64,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            resolve_buffer,
+            pending_steps: 0,
+            timings: PassTimings::default(),
+        }
+    }
+
+    fn timestamp_writes(&self, first_index: u32) -> ComputePassTimestampWrites<'_> {
+        ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(first_index),
+            end_of_pass_write_index: Some(first_index + 1),
+        }
+    }
+
+    pub fn wire_pass_writes(&self, step: u32) -> ComputePassTimestampWrites<'_> {
+        self.timestamp_writes(step * TIMESTAMPS_PER_STEP)
+    }
+
+    pub fn component_pass_writes(&self, step: u32) -> ComputePassTimestampWrites<'_> {
+        self.timestamp_writes(step * TIMESTAMPS_PER_STEP + 2)
+    }
+
+    /// Copies the timestamps of the first `step_count` steps into the resolve buffer
+    pub fn resolve(&self, encoder: &mut CommandEncoder, step_count: u32) {
+        assert!(step_count <= Self::MAX_STEPS);
+        encoder.resolve_query_set(
+            &self.query_set,
+            0..(step_count * TIMESTAMPS_PER_STEP),
+            &self.resolve_buffer,
+            0,
+        );
+    }
+
+    /// The number of timestamps that have to be read back from the resolve buffer
+    pub fn pending_timestamp_count(&self) -> usize {
+        (self.pending_steps * TIMESTAMPS_PER_STEP) as usize
+    }
+
+    /// Adds the pass durations of the timestamps read back from the resolve buffer
+    ///
+    /// `timestamp_period` is the number of nanoseconds per timestamp tick.
+    pub fn record(&mut self, timestamps: &[u64], timestamp_period: f32) {
+        let mut wire_ticks = 0u64;
+        let mut component_ticks = 0u64;
+        for step in timestamps.chunks_exact(TIMESTAMPS_PER_STEP as usize) {
+            // Timestamps are not guaranteed to be monotonic on all platforms.
+            wire_ticks += step[1].saturating_sub(step[0]);
+            component_ticks += step[3].saturating_sub(step[2]);
+        }
+
+        let to_duration = |ticks: u64| {
+            std::time::Duration::from_nanos((ticks as f64 * timestamp_period as f64) as u64)
+        };
+        self.timings.wire_pass += to_duration(wire_ticks);
+        self.timings.component_pass += to_duration(component_ticks);
+        self.timings.step_count += self.pending_steps as u64;
+        self.pending_steps = 0;
+    }
 }
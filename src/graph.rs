@@ -1,5 +1,6 @@
 use crate::*;
 use pod_enum::pod_enum;
+use std::borrow::Borrow;
 use std::fmt;
 
 trait LinkedListNode: Pod + 'static {
@@ -49,10 +50,10 @@ fn linked_list_push<T: LinkedListNode>(
 }
 
 #[inline]
-fn linked_list_iter<'a, T: LinkedListNode, S: BufferState>(
-    buffer: &'a Buffer<T, S>,
+fn linked_list_iter<T: LinkedListNode, S: BufferState>(
+    buffer: &Buffer<T, S>,
     first_index: Index<T>,
-) -> impl Iterator<Item = &'a T> {
+) -> impl Iterator<Item = &T> {
     struct Iter<'a, T: LinkedListNode, S: BufferState> {
         buffer: &'a Buffer<T, S>,
         current: Index<T>,
@@ -81,6 +82,8 @@ pub struct WireDriver {
     pub width: u32,
     pub output_state_offset: Offset<OutputState>,
     pub next_driver: Index<WireDriver>,
+    /// The bit of the wire that the first bit of the output drives
+    pub bit_offset: u32,
 }
 
 impl_linked_list_node!(WireDriver => next_driver);
@@ -96,7 +99,12 @@ pub struct Wire {
     pub drive_offset: Offset<WireBaseDrive>,
     pub first_driver_width: u32,
     pub first_driver_offset: Offset<OutputState>,
+    pub first_driver_bit_offset: u32,
     pub driver_list: Index<WireDriver>,
+    /// Weak drive applied to all bits that are not driven by anything else
+    pub pull: LogicStateAtom,
+    /// Non-zero if the wire takes on its forced state instead of resolving its drivers
+    pub forced: u32,
 }
 
 impl Wire {
@@ -104,16 +112,19 @@ impl Wire {
         &mut self,
         buffer: &mut Buffer<WireDriver, Building>,
         width: u32,
+        bit_offset: u32,
         output_state_offset: Offset<OutputState>,
-    ) -> Result<(), AddComponentError> {
+    ) -> Result<(), BufferPushError> {
         if self.first_driver_offset == Offset::INVALID {
             self.first_driver_width = width;
             self.first_driver_offset = output_state_offset;
+            self.first_driver_bit_offset = bit_offset;
         } else {
             let new_driver = buffer.push(WireDriver {
                 width,
                 output_state_offset,
                 next_driver: Index::INVALID,
+                bit_offset,
             })?;
 
             linked_list_push(buffer, &mut self.driver_list, new_driver);
@@ -121,6 +132,94 @@ impl Wire {
 
         Ok(())
     }
+
+    /// Iterates the (width, bit offset, output state offset) of all drivers of the wire
+    pub fn drivers<'a, S: BufferState>(
+        &self,
+        wire_drivers: &'a Buffer<WireDriver, S>,
+    ) -> impl Iterator<Item = (u32, u32, Offset<OutputState>)> + 'a {
+        let first_driver = (!self.first_driver_offset.is_invalid()).then_some((
+            self.first_driver_width,
+            self.first_driver_bit_offset,
+            self.first_driver_offset,
+        ));
+
+        first_driver.into_iter().chain(
+            linked_list_iter(wire_drivers, self.driver_list)
+                .map(|driver| (driver.width, driver.bit_offset, driver.output_state_offset)),
+        )
+    }
+}
+
+/// Gives every wire in `group` the combined drivers and pull of all wires in the group
+pub fn merge_wire_drivers(
+    wires: &mut Buffer<Wire, Building>,
+    wire_drivers: &mut Buffer<WireDriver, Building>,
+    group: &[Index<Wire>],
+) -> Result<(), BufferPushError> {
+    let mut drivers = Vec::new();
+    let mut pull = LogicStateAtom::HIGH_Z;
+    for &wire_index in group {
+        let wire = wires.get(wire_index).expect("invalid wire index");
+        if pull == LogicStateAtom::HIGH_Z {
+            pull = wire.pull;
+        }
+
+        drivers.extend(wire.drivers(wire_drivers));
+    }
+
+    let mut merged = Wire {
+        first_driver_offset: Offset::INVALID,
+        driver_list: Index::INVALID,
+        ..*wires.get(group[0]).expect("invalid wire index")
+    };
+    for (width, bit_offset, output_state_offset) in drivers {
+        merged.add_driver(wire_drivers, width, bit_offset, output_state_offset)?;
+    }
+
+    for &wire_index in group {
+        let wire = wires.get_mut(wire_index).expect("invalid wire index");
+        wire.first_driver_width = merged.first_driver_width;
+        wire.first_driver_offset = merged.first_driver_offset;
+        wire.first_driver_bit_offset = merged.first_driver_bit_offset;
+        wire.driver_list = merged.driver_list;
+        wire.pull = pull;
+    }
+
+    Ok(())
+}
+
+/// Makes the outputs driving `slice` also drive the wires in `group`, starting at `bit_offset`
+pub fn add_slice_drivers(
+    wires: &mut Buffer<Wire, Building>,
+    wire_drivers: &mut Buffer<WireDriver, Building>,
+    slice: Index<Wire>,
+    group: &[Index<Wire>],
+    bit_offset: u32,
+) -> Result<(), BufferPushError> {
+    let slice = wires.get(slice).expect("invalid wire index");
+    let drivers: Vec<_> = slice.drivers(wire_drivers).collect();
+
+    // Wires in a group share their driver list, so appending to it once is enough.
+    let mut target = *wires.get(group[0]).expect("invalid wire index");
+    for (width, driver_bit_offset, output_state_offset) in drivers {
+        target.add_driver(
+            wire_drivers,
+            width,
+            bit_offset + driver_bit_offset,
+            output_state_offset,
+        )?;
+    }
+
+    for &wire_index in group {
+        let wire = wires.get_mut(wire_index).expect("invalid wire index");
+        wire.first_driver_width = target.first_driver_width;
+        wire.first_driver_offset = target.first_driver_offset;
+        wire.first_driver_bit_offset = target.first_driver_bit_offset;
+        wire.driver_list = target.driver_list;
+    }
+
+    Ok(())
 }
 
 #[pod_enum]
@@ -157,6 +256,34 @@ pub enum ComponentKind {
     CmpSgt = 27,
     CmpSle = 28,
     CmpSge = 29,
+    Lut = 30,
+    ClockDiv = 31,
+    Nop = 32,
+    SatAdd = 33,
+    SatSub = 34,
+    ZExt = 35,
+    SExt = 36,
+    OE = 37,
+    Bin2Gray = 38,
+    Gray2Bin = 39,
+    Abs = 40,
+    UMin = 41,
+    UMax = 42,
+    SMin = 43,
+    SMax = 44,
+    Counter = 45,
+    AddFlags = 46,
+    SubFlags = 47,
+    CmpConst = 48,
+}
+
+impl ComponentKind {
+    /// Whether the outputs of the component only change in response to a clock edge,
+    /// which breaks combinational paths through it
+    #[inline]
+    pub fn is_sequential(self) -> bool {
+        (self == Self::ClockDiv) || (self == Self::Counter)
+    }
 }
 
 impl Default for ComponentKind {
@@ -212,6 +339,56 @@ pub struct Component {
     pub memory_size: u32,
 }
 
+impl Component {
+    pub fn output_list<'a, S: BufferState>(
+        &self,
+        outputs: &'a Buffer<ComponentOutput, S>,
+    ) -> impl Iterator<Item = ComponentOutput> + 'a {
+        let (first_output, output_count) = match self.output_count {
+            0 => (None, 0),
+            1 => (Some(unsafe { self.output.output }), 0),
+            count => (None, count as u32),
+        };
+
+        let first_output_index = unsafe { self.output.first_output.first_output }
+            .get()
+            .unwrap_or(0);
+        first_output.into_iter().chain(
+            (first_output_index..(first_output_index + output_count)).map(move |index| {
+                *outputs
+                    .get(Index::new(index).unwrap())
+                    .expect("invalid output index")
+            }),
+        )
+    }
+
+    fn input_list<'a, S: BufferState>(
+        &self,
+        inputs: &'a Buffer<ComponentInput, S>,
+    ) -> impl Iterator<Item = ComponentInput> + 'a {
+        let first_input_index = self.first_input.get().unwrap_or(0);
+        (first_input_index..(first_input_index + (self.input_count as u32))).map(move |index| {
+            *inputs
+                .get(Index::new(index).unwrap())
+                .expect("invalid input index")
+        })
+    }
+
+    fn output_state_offsets<'a, S: BufferState>(
+        &self,
+        outputs: &'a Buffer<ComponentOutput, S>,
+    ) -> impl Iterator<Item = Offset<OutputState>> + 'a {
+        self.output_list(outputs).map(|output| output.state_offset)
+    }
+
+    fn input_state_offsets<'a, S: BufferState>(
+        &self,
+        inputs: &'a Buffer<ComponentInput, S>,
+    ) -> impl Iterator<Item = Offset<WireState>> + 'a {
+        self.input_list(inputs).map(|input| input.wire_state_offset)
+    }
+}
+
 impl fmt::Debug for Component {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut debug_struct = f.debug_struct("Component");
@@ -235,6 +412,7 @@ impl fmt::Debug for Component {
 }
 
 pub enum ComponentOutputKind {
+    None,
     Single(ComponentOutput),
     List(Index<ComponentOutput>, u8),
 }
@@ -242,6 +420,13 @@ pub enum ComponentOutputKind {
 pub trait ComponentPorts {
     const COMPONENT_KIND: ComponentKind;
 
+    /// Checks everything about the ports that can make adding the component fail later on,
+    /// before any output is connected to its wire
+    #[inline]
+    fn validate(&self, _wires: &Buffer<Wire, Building>) -> Result<(), AddComponentError> {
+        Ok(())
+    }
+
     fn create_outputs(
         &self,
         wire_drivers: &mut Buffer<WireDriver, Building>,
@@ -258,12 +443,70 @@ pub trait ComponentPorts {
 
     fn create_memory(
         &self,
+        wires: &Buffer<Wire, Building>,
         memory: &mut LogicStateBuffer<Memory, Building>,
     ) -> Result<(Offset<Memory>, u32), AddComponentError>;
 }
 
+/// Allocates the state of an output and connects it to `output` as a driver
+///
+/// `fixed_width` is the width the component requires the output to have, if any.
+fn create_output(
+    wire_drivers: &mut Buffer<WireDriver, Building>,
+    wires: &mut Buffer<Wire, Building>,
+    output_states: &mut LogicStateBuffer<OutputState, Building>,
+    output: WireId,
+    fixed_width: Option<u32>,
+) -> Result<ComponentOutput, AddComponentError> {
+    let output_wire = wires
+        .get_mut(output.0)
+        .ok_or(AddComponentError::InvalidWireId)?;
+
+    if fixed_width.is_some_and(|width| width != output_wire.width) {
+        return Err(AddComponentError::WidthMismatch);
+    }
+
+    let state_width = output_wire.width.div_ceil(LogicStateAtom::BITS);
+    let state_offset = output_states.push(state_width)?;
+    output_wire.add_driver(wire_drivers, output_wire.width, 0, state_offset)?;
+
+    Ok(ComponentOutput {
+        width: output_wire.width,
+        state_offset,
+    })
+}
+
+/// Checks that `output` exists and has the width the component requires, without connecting it
+///
+/// Components with several outputs check all of them before creating the first one,
+/// so a mismatch does not leave drivers behind on the outputs created before it.
+fn check_output(
+    wires: &Buffer<Wire, Building>,
+    output: WireId,
+    fixed_width: Option<u32>,
+) -> Result<(), AddComponentError> {
+    let output_wire = wires
+        .get(output.0)
+        .ok_or(AddComponentError::InvalidWireId)?;
+
+    if fixed_width.is_some_and(|width| width != output_wire.width) {
+        return Err(AddComponentError::WidthMismatch);
+    }
+
+    Ok(())
+}
+
 macro_rules! single_output {
     () => {
+        single_output!(output);
+    };
+    ($output:ident) => {
+        single_output!(@impl $output, None);
+    };
+    ($output:ident: $width:expr) => {
+        single_output!(@impl $output, Some($width));
+    };
+    (@impl $output:ident, $fixed_width:expr) => {
         fn create_outputs(
             &self,
             wire_drivers: &mut Buffer<WireDriver, Building>,
@@ -271,19 +514,13 @@ macro_rules! single_output {
             output_states: &mut LogicStateBuffer<OutputState, Building>,
             _outputs: &mut Buffer<ComponentOutput, Building>,
         ) -> Result<ComponentOutputKind, AddComponentError> {
-            let output_wire = wires
-                .get_mut(self.output.0)
-                .ok_or(AddComponentError::InvalidWireId)?;
-
-            let state_width = output_wire.width.div_ceil(LogicStateAtom::BITS);
-            let state_offset = output_states.push(state_width)?;
-            output_wire.add_driver(wire_drivers, output_wire.width, state_offset)?;
-
-            let output = ComponentOutput {
-                width: output_wire.width,
-                state_offset,
-            };
-
+            let output = create_output(
+                wire_drivers,
+                wires,
+                output_states,
+                self.$output,
+                $fixed_width,
+            )?;
             Ok(ComponentOutputKind::Single(output))
         }
     };
@@ -291,13 +528,16 @@ macro_rules! single_output {
 
 macro_rules! single_input {
     () => {
+        single_input!(input);
+    };
+    ($input:ident) => {
         fn create_inputs(
             &self,
             wires: &Buffer<Wire, Building>,
             inputs: &mut Buffer<ComponentInput, Building>,
         ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
             let input_wire = wires
-                .get(self.input.0)
+                .get(self.$input.0)
                 .ok_or(AddComponentError::InvalidWireId)?;
 
             let input = ComponentInput {
@@ -316,6 +556,7 @@ macro_rules! no_memory {
         #[inline]
         fn create_memory(
             &self,
+            _wires: &Buffer<Wire, Building>,
             _memory: &mut LogicStateBuffer<Memory, Building>,
         ) -> Result<(Offset<Memory>, u32), AddComponentError> {
             Ok((Offset::INVALID, 0))
@@ -323,40 +564,59 @@ macro_rules! no_memory {
     };
 }
 
+fn create_input_list<I>(
+    wires: &Buffer<Wire, Building>,
+    inputs: &mut Buffer<ComponentInput, Building>,
+    input_wires: I,
+) -> Result<(Index<ComponentInput>, u8), AddComponentError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<WireId>,
+{
+    let mut input_count = 0u8;
+    let mut first_input_index = Index::INVALID;
+    for input in input_wires {
+        if (input_count as usize) >= MAX_GATE_INPUTS {
+            return Err(AddComponentError::TooManyInputs);
+        }
+        input_count += 1;
+
+        let input_wire = wires
+            .get(input.borrow().0)
+            .ok_or(AddComponentError::InvalidWireId)?;
+
+        let input = ComponentInput {
+            width: input_wire.width,
+            wire_state_offset: input_wire.state_offset,
+        };
+
+        let input_index = inputs.push(input)?;
+        if first_input_index == Index::INVALID {
+            first_input_index = input_index;
+        }
+    }
+
+    Ok((first_input_index, input_count))
+}
+
 macro_rules! impl_gate_ports {
     ($args:ident => $kind:ident) => {
-        impl ComponentPorts for $args<'_> {
+        impl<I> ComponentPorts for $args<I>
+        where
+            I: IntoIterator + Clone,
+            I::Item: Borrow<WireId>,
+        {
             const COMPONENT_KIND: ComponentKind = ComponentKind::$kind;
 
             single_output!();
 
+            #[inline]
             fn create_inputs(
                 &self,
                 wires: &Buffer<Wire, Building>,
                 inputs: &mut Buffer<ComponentInput, Building>,
             ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
-                let input_count: u8 = self
-                    .inputs
-                    .len()
-                    .try_into()
-                    .map_err(|_| AddComponentError::TooManyInputs)?;
-
-                let mut first_input_index = Index::INVALID;
-                for input in self.inputs {
-                    let input_wire = wires.get(input.0).ok_or(AddComponentError::InvalidWireId)?;
-
-                    let input = ComponentInput {
-                        width: input_wire.width,
-                        wire_state_offset: input_wire.state_offset,
-                    };
-
-                    let input_index = inputs.push(input)?;
-                    if first_input_index == Index::INVALID {
-                        first_input_index = input_index;
-                    }
-                }
-
-                Ok((first_input_index, input_count))
+                create_input_list(wires, inputs, self.inputs.clone())
             }
 
             no_memory!();
@@ -371,12 +631,40 @@ impl_gate_ports!(NandGatePorts => Nand);
 impl_gate_ports!(NorGatePorts => Nor);
 impl_gate_ports!(XnorGatePorts => Xnor);
 
-macro_rules! impl_horizontal_gate_ports {
+macro_rules! impl_binary_gate_ports {
     ($args:ident => $kind:ident) => {
         impl ComponentPorts for $args {
             const COMPONENT_KIND: ComponentKind = ComponentKind::$kind;
 
             single_output!();
+
+            #[inline]
+            fn create_inputs(
+                &self,
+                wires: &Buffer<Wire, Building>,
+                inputs: &mut Buffer<ComponentInput, Building>,
+            ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
+                create_input_list(wires, inputs, [self.input_a, self.input_b])
+            }
+
+            no_memory!();
+        }
+    };
+}
+
+impl_binary_gate_ports!(AndGate2Ports => And);
+impl_binary_gate_ports!(OrGate2Ports => Or);
+impl_binary_gate_ports!(XorGate2Ports => Xor);
+impl_binary_gate_ports!(NandGate2Ports => Nand);
+impl_binary_gate_ports!(NorGate2Ports => Nor);
+impl_binary_gate_ports!(XnorGate2Ports => Xnor);
+
+macro_rules! impl_horizontal_gate_ports {
+    ($args:ident => $kind:ident) => {
+        impl ComponentPorts for $args {
+            const COMPONENT_KIND: ComponentKind = ComponentKind::$kind;
+
+            single_output!(output: 1);
             single_input!();
             no_memory!();
         }
@@ -437,11 +725,11 @@ impl ComponentPorts for BufferPorts {
 }
 
 macro_rules! impl_arithmetic_ports {
-    ($args:ident => $kind:ident) => {
+    ($args:ident => $kind:ident $(, output_width = $width:expr)?) => {
         impl ComponentPorts for $args {
             const COMPONENT_KIND: ComponentKind = ComponentKind::$kind;
 
-            single_output!();
+            single_output!(output $(: $width)?);
 
             fn create_inputs(
                 &self,
@@ -480,19 +768,74 @@ macro_rules! impl_arithmetic_ports {
 
 impl_arithmetic_ports!(AddPorts => Add);
 impl_arithmetic_ports!(SubtractPorts => Sub);
+impl_arithmetic_ports!(SaturatingAddPorts => SatAdd);
+impl_arithmetic_ports!(SaturatingSubtractPorts => SatSub);
 impl_arithmetic_ports!(LeftShiftPorts => Lsh);
 impl_arithmetic_ports!(LogicalRightShiftPorts => LRsh);
 impl_arithmetic_ports!(ArithmeticRightShiftPorts => ARsh);
-impl_arithmetic_ports!(CompareEqual => CmpEq);
-impl_arithmetic_ports!(CompareNotEqual => CmpNe);
-impl_arithmetic_ports!(CompareUnsignedLessThan => CmpUlt);
-impl_arithmetic_ports!(CompareUnsignedGreaterThan => CmpUgt);
-impl_arithmetic_ports!(CompareUnsignedLessThanOrEqual => CmpUle);
-impl_arithmetic_ports!(CompareUnsignedGreaterThanEqual => CmpUge);
-impl_arithmetic_ports!(CompareSignedLessThan => CmpSlt);
-impl_arithmetic_ports!(CompareSignedGreaterThan => CmpSgt);
-impl_arithmetic_ports!(CompareSignedLessThanOrEqual => CmpSle);
-impl_arithmetic_ports!(CompareSignedGreaterThanEqual => CmpSge);
+impl_arithmetic_ports!(CompareEqual => CmpEq, output_width = 1);
+impl_arithmetic_ports!(CompareNotEqual => CmpNe, output_width = 1);
+impl_arithmetic_ports!(CompareUnsignedLessThan => CmpUlt, output_width = 1);
+impl_arithmetic_ports!(CompareUnsignedGreaterThan => CmpUgt, output_width = 1);
+impl_arithmetic_ports!(CompareUnsignedLessThanOrEqual => CmpUle, output_width = 1);
+impl_arithmetic_ports!(CompareUnsignedGreaterThanEqual => CmpUge, output_width = 1);
+impl_arithmetic_ports!(CompareSignedLessThan => CmpSlt, output_width = 1);
+impl_arithmetic_ports!(CompareSignedGreaterThan => CmpSgt, output_width = 1);
+impl_arithmetic_ports!(CompareSignedLessThanOrEqual => CmpSle, output_width = 1);
+impl_arithmetic_ports!(CompareSignedGreaterThanEqual => CmpSge, output_width = 1);
+impl_arithmetic_ports!(MinPorts => UMin);
+impl_arithmetic_ports!(MaxPorts => UMax);
+impl_arithmetic_ports!(SignedMinPorts => SMin);
+impl_arithmetic_ports!(SignedMaxPorts => SMax);
+
+macro_rules! impl_flag_arithmetic_ports {
+    ($args:ident => $kind:ident) => {
+        impl ComponentPorts for $args {
+            const COMPONENT_KIND: ComponentKind = ComponentKind::$kind;
+
+            fn create_outputs(
+                &self,
+                wire_drivers: &mut Buffer<WireDriver, Building>,
+                wires: &mut Buffer<Wire, Building>,
+                output_states: &mut LogicStateBuffer<OutputState, Building>,
+                outputs: &mut Buffer<ComponentOutput, Building>,
+            ) -> Result<ComponentOutputKind, AddComponentError> {
+                check_output(wires, self.output, None)?;
+                for flag in [self.zero, self.negative, self.overflow] {
+                    check_output(wires, flag, Some(1))?;
+                }
+
+                let output = create_output(wire_drivers, wires, output_states, self.output, None)?;
+                let zero = create_output(wire_drivers, wires, output_states, self.zero, Some(1))?;
+                let negative =
+                    create_output(wire_drivers, wires, output_states, self.negative, Some(1))?;
+                let overflow =
+                    create_output(wire_drivers, wires, output_states, self.overflow, Some(1))?;
+
+                let first_output = outputs.push(output)?;
+                outputs.push(zero)?;
+                outputs.push(negative)?;
+                outputs.push(overflow)?;
+
+                Ok(ComponentOutputKind::List(first_output, 4))
+            }
+
+            #[inline]
+            fn create_inputs(
+                &self,
+                wires: &Buffer<Wire, Building>,
+                inputs: &mut Buffer<ComponentInput, Building>,
+            ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
+                create_input_list(wires, inputs, [self.input_lhs, self.input_rhs])
+            }
+
+            no_memory!();
+        }
+    };
+}
+
+impl_flag_arithmetic_ports!(AddWithFlagsPorts => AddFlags);
+impl_flag_arithmetic_ports!(SubtractWithFlagsPorts => SubFlags);
 
 impl ComponentPorts for NegatePorts {
     const COMPONENT_KIND: ComponentKind = ComponentKind::Neg;
@@ -501,3 +844,693 @@ impl ComponentPorts for NegatePorts {
     single_input!();
     no_memory!();
 }
+
+impl ComponentPorts for AbsPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::Abs;
+
+    single_output!();
+    single_input!();
+    no_memory!();
+}
+
+impl ComponentPorts for OutputEnablePorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::OE;
+
+    single_output!();
+
+    #[inline]
+    fn create_inputs(
+        &self,
+        wires: &Buffer<Wire, Building>,
+        inputs: &mut Buffer<ComponentInput, Building>,
+    ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
+        // The inputs are laid out exactly like the ones of a buffer.
+        let buffer = BufferPorts {
+            input: self.source,
+            enable: self.enable,
+            output: self.output,
+        };
+        buffer.create_inputs(wires, inputs)
+    }
+
+    no_memory!();
+}
+
+impl ComponentPorts for ZeroExtendPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::ZExt;
+
+    single_output!();
+    single_input!();
+    no_memory!();
+}
+
+impl ComponentPorts for SignExtendPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::SExt;
+
+    single_output!();
+    single_input!();
+    no_memory!();
+}
+
+impl ComponentPorts for BinaryToGrayPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::Bin2Gray;
+
+    single_output!();
+    single_input!();
+    no_memory!();
+}
+
+impl ComponentPorts for GrayToBinaryPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::Gray2Bin;
+
+    single_output!();
+    single_input!();
+    no_memory!();
+}
+
+impl ComponentPorts for CompareConstPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::CmpConst;
+
+    single_output!(output: 1);
+    single_input!();
+
+    fn create_memory(
+        &self,
+        wires: &Buffer<Wire, Building>,
+        memory: &mut LogicStateBuffer<Memory, Building>,
+    ) -> Result<(Offset<Memory>, u32), AddComponentError> {
+        let input_wire = wires
+            .get(self.input.0)
+            .ok_or(AddComponentError::InvalidWireId)?;
+
+        let memory_size = input_wire.width.div_ceil(LogicStateAtom::BITS);
+        let memory_offset = memory.push(memory_size)?;
+
+        let constant = memory
+            .get_mut(memory_offset, memory_size)
+            .expect("invalid memory offset");
+        constant.copy_from_slice(&self.constant.0[..constant.len()]);
+
+        Ok((memory_offset, memory_size))
+    }
+}
+
+impl ComponentPorts for TruthTablePorts<'_> {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::Lut;
+
+    fn validate(&self, wires: &Buffer<Wire, Building>) -> Result<(), AddComponentError> {
+        let mut total_input_width = 0;
+        for input in self.inputs {
+            let input_wire = wires.get(input.0).ok_or(AddComponentError::InvalidWireId)?;
+            total_input_width += input_wire.width;
+        }
+
+        // The index has to fit into a single atom in the shader.
+        if (total_input_width >= LogicStateAtom::BITS)
+            || (self.table.len() != (1usize << total_input_width))
+        {
+            return Err(AddComponentError::InvalidTableLength);
+        }
+
+        Ok(())
+    }
+
+    single_output!();
+
+    #[inline]
+    fn create_inputs(
+        &self,
+        wires: &Buffer<Wire, Building>,
+        inputs: &mut Buffer<ComponentInput, Building>,
+    ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
+        create_input_list(wires, inputs, self.inputs)
+    }
+
+    fn create_memory(
+        &self,
+        wires: &Buffer<Wire, Building>,
+        memory: &mut LogicStateBuffer<Memory, Building>,
+    ) -> Result<(Offset<Memory>, u32), AddComponentError> {
+        let output_wire = wires
+            .get(self.output.0)
+            .ok_or(AddComponentError::InvalidWireId)?;
+
+        let entry_size = output_wire.width.div_ceil(LogicStateAtom::BITS);
+        let memory_size = entry_size
+            .checked_mul(self.table.len() as u32)
+            .ok_or(AddComponentError::OffsetSpaceExhausted)?;
+        let memory_offset = memory.push(memory_size)?;
+
+        let table = memory
+            .get_mut(memory_offset, memory_size)
+            .expect("invalid memory offset");
+        for (dst, entry) in table.chunks_exact_mut(entry_size as usize).zip(self.table) {
+            dst.copy_from_slice(&entry.0[..dst.len()]);
+        }
+
+        Ok((memory_offset, memory_size))
+    }
+}
+
+impl ComponentPorts for ClockDividerPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::ClockDiv;
+
+    #[inline]
+    fn validate(&self, _wires: &Buffer<Wire, Building>) -> Result<(), AddComponentError> {
+        if self.divisor == 0 {
+            return Err(AddComponentError::InvalidDivisor);
+        }
+
+        Ok(())
+    }
+
+    single_output!(clock_out);
+    single_input!(clock_in);
+
+    fn create_memory(
+        &self,
+        _wires: &Buffer<Wire, Building>,
+        memory: &mut LogicStateBuffer<Memory, Building>,
+    ) -> Result<(Offset<Memory>, u32), AddComponentError> {
+        // Layout: last clock state, edge counter, output state, divisor
+        const MEMORY_SIZE: u32 = 4;
+        let memory_offset = memory.push(MEMORY_SIZE)?;
+
+        let state = memory
+            .get_mut(memory_offset, MEMORY_SIZE)
+            .expect("invalid memory offset");
+        state[1] = LogicStateAtom::from_int(0);
+        state[2] = LogicStateAtom::LOGIC_0;
+        state[3] = LogicStateAtom::from_int(self.divisor);
+
+        Ok((memory_offset, MEMORY_SIZE))
+    }
+}
+
+impl ComponentPorts for CounterPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::Counter;
+
+    fn create_outputs(
+        &self,
+        wire_drivers: &mut Buffer<WireDriver, Building>,
+        wires: &mut Buffer<Wire, Building>,
+        output_states: &mut LogicStateBuffer<OutputState, Building>,
+        outputs: &mut Buffer<ComponentOutput, Building>,
+    ) -> Result<ComponentOutputKind, AddComponentError> {
+        check_output(wires, self.count_out, None)?;
+        check_output(wires, self.wrap_out, Some(1))?;
+
+        let count_out = create_output(wire_drivers, wires, output_states, self.count_out, None)?;
+        let wrap_out = create_output(wire_drivers, wires, output_states, self.wrap_out, Some(1))?;
+
+        let first_output = outputs.push(count_out)?;
+        outputs.push(wrap_out)?;
+
+        Ok(ComponentOutputKind::List(first_output, 2))
+    }
+
+    #[inline]
+    fn create_inputs(
+        &self,
+        wires: &Buffer<Wire, Building>,
+        inputs: &mut Buffer<ComponentInput, Building>,
+    ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
+        create_input_list(wires, inputs, [self.clock, self.enable, self.reset])
+    }
+
+    fn create_memory(
+        &self,
+        wires: &Buffer<Wire, Building>,
+        memory: &mut LogicStateBuffer<Memory, Building>,
+    ) -> Result<(Offset<Memory>, u32), AddComponentError> {
+        let count_width = wires
+            .get(self.count_out.0)
+            .ok_or(AddComponentError::InvalidWireId)?
+            .width;
+
+        // Layout: last clock state, wrap state, count
+        let memory_size = 2 + count_width.div_ceil(LogicStateAtom::BITS);
+        let memory_offset = memory.push(memory_size)?;
+
+        let state = memory
+            .get_mut(memory_offset, memory_size)
+            .expect("invalid memory offset");
+        state[1..].fill(LogicStateAtom::LOGIC_0);
+
+        Ok((memory_offset, memory_size))
+    }
+}
+
+impl ComponentPorts for NopPorts {
+    const COMPONENT_KIND: ComponentKind = ComponentKind::Nop;
+
+    #[inline]
+    fn create_outputs(
+        &self,
+        _wire_drivers: &mut Buffer<WireDriver, Building>,
+        _wires: &mut Buffer<Wire, Building>,
+        _output_states: &mut LogicStateBuffer<OutputState, Building>,
+        _outputs: &mut Buffer<ComponentOutput, Building>,
+    ) -> Result<ComponentOutputKind, AddComponentError> {
+        Ok(ComponentOutputKind::None)
+    }
+
+    #[inline]
+    fn create_inputs(
+        &self,
+        _wires: &Buffer<Wire, Building>,
+        _inputs: &mut Buffer<ComponentInput, Building>,
+    ) -> Result<(Index<ComponentInput>, u8), AddComponentError> {
+        Ok((Index::INVALID, 0))
+    }
+
+    no_memory!();
+}
+
+/// Lists the output state offsets of all components together with the component
+/// they belong to and the index of the output within it, sorted by offset
+pub fn output_state_owners<S: BufferState>(
+    outputs: &Buffer<ComponentOutput, S>,
+    components: &Buffer<Component, S>,
+) -> Vec<(Offset<OutputState>, Index<Component>, u8)> {
+    let mut owners: Vec<_> = components
+        .iter_indices()
+        .flat_map(|component_index| {
+            let component = components.get(component_index).unwrap();
+            component.output_state_offsets(outputs).enumerate().map(
+                move |(output_index, output_state_offset)| {
+                    (output_state_offset, component_index, output_index as u8)
+                },
+            )
+        })
+        .collect();
+
+    owners.sort_by_key(|&(output_state_offset, _, _)| output_state_offset.get());
+    owners
+}
+
+/// Lists the wires that share their drive with an alias, sorted by drive offset
+///
+/// Wires with the same drive offset were made aliases of each other by
+/// [`SimulatorBuilder::alias_wire`], every other wire has a drive of its own.
+pub fn wire_aliases<S: BufferState>(
+    wires: &Buffer<Wire, S>,
+) -> Vec<(Offset<WireBaseDrive>, Index<Wire>)> {
+    let mut drive_owners: Vec<_> = wires
+        .iter_indices()
+        .map(|wire_index| (wires.get(wire_index).unwrap().drive_offset, wire_index))
+        .collect();
+    drive_owners.sort_by_key(|&(drive_offset, _)| drive_offset.get());
+
+    drive_owners
+        .chunk_by(|(a, _), (b, _)| a == b)
+        .filter(|group| group.len() > 1)
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Finds the wires connected to the inputs of `component`
+pub fn component_input_wires<S: BufferState>(
+    wires: &Buffer<Wire, S>,
+    inputs: &Buffer<ComponentInput, S>,
+    component: &Component,
+) -> Vec<WireId> {
+    // Wire states are allocated in the order the wires were added, so the wires are sorted by
+    // their state offset.
+    let wires = wires.as_slice();
+    component
+        .input_list(inputs)
+        .map(|input| {
+            let wire_index =
+                wires.partition_point(|wire| wire.state_offset < input.wire_state_offset);
+            debug_assert_eq!(wires[wire_index].state_offset, input.wire_state_offset);
+            WireId(Index::new(wire_index as u32).unwrap())
+        })
+        .collect()
+}
+
+/// Components whose inputs and output all have the same width
+const UNIFORM_WIDTH_KINDS: &[ComponentKind] = &[
+    ComponentKind::And,
+    ComponentKind::Or,
+    ComponentKind::Xor,
+    ComponentKind::Nand,
+    ComponentKind::Nor,
+    ComponentKind::Xnor,
+    ComponentKind::Not,
+    ComponentKind::Add,
+    ComponentKind::Sub,
+    ComponentKind::AddFlags,
+    ComponentKind::SubFlags,
+    ComponentKind::SatAdd,
+    ComponentKind::SatSub,
+    ComponentKind::UMin,
+    ComponentKind::UMax,
+    ComponentKind::SMin,
+    ComponentKind::SMax,
+    ComponentKind::Neg,
+    ComponentKind::Abs,
+    ComponentKind::Bin2Gray,
+    ComponentKind::Gray2Bin,
+];
+
+const SHIFT_KINDS: &[ComponentKind] =
+    &[ComponentKind::Lsh, ComponentKind::LRsh, ComponentKind::ARsh];
+
+const COMPARISON_KINDS: &[ComponentKind] = &[
+    ComponentKind::CmpEq,
+    ComponentKind::CmpNe,
+    ComponentKind::CmpUlt,
+    ComponentKind::CmpUgt,
+    ComponentKind::CmpUle,
+    ComponentKind::CmpUge,
+    ComponentKind::CmpSlt,
+    ComponentKind::CmpSgt,
+    ComponentKind::CmpSle,
+    ComponentKind::CmpSge,
+];
+
+const HORIZONTAL_KINDS: &[ComponentKind] = &[
+    ComponentKind::HAnd,
+    ComponentKind::HOr,
+    ComponentKind::HXor,
+    ComponentKind::HNand,
+    ComponentKind::HNor,
+    ComponentKind::HXnor,
+];
+
+/// Checks the widths of the ports of all components against the widths their kind requires
+///
+/// Ports whose width is only a convention, like the inputs of extensions and shift amounts,
+/// are not checked.
+pub fn find_width_errors<S: BufferState>(
+    inputs: &Buffer<ComponentInput, S>,
+    outputs: &Buffer<ComponentOutput, S>,
+    components: &Buffer<Component, S>,
+) -> Vec<WidthError> {
+    let mut errors = Vec::new();
+
+    for component_index in components.iter_indices() {
+        let component = components.get(component_index).unwrap();
+        let kind = component.kind;
+        let input_widths: Vec<_> = component
+            .input_list(inputs)
+            .map(|input| input.width)
+            .collect();
+        let output_widths: Vec<_> = component
+            .output_list(outputs)
+            .map(|output| output.width)
+            .collect();
+
+        let mut expect = |port, expected, actual| {
+            if actual != expected {
+                errors.push(WidthError {
+                    component: ComponentId(component_index),
+                    port,
+                    expected,
+                    actual,
+                });
+            }
+        };
+
+        if UNIFORM_WIDTH_KINDS.contains(&kind) {
+            for (i, &width) in input_widths.iter().enumerate() {
+                expect(ComponentPort::Input(i as u8), output_widths[0], width);
+            }
+        } else if SHIFT_KINDS.contains(&kind) {
+            expect(ComponentPort::Input(0), output_widths[0], input_widths[0]);
+        } else if COMPARISON_KINDS.contains(&kind) {
+            expect(ComponentPort::Input(1), input_widths[0], input_widths[1]);
+            expect(ComponentPort::Output(0), 1, output_widths[0]);
+        } else if HORIZONTAL_KINDS.contains(&kind) {
+            expect(ComponentPort::Output(0), 1, output_widths[0]);
+        } else if (kind == ComponentKind::Buffer) || (kind == ComponentKind::OE) {
+            expect(ComponentPort::Input(0), output_widths[0], input_widths[0]);
+            expect(ComponentPort::Input(1), 1, input_widths[1]);
+        } else if kind == ComponentKind::ClockDiv {
+            expect(ComponentPort::Input(0), 1, input_widths[0]);
+        } else if kind == ComponentKind::Counter {
+            for (i, &width) in input_widths.iter().enumerate() {
+                expect(ComponentPort::Input(i as u8), 1, width);
+            }
+            expect(ComponentPort::Output(1), 1, output_widths[1]);
+        }
+    }
+
+    errors
+}
+
+/// Whether `bits` returns a set bit for any of the first `width` bits of `state`
+fn any_bit(state: &[LogicStateAtom], width: u32, bits: impl Fn(LogicStateAtom) -> u32) -> bool {
+    state.iter().enumerate().any(|(i, &atom)| {
+        let remaining_width = width - (i as u32) * LogicStateAtom::BITS;
+        let mask = if remaining_width < LogicStateAtom::BITS {
+            (1 << remaining_width) - 1
+        } else {
+            u32::MAX
+        };
+
+        (bits(atom) & mask) != 0
+    })
+}
+
+/// Finds all components that have an X or Z on an input and an X on an output
+pub fn find_unknown_propagation<S: BufferState>(
+    inputs: &Buffer<ComponentInput, S>,
+    outputs: &Buffer<ComponentOutput, S>,
+    components: &Buffer<Component, S>,
+    wire_states: &LogicStateBuffer<WireState, S>,
+    output_states: &LogicStateBuffer<OutputState, S>,
+) -> Vec<ComponentId> {
+    let atom_count = |width: u32| width.div_ceil(LogicStateAtom::BITS);
+
+    components
+        .iter_indices()
+        .filter(|&component_index| {
+            let component = components.get(component_index).unwrap();
+
+            let has_unknown_input = component.input_list(inputs).any(|input| {
+                let state = wire_states
+                    .get(input.wire_state_offset, atom_count(input.width))
+                    .expect("invalid wire state offset");
+                any_bit(state, input.width, |atom| !atom.valid())
+            });
+
+            has_unknown_input
+                && component.output_list(outputs).any(|output| {
+                    let state = output_states
+                        .get(output.state_offset, atom_count(output.width))
+                        .expect("invalid output state offset");
+                    any_bit(state, output.width, |atom| atom.state() & !atom.valid())
+                })
+        })
+        .map(ComponentId)
+        .collect()
+}
+
+/// Finds all groups of components that form combinational loops
+///
+/// `wire_links` are pairs of (source wire, target wire) where the drivers of the source
+/// also drive the target. Sequential components are treated as the end of a path.
+pub fn find_combinational_cycles<S: BufferState>(
+    wires: &Buffer<Wire, S>,
+    wire_drivers: &Buffer<WireDriver, S>,
+    inputs: &Buffer<ComponentInput, S>,
+    outputs: &Buffer<ComponentOutput, S>,
+    components: &Buffer<Component, S>,
+    wire_links: &[(Index<Wire>, Index<Wire>)],
+) -> Vec<Vec<Index<Component>>> {
+    use std::collections::HashMap;
+
+    // Wires are nodes `0..wire_count`, components are the nodes after them.
+    let wire_count = wires.len() as usize;
+    let mut edges = vec![Vec::new(); wire_count + (components.len() as usize)];
+
+    let mut state_offset_wires = HashMap::new();
+    let mut output_state_wires: HashMap<_, Vec<_>> = HashMap::new();
+    for wire_index in wires.iter_indices() {
+        let wire = wires.get(wire_index).unwrap();
+        let node = wire_index.get().unwrap() as usize;
+        state_offset_wires.insert(wire.state_offset.get(), node);
+
+        for (_, _, output_state_offset) in wire.drivers(wire_drivers) {
+            output_state_wires
+                .entry(output_state_offset.get())
+                .or_default()
+                .push(node);
+        }
+    }
+
+    for &(source, target) in wire_links {
+        let source = source.get().unwrap() as usize;
+        let target = target.get().unwrap() as usize;
+        edges[source].push(target);
+    }
+
+    for component_index in components.iter_indices() {
+        let component = components.get(component_index).unwrap();
+        let node = wire_count + (component_index.get().unwrap() as usize);
+
+        for output_state_offset in component.output_state_offsets(outputs) {
+            if let Some(driven_wires) = output_state_wires.get(&output_state_offset.get()) {
+                edges[node].extend_from_slice(driven_wires);
+            }
+        }
+
+        if !component.kind.is_sequential() {
+            for wire_state_offset in component.input_state_offsets(inputs) {
+                if let Some(&wire_node) = state_offset_wires.get(&wire_state_offset.get()) {
+                    edges[wire_node].push(node);
+                }
+            }
+        }
+    }
+
+    let mut cycles: Vec<Vec<_>> = strongly_connected_components(&edges)
+        .into_iter()
+        .filter(|scc| scc.len() > 1)
+        .map(|scc| {
+            let mut cycle: Vec<_> = scc
+                .into_iter()
+                .filter(|&node| node >= wire_count)
+                .map(|node| Index::new((node - wire_count) as u32).unwrap())
+                .collect();
+            cycle.sort();
+            cycle
+        })
+        .filter(|cycle| !cycle.is_empty())
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// Tarjan's algorithm, iterative to support arbitrarily deep graphs
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut indices = vec![UNVISITED; edges.len()];
+    let mut low_links = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut sccs = Vec::new();
+
+    let mut call_stack: Vec<(usize, usize)> = Vec::new();
+    for root in 0..edges.len() {
+        if indices[root] != UNVISITED {
+            continue;
+        }
+
+        indices[root] = next_index;
+        low_links[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        call_stack.push((root, 0));
+
+        while let Some((node, edge_index)) = call_stack.last_mut() {
+            let node = *node;
+            if let Some(&next) = edges[node].get(*edge_index) {
+                *edge_index += 1;
+
+                if indices[next] == UNVISITED {
+                    indices[next] = next_index;
+                    low_links[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    call_stack.push((next, 0));
+                } else if on_stack[next] {
+                    low_links[node] = low_links[node].min(indices[next]);
+                }
+            } else {
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_links[parent] = low_links[parent].min(low_links[node]);
+                }
+
+                if low_links[node] == indices[node] {
+                    let mut scc = Vec::new();
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        scc.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    sccs.push(scc);
+                }
+            }
+        }
+    }
+
+    sccs
+}
+
+/// 64 bit FNV-1a, which unlike the hasher of the standard library
+/// produces the same hash on every platform and in every release
+pub struct StructuralHasher(u64);
+
+impl Default for StructuralHasher {
+    #[inline]
+    fn default() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for StructuralHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ (byte as u64)).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+}
+
+/// Finds the drivers whose width differs from the width of their wire or their output
+///
+/// Only meaningful before wires are merged, since merging slices moves narrower drivers
+/// onto wider wires.
+pub fn find_driver_width_mismatches<S: BufferState>(
+    wires: &Buffer<Wire, S>,
+    wire_drivers: &Buffer<WireDriver, S>,
+    outputs: &Buffer<ComponentOutput, S>,
+    components: &Buffer<Component, S>,
+) -> Vec<(WireId, ComponentId)> {
+    let mut output_owners: Vec<_> = components
+        .iter_indices()
+        .flat_map(|component_index| {
+            let component = components.get(component_index).unwrap();
+            component
+                .output_list(outputs)
+                .map(move |output| (output.state_offset, output.width, component_index))
+        })
+        .collect();
+    output_owners.sort_by_key(|&(state_offset, _, _)| state_offset.get());
+
+    let mut mismatches = Vec::new();
+    for wire_index in wires.iter_indices() {
+        let wire = wires.get(wire_index).unwrap();
+        for (width, bit_offset, output_state_offset) in wire.drivers(wire_drivers) {
+            // Drivers left behind by components that failed to be added have no owner.
+            let Ok(owner_index) = output_owners
+                .binary_search_by_key(&output_state_offset.get(), |(offset, _, _)| offset.get())
+            else {
+                continue;
+            };
+
+            let (_, output_width, component_index) = output_owners[owner_index];
+            if (width != wire.width) || (width != output_width) || (bit_offset != 0) {
+                mismatches.push((WireId(wire_index), ComponentId(component_index)));
+            }
+        }
+    }
+
+    mismatches
+}
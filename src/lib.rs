@@ -1,7 +1,10 @@
 mod buffer;
+#[cfg(feature = "event-log")]
+mod event_log;
 mod gpu;
 mod graph;
 mod logic;
+mod snapshot;
 mod vec;
 
 #[cfg(test)]
@@ -10,12 +13,18 @@ mod test;
 use buffer::*;
 use bytemuck::{Pod, Zeroable};
 use graph::*;
-use logic::*;
 use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
+#[cfg(feature = "event-log")]
+pub use event_log::{Event, EventLog, ReplayError};
+pub use graph::ComponentKind;
 pub use logic::{
-    FromBigIntError, FromBitsError, LogicBitState, LogicState, ParseError, ToIntError,
+    FromBigIntError, FromBitsError, FromIntError, FromPlanesError, LogicBitState, LogicState,
+    LogicStateAtom, LogicStateDisplay, ParseError, ToIntError,
 };
+pub use snapshot::{CircuitSnapshot, DeserializeError};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Zeroable, Pod)]
 #[repr(transparent)]
@@ -34,49 +43,168 @@ impl ComponentId {
 }
 
 pub const MIN_WIRE_WIDTH: u32 = 1;
+/// The maximum width of a wire in bits
+///
+/// Enabling the `wide-wires` feature raises this limit from 256 to 1024 bits.
+#[cfg(not(feature = "wide-wires"))]
 pub const MAX_WIRE_WIDTH: u32 = (u8::MAX as u32) + 1;
+/// The maximum width of a wire in bits
+///
+/// Enabling the `wide-wires` feature raises this limit from 256 to 1024 bits.
+#[cfg(feature = "wide-wires")]
+pub const MAX_WIRE_WIDTH: u32 = 1024;
+
+/// The number of 32 bit words, or [`LogicStateAtom`]s, that make up a [`LogicState`]
+///
+/// This is the largest word count accepted by [`LogicState::from_big_int`] and the length of
+/// the array returned by [`LogicState::to_words`].
+pub const LOGIC_STATE_WORD_COUNT: usize = (MAX_WIRE_WIDTH / LogicStateAtom::BITS) as usize;
+
+/// The maximum number of inputs a single component can have
+///
+/// The input count of a component is stored as a `u8`,
+/// so wider gates have to be split into multiple components.
+pub const MAX_GATE_INPUTS: usize = u8::MAX as usize;
+
+/// The number of conflicting wires a run reports unless changed with
+/// [`SimulatorBuilder::max_reported_conflicts`]
+pub const DEFAULT_MAX_REPORTED_CONFLICTS: u32 = 256;
+
+/// The largest number of conflicting wires a run can report
+///
+/// The list of conflicting wires has to fit into a single storage buffer binding
+/// of the smallest size every device supports.
+pub const MAX_REPORTED_CONFLICTS_LIMIT: u32 = wgpu::Limits::downlevel_defaults()
+    .max_storage_buffer_binding_size
+    / (std::mem::size_of::<WireId>() as u32);
 
 #[derive(Debug, Clone)]
 pub enum AddWireError {
     WidthOutOfRange,
+    InvalidWireId,
+    /// The slice does not fit into the wire it is part of
+    SliceOutOfRange,
     OutOfMemory,
+    /// The circuit needs more than 2^32 - 1 entries in one of its buffers,
+    /// which is the limit of the 32 bit indices and offsets used on the GPU
+    OffsetSpaceExhausted,
 }
 
 impl From<BufferPushError> for AddWireError {
     fn from(err: BufferPushError) -> Self {
         match err {
             BufferPushError::OutOfMemory => AddWireError::OutOfMemory,
+            BufferPushError::OffsetSpaceExhausted => AddWireError::OffsetSpaceExhausted,
         }
     }
 }
 
 pub type AddWireResult = Result<WireId, AddWireError>;
 
+#[derive(Debug, Clone)]
+pub enum AliasWireError {
+    InvalidWireId,
+    WidthMismatch,
+}
+
 #[derive(Debug, Clone)]
 pub struct InvalidWireIdError;
 
+#[derive(Debug, Clone)]
+pub struct InvalidComponentIdError;
+
+/// The evaluation order is not a permutation of all components added so far
+#[derive(Debug, Clone)]
+pub struct InvalidEvaluationOrderError;
+
+/// The requested number of reported conflicts exceeds [`MAX_REPORTED_CONFLICTS_LIMIT`]
+#[derive(Debug, Clone)]
+pub struct TooManyReportedConflictsError;
+
+#[derive(Debug, Clone)]
+pub enum SetWireDriveBitsError {
+    InvalidWireId,
+    /// The number of bits does not match the width of the wire
+    WidthMismatch,
+}
+
+#[derive(Debug, Clone)]
+pub enum SetWireDriveRangeError {
+    InvalidWireId,
+    /// The range extends past the end of the wire
+    OutOfRange,
+}
+
+#[derive(Debug, Clone)]
+pub enum SetComponentConstantError {
+    InvalidComponentId,
+    /// The component has no constant that could be changed
+    NoConstant,
+}
+
 #[derive(Debug, Clone)]
 pub enum AddComponentError {
     InvalidWireId,
+    /// The component has more than [`MAX_GATE_INPUTS`] inputs
     TooManyInputs,
+    InvalidTableLength,
+    InvalidDivisor,
+    /// An output wire does not have the width the component requires, e.g. 1 bit for comparisons
+    WidthMismatch,
     OutOfMemory,
+    /// The circuit needs more than 2^32 - 1 entries in one of its buffers,
+    /// which is the limit of the 32 bit indices and offsets used on the GPU
+    OffsetSpaceExhausted,
 }
 
 impl From<BufferPushError> for AddComponentError {
     fn from(err: BufferPushError) -> Self {
         match err {
             BufferPushError::OutOfMemory => AddComponentError::OutOfMemory,
+            BufferPushError::OffsetSpaceExhausted => AddComponentError::OffsetSpaceExhausted,
         }
     }
 }
 
 pub type AddComponentResult = Result<ComponentId, AddComponentError>;
 
+/// What [`SimulatorBuilder::add_component_detailed`] created for a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddedComponent {
+    pub id: ComponentId,
+    /// The offset of the state of the first output in the output state buffer,
+    /// or `None` if the component has no outputs
+    ///
+    /// The states of all outputs of a component are allocated consecutively.
+    pub output_state_offset: Option<u32>,
+    pub output_count: u8,
+    pub input_count: u8,
+}
+
+/// A port of a component, identified by its position in the component's inputs or outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentPort {
+    Input(u8),
+    Output(u8),
+}
+
+/// A port whose width does not fit the component, found by [`SimulatorBuilder::check_widths`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WidthError {
+    pub component: ComponentId,
+    pub port: ComponentPort,
+    /// The width required by the component or its other ports
+    pub expected: u32,
+    pub actual: u32,
+}
+
 macro_rules! gate_ports {
     ($ports:ident) => {
+        /// `inputs` can be anything that yields wire IDs, e.g. a slice or an iterator adapter,
+        /// so wide gates can be built without collecting their inputs first
         #[derive(Debug, Clone)]
-        pub struct $ports<'a> {
-            pub inputs: &'a [WireId],
+        pub struct $ports<I> {
+            pub inputs: I,
             pub output: WireId,
         }
     };
@@ -89,8 +217,28 @@ gate_ports!(NandGatePorts);
 gate_ports!(NorGatePorts);
 gate_ports!(XnorGatePorts);
 
-macro_rules! horizontal_gate_ports {
+macro_rules! binary_gate_ports {
     ($ports:ident) => {
+        /// A gate with exactly two inputs
+        #[derive(Debug, Clone)]
+        pub struct $ports {
+            pub input_a: WireId,
+            pub input_b: WireId,
+            pub output: WireId,
+        }
+    };
+}
+
+binary_gate_ports!(AndGate2Ports);
+binary_gate_ports!(OrGate2Ports);
+binary_gate_ports!(XorGate2Ports);
+binary_gate_ports!(NandGate2Ports);
+binary_gate_ports!(NorGate2Ports);
+binary_gate_ports!(XnorGate2Ports);
+
+macro_rules! horizontal_gate_ports {
+    ($(#[$attr:meta])* $ports:ident) => {
+        $(#[$attr])*
         #[derive(Debug, Clone)]
         pub struct $ports {
             pub input: WireId,
@@ -101,10 +249,22 @@ macro_rules! horizontal_gate_ports {
 
 horizontal_gate_ports!(HorizontalAndGatePorts);
 horizontal_gate_ports!(HorizontalOrGatePorts);
-horizontal_gate_ports!(HorizontalXorGatePorts);
+horizontal_gate_ports!(
+    /// Computes the parity of the input
+    ///
+    /// The output is 1 if an odd number of input bits is 1, which makes it the bit that gives
+    /// the input and itself even parity. If any input bit is X or Z the output is X.
+    HorizontalXorGatePorts
+);
 horizontal_gate_ports!(HorizontalNandGatePorts);
 horizontal_gate_ports!(HorizontalNorGatePorts);
-horizontal_gate_ports!(HorizontalXnorGatePorts);
+horizontal_gate_ports!(
+    /// Computes exactly the inverse of [`HorizontalXorGatePorts`]
+    ///
+    /// The output is 1 if an even number of input bits is 1, which makes it the bit that gives
+    /// the input and itself odd parity. If any input bit is X or Z the output is X.
+    HorizontalXnorGatePorts
+);
 
 #[derive(Debug, Clone)]
 pub struct NotGatePorts {
@@ -119,8 +279,21 @@ pub struct BufferPorts {
     pub output: WireId,
 }
 
+/// Drives `output` with `source` while `enable` is high and leaves it undriven otherwise
+///
+/// Connect the result of any component to `source` to give it a tri-state output,
+/// so multiple components can share one bus. If `enable` is X the output is X,
+/// if it is Z the output is not driven.
+#[derive(Debug, Clone)]
+pub struct OutputEnablePorts {
+    pub source: WireId,
+    pub enable: WireId,
+    pub output: WireId,
+}
+
 macro_rules! arithmetic_ports {
-    ($ports:ident) => {
+    ($(#[$attr:meta])* $ports:ident) => {
+        $(#[$attr])*
         #[derive(Debug, Clone)]
         pub struct $ports {
             pub input_lhs: WireId,
@@ -132,6 +305,19 @@ macro_rules! arithmetic_ports {
 
 arithmetic_ports!(AddPorts);
 arithmetic_ports!(SubtractPorts);
+arithmetic_ports!(
+    /// Adds both inputs, clamping the result to the largest value the output can hold
+    /// instead of wrapping around
+    ///
+    /// If any input bit is X or Z the output is X.
+    SaturatingAddPorts
+);
+arithmetic_ports!(
+    /// Subtracts `input_rhs` from `input_lhs`, clamping the result to 0 instead of wrapping around
+    ///
+    /// If any input bit is X or Z the output is X.
+    SaturatingSubtractPorts
+);
 arithmetic_ports!(LeftShiftPorts);
 arithmetic_ports!(LogicalRightShiftPorts);
 arithmetic_ports!(ArithmeticRightShiftPorts);
@@ -145,6 +331,30 @@ arithmetic_ports!(CompareSignedLessThan);
 arithmetic_ports!(CompareSignedGreaterThan);
 arithmetic_ports!(CompareSignedLessThanOrEqual);
 arithmetic_ports!(CompareSignedGreaterThanEqual);
+arithmetic_ports!(
+    /// Outputs the smaller of both inputs, interpreted as unsigned numbers
+    ///
+    /// If any input bit is X or Z the output is X.
+    MinPorts
+);
+arithmetic_ports!(
+    /// Outputs the larger of both inputs, interpreted as unsigned numbers
+    ///
+    /// If any input bit is X or Z the output is X.
+    MaxPorts
+);
+arithmetic_ports!(
+    /// Outputs the smaller of both inputs, interpreted as two's complement signed numbers
+    ///
+    /// If any input bit is X or Z the output is X.
+    SignedMinPorts
+);
+arithmetic_ports!(
+    /// Outputs the larger of both inputs, interpreted as two's complement signed numbers
+    ///
+    /// If any input bit is X or Z the output is X.
+    SignedMaxPorts
+);
 
 #[derive(Debug, Clone)]
 pub struct NegatePorts {
@@ -152,9 +362,157 @@ pub struct NegatePorts {
     pub output: WireId,
 }
 
+/// Outputs the magnitude of the input, interpreted as a two's complement signed number
+///
+/// The most negative value has no positive counterpart and is output unchanged.
+/// If the sign bit is X or Z the output is X.
+#[derive(Debug, Clone)]
+pub struct AbsPorts {
+    pub input: WireId,
+    pub output: WireId,
+}
+
+/// Widens the input by filling all bits past it with 0
+///
+/// If the output is narrower than the input, the input is truncated instead.
+#[derive(Debug, Clone)]
+pub struct ZeroExtendPorts {
+    pub input: WireId,
+    pub output: WireId,
+}
+
+/// Widens the input by filling all bits past it with the most significant bit of the input
+///
+/// An X or Z in the most significant bit is replicated as well.
+/// If the output is narrower than the input, the input is truncated instead.
+#[derive(Debug, Clone)]
+pub struct SignExtendPorts {
+    pub input: WireId,
+    pub output: WireId,
+}
+
+/// Converts a binary number into gray code, so consecutive values differ in exactly one bit
+///
+/// The output should be as wide as the input. An X or Z input bit makes the two
+/// output bits depending on it undefined.
+#[derive(Debug, Clone)]
+pub struct BinaryToGrayPorts {
+    pub input: WireId,
+    pub output: WireId,
+}
+
+/// Converts gray code back into a binary number
+///
+/// The output should be as wide as the input. Every output bit depends on all input bits
+/// at and above it, so an X or Z input bit makes all output bits below and including it undefined.
+#[derive(Debug, Clone)]
+pub struct GrayToBinaryPorts {
+    pub input: WireId,
+    pub output: WireId,
+}
+
+/// Compares `input` against a constant baked into the component
+///
+/// `output` must be 1 bit wide. It is 1 if the first `input.width` bits of `constant` equal
+/// the input and 0 otherwise. If any of these bits is X or Z, in the input or in the constant,
+/// the output is X.
+#[derive(Debug, Clone)]
+pub struct CompareConstPorts {
+    pub input: WireId,
+    pub constant: LogicState,
+    pub output: WireId,
+}
+
+/// A lookup table with an arbitrary number of inputs
+///
+/// The inputs are concatenated, with the first input forming the least significant bits,
+/// and used as an index into `table`, which must contain exactly `2^total_input_width` entries.
+/// If any input bit is X or Z the output is X.
+#[derive(Debug, Clone)]
+pub struct TruthTablePorts<'a> {
+    pub inputs: &'a [WireId],
+    pub output: WireId,
+    pub table: &'a [LogicState],
+}
+
+/// A weak driver that pulls all bits of `wire` that are not driven otherwise to `value`
+///
+/// A pull never conflicts with other drivers. Pulling to [`LogicBitState::HighZ`] removes the pull.
+#[derive(Debug, Clone)]
+pub struct PullPorts {
+    pub wire: WireId,
+    pub value: LogicBitState,
+}
+
+/// Toggles `clock_out` every `divisor` rising edges of `clock_in`
+///
+/// Only the first bit of `clock_in` is considered and the result is placed in the first bit of
+/// `clock_out`, which starts out low. `divisor` must not be 0.
+#[derive(Debug, Clone)]
+pub struct ClockDividerPorts {
+    pub clock_in: WireId,
+    pub clock_out: WireId,
+    pub divisor: u32,
+}
+
+macro_rules! flag_arithmetic_ports {
+    ($(#[$attr:meta])* $ports:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone)]
+        pub struct $ports {
+            pub input_lhs: WireId,
+            pub input_rhs: WireId,
+            pub output: WireId,
+            /// High if all bits of the result are 0
+            pub zero: WireId,
+            /// The most significant bit of the result
+            pub negative: WireId,
+            /// High if the result, interpreted as a signed number, does not fit into `output`
+            pub overflow: WireId,
+        }
+    };
+}
+
+flag_arithmetic_ports!(
+    /// Like [`AddPorts`], but also outputs the condition flags of the result
+    ///
+    /// The flags must be 1 bit wide. If any bit they depend on is X or Z, they are X.
+    AddWithFlagsPorts
+);
+flag_arithmetic_ports!(
+    /// Like [`SubtractPorts`], but also outputs the condition flags of the result
+    ///
+    /// The flags must be 1 bit wide. If any bit they depend on is X or Z, they are X.
+    SubtractWithFlagsPorts
+);
+
+/// Counts the rising edges of `clock` while `enable` is high
+///
+/// The count has the width of `count_out` and starts out at 0. When it wraps around to 0,
+/// `wrap_out` goes high until the next rising edge. If `reset` is high at a rising edge,
+/// the count and `wrap_out` are cleared instead. Only the first bit of `clock`, `enable` and
+/// `reset` is considered, X and Z count as low. Only the first bit of `wrap_out` is driven.
+#[derive(Debug, Clone)]
+pub struct CounterPorts {
+    pub clock: WireId,
+    pub enable: WireId,
+    pub reset: WireId,
+    pub count_out: WireId,
+    pub wrap_out: WireId,
+}
+
+/// A component without any ports that does nothing
+///
+/// Useful as a placeholder for a removed component, so the remaining components keep their IDs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NopPorts;
+
 /// The result of running a simulation
+///
+/// More outcomes may be reported in the future, so matches need a wildcard arm.
 #[derive(Debug, Clone)]
 #[must_use]
+#[non_exhaustive]
 pub enum SimulationRunResult {
     /// The simulation settled
     Ok,
@@ -165,15 +523,157 @@ pub enum SimulationRunResult {
         /// A list of wires that had more than one driver
         conflicting_wires: Box<[WireId]>,
     },
+    /// The simulation settled, but X or Z inputs propagated to the outputs of some components
+    ///
+    /// Only reported if [`SimulatorBuilder::strict_unknown`] was called.
+    UnknownPropagated {
+        /// A list of components with an X or Z on an input and an X on an output
+        components: Box<[ComponentId]>,
+    },
+}
+
+impl SimulationRunResult {
+    /// Converts the result into a [`Result`], so it can be propagated with `?`
+    pub fn into_result(self) -> Result<(), SimulationError> {
+        match self {
+            SimulationRunResult::Ok => Ok(()),
+            SimulationRunResult::MaxStepsReached => Err(SimulationError::MaxStepsReached),
+            SimulationRunResult::Err { conflicting_wires } => {
+                Err(SimulationError::Conflict { conflicting_wires })
+            }
+            SimulationRunResult::UnknownPropagated { components } => {
+                Err(SimulationError::UnknownPropagated { components })
+            }
+        }
+    }
+}
+
+/// An error produced by running a simulation
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum SimulationError {
+    /// The simulation did not settle within the maximum allowed steps
+    ///
+    /// This is also how an oscillating circuit manifests, since it never settles.
+    MaxStepsReached,
+    /// One or more wires had more than one driver
+    Conflict {
+        /// A list of wires that had more than one driver
+        conflicting_wires: Box<[WireId]>,
+    },
+    /// X or Z inputs propagated to the outputs of some components in strict mode
+    UnknownPropagated {
+        /// A list of components with an X or Z on an input and an X on an output
+        components: Box<[ComponentId]>,
+    },
+}
+
+impl std::fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::MaxStepsReached => {
+                write!(
+                    f,
+                    "the simulation did not settle within the maximum allowed steps"
+                )
+            }
+            SimulationError::Conflict { conflicting_wires } => {
+                write!(
+                    f,
+                    "{} wire(s) had conflicting drivers",
+                    conflicting_wires.len()
+                )
+            }
+            SimulationError::UnknownPropagated { components } => {
+                write!(
+                    f,
+                    "{} component(s) propagated an unknown input to their output",
+                    components.len()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+/// An error produced by [`Simulator::run_vectors`]
+#[derive(Debug, Clone)]
+pub enum RunVectorsError {
+    InvalidWireId,
+    /// A vector did not contain exactly one state per input wire
+    VectorLengthMismatch {
+        vector_index: usize,
+    },
+    /// Running the simulation for a vector failed
+    Simulation {
+        vector_index: usize,
+        error: SimulationError,
+    },
+}
+
+macro_rules! component_info_fns {
+    () => {
+        /// Gets the kind of `component`
+        pub fn component_kind(
+            &self,
+            component: ComponentId,
+        ) -> Result<ComponentKind, InvalidComponentIdError> {
+            let component = self
+                .components
+                .get(component.0)
+                .ok_or(InvalidComponentIdError)?;
+            Ok(component.kind)
+        }
+
+        /// Gets the wires connected to the inputs of `component`, in the order the component
+        /// defines its inputs
+        pub fn component_inputs(
+            &self,
+            component: ComponentId,
+        ) -> Result<Vec<WireId>, InvalidComponentIdError> {
+            let component = self
+                .components
+                .get(component.0)
+                .ok_or(InvalidComponentIdError)?;
+            Ok(graph::component_input_wires(
+                &self.wires,
+                &self.inputs,
+                component,
+            ))
+        }
+    };
+}
+
+macro_rules! gate_fns {
+    ($($(#[$attr:meta])* $fn_name:ident => $ports:ident;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $fn_name(
+                &mut self,
+                inputs: &[WireId],
+            ) -> Result<(ComponentId, WireId), AddComponentError> {
+                self.add_gate_with_output(inputs, |inputs, output| $ports { inputs, output })
+            }
+        )*
+    };
 }
 
 macro_rules! wire_drive_fns {
     () => {
+        /// Sets the base drive of `wire`, which is combined with the outputs of all components
+        /// driving the wire
         pub fn set_wire_drive(
             &mut self,
             wire: WireId,
             new_drive: &LogicState,
         ) -> Result<(), InvalidWireIdError> {
+            #[cfg(feature = "event-log")]
+            let event = Event::SetWireDrive {
+                wire,
+                drive: new_drive.clone(),
+            };
+
             let wire = self.wires.get(wire.0).ok_or(InvalidWireIdError)?;
 
             let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
@@ -183,9 +683,94 @@ macro_rules! wire_drive_fns {
                 .expect("invalid wire drive offset");
             drive.copy_from_slice(&new_drive.0[..drive.len()]);
 
+            #[cfg(feature = "event-log")]
+            self.record_event(event);
+            Ok(())
+        }
+
+        /// Sets `width` bits of the base drive of `wire`, starting at bit `offset`,
+        /// to the first `width` bits of `new_drive`
+        ///
+        /// All other bits of the drive are left unchanged.
+        pub fn set_wire_drive_range(
+            &mut self,
+            wire: WireId,
+            offset: u32,
+            new_drive: &LogicState,
+            width: u32,
+        ) -> Result<(), SetWireDriveRangeError> {
+            #[cfg(feature = "event-log")]
+            let event = Event::SetWireDriveRange {
+                wire,
+                offset,
+                drive: new_drive.clone(),
+                width,
+            };
+
+            let wire = self
+                .wires
+                .get(wire.0)
+                .ok_or(SetWireDriveRangeError::InvalidWireId)?;
+            if !offset
+                .checked_add(width)
+                .is_some_and(|end| end <= wire.width)
+            {
+                return Err(SetWireDriveRangeError::OutOfRange);
+            }
+
+            let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
+            let drive = self
+                .wire_drives
+                .get_mut(wire.drive_offset, state_width)
+                .expect("invalid wire drive offset");
+
+            for src_bit in 0..width {
+                let src = new_drive.0[(src_bit / LogicStateAtom::BITS) as usize];
+                let src_shift = src_bit % LogicStateAtom::BITS;
+                let state = (src.state() >> src_shift) & 0x1;
+                let valid = (src.valid() >> src_shift) & 0x1;
+
+                let dst_bit = offset + src_bit;
+                let dst = &mut drive[(dst_bit / LogicStateAtom::BITS) as usize];
+                let dst_shift = dst_bit % LogicStateAtom::BITS;
+                let mask = !(0x1 << dst_shift);
+                *dst = LogicStateAtom::new(
+                    (dst.state() & mask) | (state << dst_shift),
+                    (dst.valid() & mask) | (valid << dst_shift),
+                );
+            }
+
+            #[cfg(feature = "event-log")]
+            self.record_event(event);
             Ok(())
         }
 
+        /// Drives `wire` with `bits`, which must contain exactly as many bits as the wire is wide
+        ///
+        /// The bits are ordered the same way as in [`LogicState::from_bits`].
+        pub fn set_wire_drive_bits(
+            &mut self,
+            wire: WireId,
+            bits: &[LogicBitState],
+        ) -> Result<(), SetWireDriveBitsError> {
+            let width = self
+                .wires
+                .get(wire.0)
+                .ok_or(SetWireDriveBitsError::InvalidWireId)?
+                .width;
+            if bits.len() != (width as usize) {
+                return Err(SetWireDriveBitsError::WidthMismatch);
+            }
+
+            let new_drive = LogicState::from_bits(bits).expect("invalid wire width");
+            self.set_wire_drive(wire, &new_drive)
+                .map_err(|_| SetWireDriveBitsError::InvalidWireId)
+        }
+
+        /// Gets the base drive of `wire` as set by [`set_wire_drive`](Self::set_wire_drive)
+        ///
+        /// This is neither the state the simulation resolved for the wire nor the state it is
+        /// forced to, use `get_wire_state` to read the resolved state.
         pub fn get_wire_drive(&mut self, wire: WireId) -> Result<LogicState, InvalidWireIdError> {
             let wire = self.wires.get(wire.0).ok_or(InvalidWireIdError)?;
 
@@ -199,6 +784,27 @@ macro_rules! wire_drive_fns {
             result.0[..drive.len()].copy_from_slice(drive);
             Ok(result)
         }
+
+        /// Lists all wires whose drive is not high impedance on every bit
+        pub fn driven_wires(&self) -> Vec<WireId> {
+            self.wires
+                .iter_indices()
+                .filter(|&wire_index| {
+                    let wire = self.wires.get(wire_index).unwrap();
+
+                    let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
+                    let drive = self
+                        .wire_drives
+                        .get(wire.drive_offset, state_width)
+                        .expect("invalid wire drive offset");
+
+                    let mut state = LogicState::HIGH_Z;
+                    state.0[..drive.len()].copy_from_slice(drive);
+                    !state.eq(&LogicState::HIGH_Z, wire.width)
+                })
+                .map(WireId)
+                .collect()
+        }
     };
 }
 
@@ -206,6 +812,21 @@ macro_rules! wire_drive_fns {
 pub enum SimulatorBuildError {
     GraphicsAdapterNotFound,
     GraphicsDeviceNotSupported,
+    OutOfMemory,
+    /// The circuit needs more than 2^32 - 1 entries in one of its buffers,
+    /// which is the limit of the 32 bit indices and offsets used on the GPU
+    OffsetSpaceExhausted,
+    /// GPU timing was enabled, but the device does not support timestamp queries
+    GpuTimingNotSupported,
+}
+
+impl From<BufferPushError> for SimulatorBuildError {
+    fn from(err: BufferPushError) -> Self {
+        match err {
+            BufferPushError::OutOfMemory => SimulatorBuildError::OutOfMemory,
+            BufferPushError::OffsetSpaceExhausted => SimulatorBuildError::OffsetSpaceExhausted,
+        }
+    }
 }
 
 impl From<gpu::CreateDeviceError> for SimulatorBuildError {
@@ -215,14 +836,43 @@ impl From<gpu::CreateDeviceError> for SimulatorBuildError {
             gpu::CreateDeviceError::DeviceNotSupported => {
                 SimulatorBuildError::GraphicsDeviceNotSupported
             }
+            gpu::CreateDeviceError::TimestampQueryNotSupported => {
+                SimulatorBuildError::GpuTimingNotSupported
+            }
+        }
+    }
+}
+
+/// Options for the device a simulator is built on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuOptions {
+    /// Which adapter to prefer on systems with more than one, defaults to high performance
+    ///
+    /// [`wgpu::PowerPreference::LowPower`] usually selects an integrated GPU,
+    /// which avoids waking up a discrete one for small circuits.
+    pub power_preference: wgpu::PowerPreference,
+}
+
+impl Default for GpuOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::HighPerformance,
         }
     }
 }
 
-#[derive(Debug, Default)]
+/// Collects wires and components and builds a [`Simulator`] from them
+///
+/// The layout of all GPU buffers is fully determined by the order in which wires, components,
+/// pulls, aliases and slices are added, so adding the same design in the same order
+/// always produces byte-identical buffers.
+#[derive(Debug, Clone)]
 pub struct SimulatorBuilder {
     wire_states: LogicStateBuffer<WireState, Building>,
     wire_drives: LogicStateBuffer<WireBaseDrive, Building>,
+    /// Forced wire states, laid out identically to the wire drives
+    wire_forces: LogicStateBuffer<WireBaseDrive, Building>,
     wire_drivers: Buffer<WireDriver, Building>,
     wires: Buffer<Wire, Building>,
 
@@ -231,9 +881,59 @@ pub struct SimulatorBuilder {
     inputs: Buffer<ComponentInput, Building>,
     memory: LogicStateBuffer<Memory, Building>,
     components: Buffer<Component, Building>,
+    /// The order set with [`SimulatorBuilder::set_evaluation_order`], completed with all
+    /// remaining components when building
+    evaluation_order: Buffer<Index<Component>, Building>,
+
+    /// Pairs of (root wire, aliased wire)
+    wire_aliases: Vec<(Index<Wire>, Index<Wire>)>,
+    /// Triples of (slice wire, target wire, bit offset)
+    wire_slices: Vec<(Index<Wire>, Index<Wire>, u32)>,
+    has_initial_wire_states: bool,
+    debug_label: Option<String>,
+    detect_conflicts: bool,
+    strict_unknown: bool,
+    max_reported_conflicts: u32,
+    enable_profiling: bool,
+    enable_gpu_timing: bool,
+}
+
+impl Default for SimulatorBuilder {
+    fn default() -> Self {
+        Self {
+            wire_states: Default::default(),
+            wire_drives: Default::default(),
+            wire_forces: Default::default(),
+            wire_drivers: Default::default(),
+            wires: Default::default(),
+
+            output_states: Default::default(),
+            outputs: Default::default(),
+            inputs: Default::default(),
+            memory: Default::default(),
+            components: Default::default(),
+            evaluation_order: Default::default(),
+
+            wire_aliases: Vec::new(),
+            wire_slices: Vec::new(),
+            has_initial_wire_states: false,
+            debug_label: None,
+            detect_conflicts: true,
+            strict_unknown: false,
+            max_reported_conflicts: DEFAULT_MAX_REPORTED_CONFLICTS,
+            enable_profiling: false,
+            enable_gpu_timing: false,
+        }
+    }
 }
 
 impl SimulatorBuilder {
+    /// Drives set on the builder are part of the initial state of the simulator,
+    /// so they are not recorded
+    #[cfg(feature = "event-log")]
+    #[inline]
+    fn record_event(&mut self, _event: Event) {}
+
     pub fn add_wire(&mut self, width: u32) -> AddWireResult {
         if (width < MIN_WIRE_WIDTH) || (width > MAX_WIRE_WIDTH) {
             return Err(AddWireError::WidthOutOfRange);
@@ -242,6 +942,8 @@ impl SimulatorBuilder {
         let state_width = width.div_ceil(LogicStateAtom::BITS);
         let state_offset = self.wire_states.push(state_width)?;
         let drive_offset = self.wire_drives.push(state_width)?;
+        let force_offset = self.wire_forces.push(state_width)?;
+        debug_assert_eq!(drive_offset, force_offset);
 
         let wire = Wire {
             width,
@@ -249,123 +951,1229 @@ impl SimulatorBuilder {
             drive_offset,
             first_driver_width: 0,
             first_driver_offset: Offset::INVALID,
+            first_driver_bit_offset: 0,
             driver_list: Index::INVALID,
+            pull: LogicStateAtom::HIGH_Z,
+            forced: 0,
         };
 
         let wire_index = self.wires.push(wire)?;
         Ok(WireId(wire_index))
     }
 
+    /// Adds a wire of `width` bits whose drivers also drive the bits of `wire` starting at `bit_offset`
+    ///
+    /// Components connected to the slice drive only these bits of `wire`, so several narrow
+    /// components can drive one wide wire without conflicting on the bits they don't touch.
+    /// The state of the slice itself only resolves the drivers connected to the slice,
+    /// the combined state is read from `wire`.
+    pub fn add_wire_slice(&mut self, wire: WireId, bit_offset: u32, width: u32) -> AddWireResult {
+        let target = self.wires.get(wire.0).ok_or(AddWireError::InvalidWireId)?;
+        if bit_offset.saturating_add(width) > target.width {
+            return Err(AddWireError::SliceOutOfRange);
+        }
+
+        let slice = self.add_wire(width)?;
+        self.wire_slices.push((slice.0, wire.0, bit_offset));
+        Ok(slice)
+    }
+
     wire_drive_fns!();
+    component_info_fns!();
+
+    /// Sets the state `wire` has at power-on, before any component has been evaluated
+    ///
+    /// Wires start out as high-Z by default. The initial states are seen by the components in
+    /// the first step after building and after [`Simulator::reset_to_initial`], which gives
+    /// feedback loops like latches a defined starting point.
+    pub fn set_initial_wire_state(
+        &mut self,
+        wire: WireId,
+        state: &LogicState,
+    ) -> Result<(), InvalidWireIdError> {
+        let wire = self.wires.get(wire.0).ok_or(InvalidWireIdError)?;
 
-    pub fn add_component<Ports: ComponentPorts>(&mut self, ports: Ports) -> AddComponentResult {
-        let output_kind = ports.create_outputs(
-            &mut self.wire_drivers,
-            &mut self.wires,
-            &mut self.output_states,
-            &mut self.outputs,
-        )?;
-        let (first_input, input_count) = ports.create_inputs(&self.wires, &mut self.inputs)?;
-        let (memory_offset, memory_size) = ports.create_memory(&mut self.memory)?;
+        let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
+        let initial_state = self
+            .wire_states
+            .get_mut(wire.state_offset, state_width)
+            .expect("invalid wire state offset");
+        initial_state.copy_from_slice(&state.0[..initial_state.len()]);
 
-        let (output_count, output) = match output_kind {
-            ComponentOutputKind::Single(output) => (1, ComponentInlineOutput { output }),
-            ComponentOutputKind::List(first_output, count) => {
-                assert!(count >= 2);
-                let output = ComponentInlineOutput {
-                    first_output: ComponentFirstOutput {
-                        padding: 0,
-                        first_output,
-                    },
-                };
-                (count, output)
-            }
-        };
+        self.has_initial_wire_states = true;
+        Ok(())
+    }
 
-        let component = Component {
-            kind: Ports::COMPONENT_KIND,
-            output_count,
-            input_count,
-            output,
-            first_input,
-            memory_offset,
-            memory_size,
+    /// Adds a pull to a wire, replacing any previous pull on it
+    pub fn add_pull(&mut self, ports: PullPorts) -> Result<(), InvalidWireIdError> {
+        let wire = self.wires.get_mut(ports.wire.0).ok_or(InvalidWireIdError)?;
+        wire.pull = match ports.value {
+            LogicBitState::HighZ => LogicStateAtom::HIGH_Z,
+            LogicBitState::Undefined => LogicStateAtom::UNDEFINED,
+            LogicBitState::Logic0 => LogicStateAtom::LOGIC_0,
+            LogicBitState::Logic1 => LogicStateAtom::LOGIC_1,
         };
 
-        let component_index = self.components.push(component)?;
-        Ok(ComponentId(component_index))
+        Ok(())
     }
 
-    #[inline]
-    pub fn build(self) -> Result<Simulator, SimulatorBuildError> {
-        gpu::create_simulator(self).map_err(Into::into)
+    fn wire_alias_root(&self, wire: Index<Wire>) -> Index<Wire> {
+        self.wire_aliases
+            .iter()
+            .find(|&&(_, alias)| alias == wire)
+            .map_or(wire, |&(root, _)| root)
     }
-}
 
-#[derive(Debug, Clone, Copy, Zeroable, Pod)]
-#[repr(C)]
-struct ListData {
-    wires_changed: u32,
-    components_changed: u32,
-    conflict_list_len: u32,
-    has_conflicts: u32,
-}
+    /// Makes `b` an alias of `a`, so both wires always carry the same state
+    ///
+    /// The wires must have the same width. `b` shares the drive of `a`
+    /// and both wires are driven by the outputs connected to either of them.
+    /// Each wire keeps a state of its own, which is resolved from the same drive and
+    /// drivers, so the states of both wires are equal after every step. Forcing or
+    /// releasing one of the wires applies to both.
+    pub fn alias_wire(&mut self, a: WireId, b: WireId) -> Result<(), AliasWireError> {
+        let wire_a = self.wires.get(a.0).ok_or(AliasWireError::InvalidWireId)?;
+        let wire_b = self.wires.get(b.0).ok_or(AliasWireError::InvalidWireId)?;
+        if wire_a.width != wire_b.width {
+            return Err(AliasWireError::WidthMismatch);
+        }
 
-const WORKGROUP_SIZE: u32 = 64;
+        let root_a = self.wire_alias_root(a.0);
+        let root_b = self.wire_alias_root(b.0);
+        if root_a == root_b {
+            return Ok(());
+        }
 
-pub struct Simulator {
-    device: &'static wgpu::Device,
-    queue: &'static wgpu::Queue,
+        let drive_offset = self.wires.get(root_a).unwrap().drive_offset;
+        self.wire_aliases.push((root_a, root_b));
+        for (root, alias) in &mut self.wire_aliases {
+            if *root == root_b {
+                *root = root_a;
+            }
 
-    list_data_buffer: wgpu::Buffer,
-    conflict_list_buffer: wgpu::Buffer,
+            if *root == root_a {
+                self.wires.get_mut(*alias).unwrap().drive_offset = drive_offset;
+            }
+        }
 
-    wire_states: LogicStateBuffer<WireState, Finalized>,
-    wire_drives: LogicStateBuffer<WireBaseDrive, Finalized>,
-    wire_drivers: Buffer<WireDriver, Finalized>,
-    wires: Buffer<Wire, Finalized>,
+        Ok(())
+    }
 
-    output_states: LogicStateBuffer<OutputState, Finalized>,
-    outputs: Buffer<ComponentOutput, Finalized>,
-    inputs: Buffer<ComponentInput, Finalized>,
-    memory: LogicStateBuffer<Memory, Finalized>,
-    components: Buffer<Component, Finalized>,
+    fn wire_alias_group(&self, root: Index<Wire>) -> Vec<Index<Wire>> {
+        std::iter::once(root)
+            .chain(
+                self.wire_aliases
+                    .iter()
+                    .filter(|&&(r, _)| r == root)
+                    .map(|&(_, alias)| alias),
+            )
+            .collect()
+    }
+
+    fn merge_wire_aliases(&mut self) -> Result<(), BufferPushError> {
+        let mut roots: Vec<_> = self.wire_aliases.iter().map(|&(root, _)| root).collect();
+        roots.sort();
+        roots.dedup();
+
+        for root in roots {
+            let group = self.wire_alias_group(root);
+
+            graph::merge_wire_drivers(&mut self.wires, &mut self.wire_drivers, &group)?;
+        }
+
+        Ok(())
+    }
+
+    fn merge_wire_slices(&mut self) -> Result<(), BufferPushError> {
+        // A slice is always added after its target, so merging in reverse order
+        // lets slices of slices reach the outermost wire.
+        let wire_slices = std::mem::take(&mut self.wire_slices);
+        for &(slice, target, bit_offset) in wire_slices.iter().rev() {
+            let root = self.wire_alias_root(target);
+            let group = self.wire_alias_group(root);
+
+            graph::add_slice_drivers(
+                &mut self.wires,
+                &mut self.wire_drivers,
+                slice,
+                &group,
+                bit_offset,
+            )?;
+        }
+
+        self.wire_aliases.clear();
+        Ok(())
+    }
+
+    fn merge_wires(&mut self) -> Result<(), BufferPushError> {
+        debug_assert!(
+            self.driver_width_mismatches().is_empty(),
+            "driver width does not match its output or wire",
+        );
+
+        self.merge_wire_aliases()?;
+        self.merge_wire_slices()
+    }
+
+    /// Sets the order in which the component shader evaluates the components
+    ///
+    /// `order` has to contain every component added so far exactly once. Components added
+    /// afterwards are evaluated after all listed ones, in the order they were added.
+    /// All components are still evaluated in parallel within a step, so this only changes how
+    /// they are assigned to invocations, which allows experimenting with scheduling heuristics.
+    pub fn set_evaluation_order(
+        &mut self,
+        order: &[ComponentId],
+    ) -> Result<(), InvalidEvaluationOrderError> {
+        if order.len() != (self.components.len() as usize) {
+            return Err(InvalidEvaluationOrderError);
+        }
+
+        let mut listed = vec![false; order.len()];
+        for component in order {
+            let index = component.0.get().ok_or(InvalidEvaluationOrderError)? as usize;
+            let listed = listed.get_mut(index).ok_or(InvalidEvaluationOrderError)?;
+            if std::mem::replace(listed, true) {
+                return Err(InvalidEvaluationOrderError);
+            }
+        }
+
+        let mut evaluation_order = Buffer::new();
+        for component in order {
+            evaluation_order
+                .push(component.0)
+                .expect("evaluation order cannot be longer than the component buffer");
+        }
+
+        self.evaluation_order = evaluation_order;
+        Ok(())
+    }
+
+    /// Appends all components missing from the evaluation order in the order they were added
+    fn complete_evaluation_order(&mut self) -> Result<(), BufferPushError> {
+        for index in self.evaluation_order.len()..self.components.len() {
+            self.evaluation_order
+                .push(Index::new(index).expect("invalid component index"))?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn add_component<Ports: ComponentPorts>(&mut self, ports: Ports) -> AddComponentResult {
+        self.add_component_detailed(ports).map(|added| added.id)
+    }
+
+    fn add_gate_with_output<'a, Ports: ComponentPorts>(
+        &mut self,
+        inputs: &'a [WireId],
+        ports: impl FnOnce(&'a [WireId], WireId) -> Ports,
+    ) -> Result<(ComponentId, WireId), AddComponentError> {
+        if inputs.len() > MAX_GATE_INPUTS {
+            return Err(AddComponentError::TooManyInputs);
+        }
+
+        let width = self.assert_same_width(inputs)?;
+        let output = self.add_wire(width).map_err(|err| match err {
+            AddWireError::OutOfMemory => AddComponentError::OutOfMemory,
+            AddWireError::OffsetSpaceExhausted => AddComponentError::OffsetSpaceExhausted,
+            AddWireError::WidthOutOfRange
+            | AddWireError::InvalidWireId
+            | AddWireError::SliceOutOfRange => unreachable!("invalid wire width"),
+        })?;
+
+        let component = self.add_component(ports(inputs, output))?;
+        Ok((component, output))
+    }
+
+    gate_fns! {
+        /// Adds an AND gate together with a new output wire as wide as all of its inputs
+        ///
+        /// Returns the IDs of the gate and of its output wire.
+        add_and_gate => AndGatePorts;
+        /// Like [`add_and_gate`](Self::add_and_gate), but adds an OR gate
+        add_or_gate => OrGatePorts;
+        /// Like [`add_and_gate`](Self::add_and_gate), but adds an XOR gate
+        add_xor_gate => XorGatePorts;
+        /// Like [`add_and_gate`](Self::add_and_gate), but adds a NAND gate
+        add_nand_gate => NandGatePorts;
+        /// Like [`add_and_gate`](Self::add_and_gate), but adds a NOR gate
+        add_nor_gate => NorGatePorts;
+        /// Like [`add_and_gate`](Self::add_and_gate), but adds an XNOR gate
+        add_xnor_gate => XnorGatePorts;
+    }
+
+    /// Adds a component and reports what was allocated for it
+    ///
+    /// This is useful for higher level constructs built on top of the builder that need to know
+    /// about the created component without querying it afterwards.
+    pub fn add_component_detailed<Ports: ComponentPorts>(
+        &mut self,
+        ports: Ports,
+    ) -> Result<AddedComponent, AddComponentError> {
+        ports.validate(&self.wires)?;
+
+        let output_kind = ports.create_outputs(
+            &mut self.wire_drivers,
+            &mut self.wires,
+            &mut self.output_states,
+            &mut self.outputs,
+        )?;
+        let (first_input, input_count) = ports.create_inputs(&self.wires, &mut self.inputs)?;
+        let (memory_offset, memory_size) = ports.create_memory(&self.wires, &mut self.memory)?;
+
+        let output_state_offset = match output_kind {
+            ComponentOutputKind::None => None,
+            ComponentOutputKind::Single(output) => output.state_offset.get(),
+            ComponentOutputKind::List(first_output, _) => self
+                .outputs
+                .get(first_output)
+                .and_then(|output| output.state_offset.get()),
+        };
+
+        let (output_count, output) = match output_kind {
+            ComponentOutputKind::None => {
+                let output = ComponentInlineOutput {
+                    first_output: ComponentFirstOutput {
+                        padding: 0,
+                        first_output: Index::INVALID,
+                    },
+                };
+                (0, output)
+            }
+            ComponentOutputKind::Single(output) => (1, ComponentInlineOutput { output }),
+            ComponentOutputKind::List(first_output, count) => {
+                assert!(count >= 2);
+                let output = ComponentInlineOutput {
+                    first_output: ComponentFirstOutput {
+                        padding: 0,
+                        first_output,
+                    },
+                };
+                (count, output)
+            }
+        };
+
+        let component = Component {
+            kind: Ports::COMPONENT_KIND,
+            output_count,
+            input_count,
+            output,
+            first_input,
+            memory_offset,
+            memory_size,
+        };
+
+        let component_index = self.components.push(component)?;
+        Ok(AddedComponent {
+            id: ComponentId(component_index),
+            output_state_offset,
+            output_count,
+            input_count,
+        })
+    }
+
+    /// The number of wires added so far
+    #[inline]
+    pub fn wire_count(&self) -> u32 {
+        self.wires.len()
+    }
+
+    /// Checks the port widths of all components added so far
+    ///
+    /// Outputs with a fixed width are already rejected by [`add_component`](Self::add_component),
+    /// but mismatches between ports, e.g. an adder whose inputs are narrower than its output,
+    /// are accepted and silently truncated or padded. This reports all of them at once.
+    pub fn check_widths(&self) -> Result<(), Vec<WidthError>> {
+        let errors = graph::find_width_errors(&self.inputs, &self.outputs, &self.components);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Hashes the structure of the circuit built so far
+    ///
+    /// Two builders that added the same wires, components, aliases and slices in the same order
+    /// produce the same hash, regardless of their drives, forces and initial wire states, and of
+    /// the options set on the builder. The initial memory contents are part of the structure.
+    /// The hash does not change between runs of the program, so it can be used as a persistent cache key.
+    pub fn structural_hash(&self) -> u64 {
+        use std::hash::Hasher;
+
+        // Every list is prefixed with its length, so entries cannot move from one list
+        // to the next without changing the hash.
+        fn write_list<T: Pod>(hasher: &mut graph::StructuralHasher, list: &[T]) {
+            hasher.write(&(list.len() as u64).to_le_bytes());
+            hasher.write(bytemuck::cast_slice(list));
+        }
+
+        let wires: Vec<_> = self
+            .wires
+            .as_slice()
+            .iter()
+            .map(|wire| Wire { forced: 0, ..*wire })
+            .collect();
+        let wire_aliases: Vec<_> = self
+            .wire_aliases
+            .iter()
+            .map(|&(root, alias)| [root, alias])
+            .collect();
+        let wire_slices: Vec<_> = self
+            .wire_slices
+            .iter()
+            .map(|&(slice, target, bit_offset)| -> [u32; 3] {
+                [bytemuck::cast(slice), bytemuck::cast(target), bit_offset]
+            })
+            .collect();
+
+        let mut hasher = graph::StructuralHasher::default();
+        write_list(&mut hasher, &wires);
+        write_list(&mut hasher, self.wire_drivers.as_slice());
+        write_list(&mut hasher, self.outputs.as_slice());
+        write_list(&mut hasher, self.inputs.as_slice());
+        write_list(&mut hasher, self.memory.as_slice());
+        write_list(&mut hasher, self.components.as_slice());
+        write_list(&mut hasher, self.evaluation_order.as_slice());
+        write_list(&mut hasher, &wire_aliases);
+        write_list(&mut hasher, &wire_slices);
+        hasher.finish()
+    }
+
+    /// Finds all component outputs that drive a wire with a width that differs from it
+    ///
+    /// Every output drives all bits of the wire it was connected to, so its driver has to be
+    /// exactly as wide as both the output and the wire, otherwise the GPU would read or write
+    /// unrelated state. [`add_component`](Self::add_component) rejects mismatched fixed-width
+    /// outputs and sizes all others from their wire, so this is a guard for the invariant
+    /// rather than something to expect in practice. Outputs connected to wire slices are
+    /// checked against the slice, not the wire it is a slice of.
+    pub fn driver_width_mismatches(&self) -> Vec<(WireId, ComponentId)> {
+        graph::find_driver_width_mismatches(
+            &self.wires,
+            &self.wire_drivers,
+            &self.outputs,
+            &self.components,
+        )
+    }
+
+    /// Checks that all `wires` have the same width and returns it
+    ///
+    /// Meant to be called before adding a component whose ports must match,
+    /// e.g. the inputs of a multiplexer. An empty list has no common width
+    /// and is reported as [`AddComponentError::WidthMismatch`].
+    pub fn assert_same_width(&self, wires: &[WireId]) -> Result<u32, AddComponentError> {
+        let mut widths = wires.iter().map(|wire| {
+            self.wires
+                .get(wire.0)
+                .map(|wire| wire.width)
+                .ok_or(AddComponentError::InvalidWireId)
+        });
+
+        let width = widths.next().ok_or(AddComponentError::WidthMismatch)??;
+        for other_width in widths {
+            if other_width? != width {
+                return Err(AddComponentError::WidthMismatch);
+            }
+        }
+
+        Ok(width)
+    }
+
+    /// Finds groups of components that feed back into themselves without passing through
+    /// a sequential component
+    ///
+    /// Such loops usually never settle and make [`Simulator::run`] end with
+    /// [`SimulationRunResult::MaxStepsReached`]. Every returned group is one strongly
+    /// connected part of the circuit, with the components sorted by their ID.
+    pub fn detect_combinational_cycles(&self) -> Vec<Vec<ComponentId>> {
+        let mut wire_links = Vec::new();
+        for &(root, alias) in &self.wire_aliases {
+            wire_links.push((root, alias));
+            wire_links.push((alias, root));
+        }
+        for &(slice, target, _) in &self.wire_slices {
+            wire_links.push((slice, target));
+        }
+
+        graph::find_combinational_cycles(
+            &self.wires,
+            &self.wire_drivers,
+            &self.inputs,
+            &self.outputs,
+            &self.components,
+            &wire_links,
+        )
+        .into_iter()
+        .map(|cycle| cycle.into_iter().map(ComponentId).collect())
+        .collect()
+    }
+
+    /// The number of components added so far
+    #[inline]
+    pub fn component_count(&self) -> u32 {
+        self.components.len()
+    }
+
+    /// The number of drivers `wire` has once aliases and slices are merged into it
+    fn merged_driver_count(&self, wire: Index<Wire>) -> usize {
+        let group = self.wire_alias_group(self.wire_alias_root(wire));
+
+        let direct_count: usize = group
+            .iter()
+            .map(|&alias| {
+                let alias = self.wires.get(alias).unwrap();
+                alias.drivers(&self.wire_drivers).count()
+            })
+            .sum();
+
+        // Slices of slices reach the outermost wire, so the count recurses into them.
+        let slice_count: usize = self
+            .wire_slices
+            .iter()
+            .filter(|(_, target, _)| group.contains(target))
+            .map(|&(slice, _, _)| self.merged_driver_count(slice))
+            .sum();
+
+        direct_count + slice_count
+    }
+
+    /// Writes all wires and components added so far in a human-readable form, one per line
+    ///
+    /// Wires are listed with their width, number of drivers and the wire they alias or slice.
+    /// Like [`Simulator::wire_drivers`], the drivers of a wire include those connected to its
+    /// aliases and slices.
+    /// Components are listed with their kind and the wires connected to their inputs and outputs.
+    /// All references are resolved to wire and component IDs, so the output only depends on
+    /// the order in which the circuit was built.
+    pub fn debug_dump(&self) -> String {
+        use std::fmt::Write;
+
+        let mut output_wires: Vec<_> = self
+            .wires
+            .iter_indices()
+            .flat_map(|wire_index| {
+                let wire = self.wires.get(wire_index).unwrap();
+                wire.drivers(&self.wire_drivers)
+                    .map(move |(_, _, output_state_offset)| (output_state_offset, wire_index))
+            })
+            .collect();
+        output_wires.sort_by_key(|&(output_state_offset, _)| output_state_offset.get());
+
+        let mut dump = String::new();
+        for wire_index in self.wires.iter_indices() {
+            let wire = self.wires.get(wire_index).unwrap();
+            let driver_count = self.merged_driver_count(wire_index);
+            write!(
+                dump,
+                "wire {wire_index:?}: width {}, {driver_count} driver{}",
+                wire.width,
+                if driver_count == 1 { "" } else { "s" },
+            )
+            .unwrap();
+
+            if let Some(&(_, target, bit_offset)) = self
+                .wire_slices
+                .iter()
+                .find(|&&(slice, _, _)| slice == wire_index)
+            {
+                write!(dump, ", slice of wire {target:?} at bit {bit_offset}").unwrap();
+            }
+            if let Some(&(root, _)) = self
+                .wire_aliases
+                .iter()
+                .find(|&&(_, alias)| alias == wire_index)
+            {
+                write!(dump, ", alias of wire {root:?}").unwrap();
+            }
+            dump.push('\n');
+        }
+
+        for component_index in self.components.iter_indices() {
+            let component = self.components.get(component_index).unwrap();
+            let inputs: Vec<_> = graph::component_input_wires(&self.wires, &self.inputs, component)
+                .into_iter()
+                .map(|wire| wire.0)
+                .collect();
+            let outputs: Vec<_> = component
+                .output_list(&self.outputs)
+                .flat_map(|output| {
+                    let start = output_wires
+                        .partition_point(|(offset, _)| offset.get() < output.state_offset.get());
+                    output_wires[start..]
+                        .iter()
+                        .take_while(move |(offset, _)| *offset == output.state_offset)
+                        .map(|&(_, wire_index)| wire_index)
+                })
+                .collect();
+
+            writeln!(
+                dump,
+                "component {component_index:?}: {:?}, inputs {inputs:?}, outputs {outputs:?}",
+                component.kind,
+            )
+            .unwrap();
+        }
+
+        dump
+    }
+
+    /// Disables the detection of conflicting wire drivers
+    ///
+    /// This saves shader work in every step, but a circuit containing conflicts will silently
+    /// produce undefined states instead of [`SimulationRunResult::Err`].
+    /// Only use this for circuits that are known to be free of conflicts.
+    #[inline]
+    pub fn disable_conflict_detection(&mut self) {
+        self.detect_conflicts = false;
+    }
+
+    /// Makes runs fail if an X or Z on an input of a component reaches its output
+    ///
+    /// After the simulation settled, every component with an X or Z on an input and an X on
+    /// an output is reported through [`SimulationRunResult::UnknownPropagated`]. This turns
+    /// floating inputs into an explicit failure instead of silently propagating X states.
+    /// Checking requires reading back all wire and output states after every run.
+    #[inline]
+    pub fn strict_unknown(&mut self) {
+        self.strict_unknown = true;
+    }
+
+    /// Sets how many conflicting wires a run reports at most, defaults to
+    /// [`DEFAULT_MAX_REPORTED_CONFLICTS`]
+    ///
+    /// Conflicts past this number are still detected, but the wires are left out of
+    /// [`SimulationRunResult::Err`]. With 0 a run only reports that conflicts occurred.
+    pub fn max_reported_conflicts(&mut self, n: u32) -> Result<(), TooManyReportedConflictsError> {
+        if n > MAX_REPORTED_CONFLICTS_LIMIT {
+            return Err(TooManyReportedConflictsError);
+        }
+
+        self.max_reported_conflicts = n;
+        Ok(())
+    }
+
+    /// Labels all GPU buffers of the simulator as `"<prefix>:<buffer name>"`, e.g. `"gsim2:wire_states"`
+    ///
+    /// The labels show up in GPU debuggers and profilers. Buffers are unlabeled by default.
+    #[inline]
+    pub fn set_debug_label(&mut self, prefix: &str) {
+        self.debug_label = Some(prefix.to_owned());
+    }
+
+    /// Makes the simulator record how many wires and components changed at every readback
+    ///
+    /// The data of the last run is available through [`Simulator::last_run_profile`].
+    #[inline]
+    pub fn enable_profiling(&mut self) {
+        self.enable_profiling = true;
+    }
+
+    /// Makes the simulator measure the GPU time spent in the wire and component passes
+    ///
+    /// The passes of every step are submitted separately to place timestamp queries around
+    /// them, which slows down the simulation somewhat. The times of the last run are available
+    /// through [`Simulator::last_pass_timings`]. Building fails with
+    /// [`SimulatorBuildError::GpuTimingNotSupported`] if the device does not support
+    /// timestamp queries.
+    #[inline]
+    pub fn enable_gpu_timing(&mut self) {
+        self.enable_gpu_timing = true;
+    }
+
+    #[inline]
+    pub fn build(self) -> Result<Simulator, SimulatorBuildError> {
+        self.build_with_options(GpuOptions::default())
+    }
+
+    /// Builds a simulator from a copy of the design, so the builder can still be edited afterwards
+    #[inline]
+    pub fn build_cloned(&self) -> Result<Simulator, SimulatorBuildError> {
+        self.clone().build()
+    }
+
+    /// Builds the simulator on the shared device matching `options`
+    ///
+    /// Simulators built with equal options share one device.
+    #[inline]
+    pub fn build_with_options(
+        mut self,
+        options: GpuOptions,
+    ) -> Result<Simulator, SimulatorBuildError> {
+        self.merge_wires()?;
+        self.complete_evaluation_order()?;
+        gpu::create_simulator(self, options).map_err(Into::into)
+    }
+
+    /// Builds the simulator on a device requested from `instance` instead of the shared default device
+    ///
+    /// This allows embedding the simulator into applications that configure their own instance.
+    /// Every call creates a new device, which is dropped together with the simulator and all of
+    /// its forks.
+    #[inline]
+    pub fn build_with_instance(
+        mut self,
+        instance: &wgpu::Instance,
+    ) -> Result<Simulator, SimulatorBuildError> {
+        self.merge_wires()?;
+        self.complete_evaluation_order()?;
+        gpu::create_simulator_with_instance(self, instance, GpuOptions::default())
+            .map_err(Into::into)
+    }
+
+    /// Builds the simulator without blocking on device creation
+    ///
+    /// This is required on the web, where the main thread cannot block.
+    #[cfg(feature = "wasm")]
+    #[inline]
+    pub async fn build_async(mut self) -> Result<Simulator, SimulatorBuildError> {
+        self.merge_wires()?;
+        self.complete_evaluation_order()?;
+        gpu::create_simulator_async(self, GpuOptions::default())
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+#[repr(C)]
+struct ListData {
+    wires_changed: u32,
+    components_changed: u32,
+    conflict_list_len: u32,
+    has_conflicts: u32,
+    step_count: u32,
+}
+
+/// The arguments of an indirect dispatch
+///
+/// The reset shader writes the arguments of the wire and component dispatch of every step,
+/// followed by the arguments covering all wires and components, which it copies from
+/// as long as the circuit has not settled.
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+#[repr(C)]
+struct DispatchArgs {
+    workgroup_count_x: u32,
+    workgroup_count_y: u32,
+    workgroup_count_z: u32,
+}
+
+impl DispatchArgs {
+    const EMPTY: Self = Self::new(0);
+    const WIRE_OFFSET: u64 = 0;
+    const COMPONENT_OFFSET: u64 = std::mem::size_of::<Self>() as u64;
+
+    const fn new(workgroup_count: u32) -> Self {
+        Self {
+            workgroup_count_x: workgroup_count,
+            workgroup_count_y: 1,
+            workgroup_count_z: 1,
+        }
+    }
+}
+
+/// The activity of the simulation at one readback of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunProfileSample {
+    /// The number of steps in the run up to this point that still changed the state
+    pub step_count: u32,
+    /// The number of wires that changed in the last step
+    pub wires_changed: u32,
+    /// The number of components that changed in the last step
+    pub components_changed: u32,
+}
+
+/// Convergence data collected during a single run
+///
+/// Only recorded if [`SimulatorBuilder::enable_profiling`] was called.
+#[derive(Debug, Default, Clone)]
+pub struct RunProfile {
+    samples: Vec<RunProfileSample>,
+}
+
+impl RunProfile {
+    /// The samples taken at every readback, in order
+    #[inline]
+    pub fn samples(&self) -> &[RunProfileSample] {
+        &self.samples
+    }
+
+    /// The number of steps in the run that changed the state
+    #[inline]
+    pub fn step_count(&self) -> u32 {
+        self.samples.last().map_or(0, |sample| sample.step_count)
+    }
+
+    /// The highest number of wires that changed in a sampled step
+    pub fn peak_wires_changed(&self) -> u32 {
+        self.samples
+            .iter()
+            .map(|sample| sample.wires_changed)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The highest number of components that changed in a sampled step
+    pub fn peak_components_changed(&self) -> u32 {
+        self.samples
+            .iter()
+            .map(|sample| sample.components_changed)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// The GPU time spent in the passes of a single run
+///
+/// Only measured if [`SimulatorBuilder::enable_gpu_timing`] was called. The initial
+/// evaluation at the start of a run is not included.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PassTimings {
+    /// The time spent resolving wire states
+    pub wire_pass: std::time::Duration,
+    /// The time spent evaluating components
+    pub component_pass: std::time::Duration,
+    /// The number of steps the times were measured over
+    pub step_count: u64,
+}
+
+const STEPS_PER_BATCH: u32 = 32;
+
+/// The number of batches submitted between two reads of the list data grows up to this value,
+/// so small circuits still finish quickly while slowly converging ones avoid most readbacks.
+/// Steps submitted after the circuit settled dispatch no workgroups, so overshooting is cheap.
+const MAX_BATCHES_PER_READBACK: u32 = 16;
+
+/// Dispatches one invocation per item
+///
+/// The dispatch is skipped entirely if there are no items, since some drivers reject
+/// dispatching zero workgroups.
+fn dispatch_per_item(pass: &mut wgpu::ComputePass<'_>, item_count: u32, workgroup_size: u32) {
+    if item_count > 0 {
+        pass.dispatch_workgroups(item_count.div_ceil(workgroup_size), 1, 1);
+    }
+}
+
+const RESET_WIRES_CHANGED: u32 = 0x1;
+const RESET_COMPONENTS_CHANGED: u32 = 0x2;
+
+pub struct Simulator {
+    context: gpu::GpuContextHandle,
+
+    list_data_buffer: wgpu::Buffer,
+    conflict_list_buffer: wgpu::Buffer,
+    max_reported_conflicts: u32,
+    detect_conflicts: bool,
+    changed_wires_buffer: wgpu::Buffer,
+
+    wire_states: LogicStateBuffer<WireState, Finalized>,
+    wire_drives: LogicStateBuffer<WireBaseDrive, Finalized>,
+    wire_forces: LogicStateBuffer<WireBaseDrive, Finalized>,
+    wire_drivers: Buffer<WireDriver, Finalized>,
+    wires: Buffer<Wire, Finalized>,
+
+    output_states: LogicStateBuffer<OutputState, Finalized>,
+    outputs: Buffer<ComponentOutput, Finalized>,
+    inputs: Buffer<ComponentInput, Finalized>,
+    memory: LogicStateBuffer<Memory, Finalized>,
+    components: Buffer<Component, Finalized>,
+    evaluation_order: Buffer<Index<Component>, Finalized>,
+
+    bind_group: wgpu::BindGroup,
+    /// The arguments of the indirect wire and component dispatches, see [`DispatchArgs`]
+    dispatch_args_buffer: wgpu::Buffer,
+    dispatch_bind_group: wgpu::BindGroup,
+
+    staging_buffer: Option<wgpu::Buffer>,
+    wire_states_need_sync: bool,
+    wire_state_snapshot: Option<WireStateSnapshot>,
+    /// Set while a [`WireStateReader`] of this simulator is alive
+    wire_state_reader: Option<WireStateReader>,
+    /// The states swapped out of the front buffer of the reader, reused for the next swap
+    wire_state_back_buffer: Option<Arc<[LogicStateAtom]>>,
+    changed_wires: Vec<WireId>,
+    changed_wires_need_sync: bool,
+    max_steps_observed: u64,
+    run_profile: Option<RunProfile>,
+    debug_label: Option<String>,
+    /// Maps output state offsets back to their components, sorted by offset
+    output_state_owners: Vec<(Offset<OutputState>, Index<Component>, u8)>,
+    /// The wires sharing their drive with an alias, sorted by drive offset
+    wire_aliases: Vec<(Offset<WireBaseDrive>, Index<Wire>)>,
+    has_initial_wire_states: bool,
+    strict_unknown: bool,
+    /// The next run has to evaluate the components on the initial wire states
+    /// before resolving the wires
+    initial_wire_states_pending: bool,
+    /// Set by the queue once the last batch of steps submitted by a run has finished
+    run_batches_done: Arc<AtomicBool>,
+    pass_timer: Option<gpu::PassTimer>,
+    #[cfg(feature = "event-log")]
+    event_log: Option<EventLog>,
+}
+
+impl Simulator {
+    #[inline]
+    fn pipelines(&self) -> &gpu::Pipelines {
+        self.context.pipelines(self.detect_conflicts)
+    }
+
+    fn sync_wire_states(&mut self) {
+        if self.wire_states_need_sync {
+            self.wire_states.sync(
+                &self.context.device,
+                &self.context.queue,
+                &mut self.staging_buffer,
+            );
+            self.wire_states_need_sync = false;
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn sync_wire_states_async(&mut self) {
+        if self.wire_states_need_sync {
+            self.wire_states
+                .sync_async(
+                    &self.context.device,
+                    &self.context.queue,
+                    &mut self.staging_buffer,
+                )
+                .await;
+            self.wire_states_need_sync = false;
+        }
+    }
+
+    wire_drive_fns!();
+    component_info_fns!();
+
+    /// Creates an independent copy of the simulator, including the current state of all wires
+    /// and components
+    ///
+    /// The copy lives on the same GPU device but shares no buffers with this simulator, so both
+    /// can be driven and run independently. This is much cheaper than building the circuit again.
+    pub fn fork(&self) -> Simulator {
+        gpu::fork_simulator(self)
+    }
+
+    /// Gets the full WGSL source of the wire, component and reset shaders, in that order
+    ///
+    /// These are the sources exactly as they are compiled for the device of this simulator,
+    /// including the common definitions shared by all shaders and the
+    /// [`workgroup_size`](Self::workgroup_size) picked for the device.
+    /// Pipeline-overridable constants are not substituted.
+    pub fn dump_shader_sources(&self) -> [(&'static str, String); 3] {
+        let workgroup_size = self.pipelines().workgroup_size;
+        gpu::SHADER_SOURCES.map(|(name, source)| {
+            let source = gpu::full_shader_source(source, workgroup_size);
+            (name, source)
+        })
+    }
+
+    /// The number of invocations per workgroup of the wire and component shaders
+    ///
+    /// This is picked from the subgroup size of the device, so it is the same for all
+    /// simulators on one device.
+    #[inline]
+    pub fn workgroup_size(&self) -> u32 {
+        self.pipelines().workgroup_size
+    }
+
+    /// The number of wires in the simulation
+    #[inline]
+    pub fn wire_count(&self) -> u32 {
+        self.wires.len()
+    }
+
+    /// The number of components in the simulation
+    #[inline]
+    pub fn component_count(&self) -> u32 {
+        self.components.len()
+    }
+
+    /// Finds `wire` and all its aliases
+    fn wire_alias_group(&self, wire: Index<Wire>) -> Option<Vec<Index<Wire>>> {
+        let drive_offset = self.wires.get(wire)?.drive_offset;
+
+        let start = self
+            .wire_aliases
+            .partition_point(|&(offset, _)| offset < drive_offset);
+        let group: Vec<_> = self.wire_aliases[start..]
+            .iter()
+            .take_while(|&&(offset, _)| offset == drive_offset)
+            .map(|&(_, alias)| alias)
+            .collect();
+
+        if group.is_empty() {
+            Some(vec![wire])
+        } else {
+            Some(group)
+        }
+    }
+
+    /// Forces `wire` to `state`, ignoring all components driving it until it is released
+    ///
+    /// Unlike [`set_wire_drive`](Self::set_wire_drive) this never causes a conflict.
+    /// The drive of the wire is kept and takes effect again once the wire is released.
+    /// Aliases of the wire (see [`SimulatorBuilder::alias_wire`]) are forced along with it.
+    pub fn force_wire(
+        &mut self,
+        wire: WireId,
+        state: &LogicState,
+    ) -> Result<(), InvalidWireIdError> {
+        #[cfg(feature = "event-log")]
+        let event = Event::ForceWire {
+            wire,
+            state: state.clone(),
+        };
+
+        let group = self.wire_alias_group(wire.0).ok_or(InvalidWireIdError)?;
+        for &alias in &group {
+            self.wires.get_mut(alias).unwrap().forced = 1;
+        }
+
+        // Aliases share their drive, and with it the force.
+        let wire = self.wires.get(wire.0).unwrap();
+        let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
+        let force = self
+            .wire_forces
+            .get_mut(wire.drive_offset, state_width)
+            .expect("invalid wire force offset");
+        force.copy_from_slice(&state.0[..force.len()]);
+
+        #[cfg(feature = "event-log")]
+        self.record_event(event);
+        Ok(())
+    }
+
+    /// Releases a wire previously forced with [`force_wire`](Self::force_wire)
+    ///
+    /// Releasing a wire that is not forced has no effect.
+    /// Aliases of the wire are released along with it.
+    pub fn release_wire(&mut self, wire: WireId) -> Result<(), InvalidWireIdError> {
+        #[cfg(feature = "event-log")]
+        let event = Event::ReleaseWire { wire };
+
+        let group = self.wire_alias_group(wire.0).ok_or(InvalidWireIdError)?;
+        for alias in group {
+            self.wires.get_mut(alias).unwrap().forced = 0;
+        }
+
+        #[cfg(feature = "event-log")]
+        self.record_event(event);
+        Ok(())
+    }
+
+    /// Gets the state of `wire` as resolved by the last run
+    ///
+    /// This combines the base drive with all component drivers and the pull,
+    /// or is the forced state if the wire was forced during the last run.
+    pub fn get_wire_state(&mut self, wire: WireId) -> Result<LogicState, InvalidWireIdError> {
+        self.sync_wire_states();
+        self.read_wire_state(wire)
+    }
+
+    /// Like [`get_wire_state`](Self::get_wire_state), but does not block while reading back from the GPU
+    #[cfg(feature = "wasm")]
+    pub async fn get_wire_state_async(
+        &mut self,
+        wire: WireId,
+    ) -> Result<LogicState, InvalidWireIdError> {
+        self.sync_wire_states_async().await;
+        self.read_wire_state(wire)
+    }
+
+    /// Gets the state of `wire` as resolved by the last run as one entry per bit, LSB first
+    ///
+    /// The result has exactly as many entries as the wire is wide.
+    pub fn get_wire_bits(
+        &mut self,
+        wire: WireId,
+    ) -> Result<Vec<LogicBitState>, InvalidWireIdError> {
+        self.sync_wire_states();
+        self.read_wire_bits(wire)
+    }
+
+    /// Like [`get_wire_bits`](Self::get_wire_bits), but does not block while reading back from the GPU
+    #[cfg(feature = "wasm")]
+    pub async fn get_wire_bits_async(
+        &mut self,
+        wire: WireId,
+    ) -> Result<Vec<LogicBitState>, InvalidWireIdError> {
+        self.sync_wire_states_async().await;
+        self.read_wire_bits(wire)
+    }
+
+    /// Gets the component outputs driving `wire` as pairs of (component, output index),
+    /// in the order they were connected
+    ///
+    /// Outputs are indexed in the order the component defines them, starting at 0.
+    /// Outputs connected to aliases or slices of the wire are included, since they drive it too.
+    pub fn wire_drivers(&self, wire: WireId) -> Result<Vec<(ComponentId, u8)>, InvalidWireIdError> {
+        let wire = self.wires.get(wire.0).ok_or(InvalidWireIdError)?;
+
+        let mut drivers = Vec::new();
+        for (_, _, output_state_offset) in wire.drivers(&self.wire_drivers) {
+            // Drivers left behind by components that failed to be added have no owner.
+            let Ok(index) = self
+                .output_state_owners
+                .binary_search_by_key(&output_state_offset.get(), |(offset, _, _)| offset.get())
+            else {
+                continue;
+            };
+
+            let (_, component_index, output_index) = self.output_state_owners[index];
+            let owner = (ComponentId(component_index), output_index);
+            if !drivers.contains(&owner) {
+                drivers.push(owner);
+            }
+        }
+
+        Ok(drivers)
+    }
+
+    /// Checks whether `a` and `b` hold the same state, comparing only as many bits as the
+    /// narrower of the two wires has
+    pub fn wires_equal(&mut self, a: WireId, b: WireId) -> Result<bool, InvalidWireIdError> {
+        let width_a = self.wires.get(a.0).ok_or(InvalidWireIdError)?.width;
+        let width_b = self.wires.get(b.0).ok_or(InvalidWireIdError)?.width;
+
+        self.sync_wire_states();
+        let state_a = self.read_wire_state(a)?;
+        let state_b = self.read_wire_state(b)?;
+        Ok(state_a.eq(&state_b, width_a.min(width_b)))
+    }
+
+    /// Gets the states of all wires in the simulation, reading back from the GPU only once
+    pub fn dump_all_wire_states(&mut self) -> Vec<(WireId, LogicState)> {
+        self.sync_wire_states();
 
-    bind_group: wgpu::BindGroup,
-    _wire_shader: wgpu::ShaderModule,
-    wire_pipeline: wgpu::ComputePipeline,
-    _component_shader: wgpu::ShaderModule,
-    component_pipeline: wgpu::ComputePipeline,
-    _reset_shader: wgpu::ShaderModule,
-    reset_pipeline: wgpu::ComputePipeline,
+        self.wires
+            .iter_indices()
+            .map(|wire| {
+                let wire = WireId(wire);
+                let state = self.read_wire_state(wire).expect("invalid wire ID");
+                (wire, state)
+            })
+            .collect()
+    }
 
-    staging_buffer: Option<wgpu::Buffer>,
-    wire_states_need_sync: bool,
-    memory_needs_sync: bool,
-}
+    /// Gets all wires whose state changed during the last run
+    pub fn changed_wires(&mut self) -> &[WireId] {
+        if self.changed_wires_need_sync {
+            let mut flags = vec![0u32; self.wires.len().div_ceil(u32::BITS) as usize];
+            gpu::read_buffer(
+                &self.changed_wires_buffer,
+                &mut flags,
+                &self.context.device,
+                &self.context.queue,
+                &mut self.staging_buffer,
+            );
+
+            self.changed_wires.clear();
+            for (word_index, mut word) in flags.into_iter().enumerate() {
+                while word != 0 {
+                    let wire_index = (word_index as u32) * u32::BITS + word.trailing_zeros();
+                    let wire_index = Index::new(wire_index).expect("invalid wire index");
+                    self.changed_wires.push(WireId(wire_index));
+                    word &= word - 1;
+                }
+            }
 
-impl Simulator {
-    fn sync_wire_states(&mut self) {
-        if self.wire_states_need_sync {
-            self.wire_states
-                .sync(&self.device, &self.queue, &mut self.staging_buffer);
-            self.wire_states_need_sync = false;
+            self.changed_wires_need_sync = false;
         }
+
+        &self.changed_wires
+    }
+
+    /// Takes a snapshot of all wire states at the end of the last run
+    ///
+    /// The snapshot is independent of the simulator, so it can be handed to another thread
+    /// and read from while the simulation advances. Taking another snapshot without running
+    /// the simulation in between is cheap and returns the same data. To follow the states
+    /// while a run is in progress, use [`wire_state_reader`](Self::wire_state_reader).
+    pub fn wire_state_snapshot(&mut self) -> WireStateSnapshot {
+        self.sync_wire_states();
+
+        self.wire_state_snapshot
+            .get_or_insert_with(|| WireStateSnapshot {
+                wires: self.wires.as_slice().into(),
+                states: self.wire_states.as_slice().into(),
+            })
+            .clone()
     }
 
-    fn sync_memory(&mut self) {
-        if self.memory_needs_sync {
-            self.memory
-                .sync(&self.device, &self.queue, &mut self.staging_buffer);
-            self.memory_needs_sync = false;
+    /// Creates a handle that reads the wire states of this simulation from other threads while it runs
+    ///
+    /// As long as a reader is alive, runs read the wire states back from the GPU after every
+    /// batch of steps into a back buffer and swap it with the front buffer the readers see.
+    /// Readers never wait for the GPU or a run, only for the swap itself, and always see the
+    /// consistent states of one batch boundary. All readers of a simulator share one front buffer.
+    pub fn wire_state_reader(&mut self) -> WireStateReader {
+        if let Some(reader) = &self.wire_state_reader {
+            return reader.clone();
         }
+
+        let reader = WireStateReader {
+            front: Arc::new(Mutex::new(self.wire_state_snapshot())),
+        };
+        self.wire_state_reader = Some(reader.clone());
+        reader
     }
 
-    wire_drive_fns!();
+    /// Swaps the synced wire states into the front buffer of the readers
+    fn swap_wire_state_buffers(&mut self) {
+        let Some(reader) = &self.wire_state_reader else {
+            return;
+        };
 
-    pub fn get_wire_state(&mut self, wire: WireId) -> Result<LogicState, InvalidWireIdError> {
-        self.sync_wire_states();
+        let states = self.wire_states.as_slice();
+        let back = self
+            .wire_state_back_buffer
+            .take()
+            .and_then(|mut back| {
+                // Readers may still hold on to the previous front buffer.
+                Arc::get_mut(&mut back)?.copy_from_slice(states);
+                Some(back)
+            })
+            .unwrap_or_else(|| states.into());
+
+        let mut front = reader.front.lock().unwrap();
+        self.wire_state_back_buffer = Some(std::mem::replace(&mut front.states, back));
+    }
+
+    /// Publishes the wire states at the end of the last batch to the readers, if there are any
+    fn publish_wire_states(&mut self) {
+        match &self.wire_state_reader {
+            Some(reader) if Arc::strong_count(&reader.front) > 1 => {
+                self.sync_wire_states();
+                self.swap_wire_state_buffers();
+            }
+            // All readers have been dropped.
+            Some(_) => {
+                self.wire_state_reader = None;
+                self.wire_state_back_buffer = None;
+            }
+            None => (),
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn publish_wire_states_async(&mut self) {
+        match &self.wire_state_reader {
+            Some(reader) if Arc::strong_count(&reader.front) > 1 => {
+                self.sync_wire_states_async().await;
+                self.swap_wire_state_buffers();
+            }
+            Some(_) => {
+                self.wire_state_reader = None;
+                self.wire_state_back_buffer = None;
+            }
+            None => (),
+        }
+    }
 
+    fn read_wire_state(&self, wire: WireId) -> Result<LogicState, InvalidWireIdError> {
         let wire = self.wires.get(wire.0).ok_or(InvalidWireIdError)?;
 
         let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
@@ -379,141 +2187,949 @@ impl Simulator {
         Ok(result)
     }
 
+    fn read_wire_bits(&self, wire: WireId) -> Result<Vec<LogicBitState>, InvalidWireIdError> {
+        let width = self.wires.get(wire.0).ok_or(InvalidWireIdError)?.width;
+        let state = self.read_wire_state(wire)?;
+        Ok((0..width).map(|bit| state.get_bit_state(bit)).collect())
+    }
+
     fn read_list_data(&mut self) -> ListData {
         let mut list_data = ListData::zeroed();
 
         gpu::read_buffer::<ListData>(
             &self.list_data_buffer,
             bytemuck::cast_slice_mut(slice::from_mut(&mut list_data)),
-            &self.device,
-            &self.queue,
+            &self.context.device,
+            &self.context.queue,
             &mut self.staging_buffer,
         );
 
         list_data
     }
 
+    #[cfg(feature = "wasm")]
+    async fn read_list_data_async(&mut self) -> ListData {
+        let mut list_data = ListData::zeroed();
+
+        gpu::read_buffer_async::<ListData>(
+            &self.list_data_buffer,
+            bytemuck::cast_slice_mut(slice::from_mut(&mut list_data)),
+            &self.context.device,
+            &self.context.queue,
+            &mut self.staging_buffer,
+        )
+        .await;
+
+        list_data
+    }
+
     fn first_tick(&mut self) {
-        self.queue.write_buffer(
+        // Resolving the wires first would overwrite the initial states before any component
+        // has seen them.
+        let skip_wires = std::mem::take(&mut self.initial_wire_states_pending);
+
+        // The wire shader only runs if a component changed, so at least one has to be reported
+        // even in a circuit without components.
+        self.context.queue.write_buffer(
             &self.list_data_buffer,
             0,
             bytemuck::bytes_of(&ListData {
                 wires_changed: 0,
-                components_changed: self.components.len(),
+                components_changed: self.components.len().max(1),
                 conflict_list_len: 0,
                 has_conflicts: 0,
+                step_count: 0,
             }),
         );
 
-        let mut encoder = self.device.create_command_encoder(&Default::default());
-        {
+        let mut encoder = self
+            .context
+            .device
+            .create_command_encoder(&Default::default());
+        encoder.clear_buffer(&self.changed_wires_buffer, 0, None);
+        if !skip_wires {
             let mut pass = encoder.begin_compute_pass(&Default::default());
             pass.set_bind_group(0, &self.bind_group, &[]);
-            pass.set_pipeline(&self.wire_pipeline);
-            pass.dispatch_workgroups(self.wires.len().div_ceil(WORKGROUP_SIZE), 1, 1);
+            pass.set_pipeline(&self.pipelines().wire_pipeline);
+            dispatch_per_item(&mut pass, self.wires.len(), self.pipelines().workgroup_size);
         }
-        self.queue.submit(Some(encoder.finish()));
-
-        self.queue.write_buffer(
+        self.context.queue.submit(Some(encoder.finish()));
+
+        // Without the wire pass the wires have to be resolved in the first step regardless
+        // of whether a component changes.
+        let components_changed = if skip_wires {
+            self.components.len().max(1)
+        } else {
+            0
+        };
+        self.context.queue.write_buffer(
             &self.list_data_buffer,
             0,
             bytemuck::bytes_of(&ListData {
                 wires_changed: self.wires.len(),
-                components_changed: 0,
+                components_changed,
                 conflict_list_len: 0,
                 has_conflicts: 0,
+                step_count: 0,
             }),
         );
 
-        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let mut encoder = self
+            .context
+            .device
+            .create_command_encoder(&Default::default());
         {
             let mut pass = encoder.begin_compute_pass(&Default::default());
             pass.set_bind_group(0, &self.bind_group, &[]);
-            pass.set_pipeline(&self.component_pipeline);
-            pass.dispatch_workgroups(self.components.len().div_ceil(WORKGROUP_SIZE), 1, 1);
+            pass.set_pipeline(&self.pipelines().component_pipeline);
+            dispatch_per_item(
+                &mut pass,
+                self.components.len(),
+                self.pipelines().workgroup_size,
+            );
         }
-        self.queue.submit(Some(encoder.finish()));
+        self.context.queue.submit(Some(encoder.finish()));
     }
 
-    pub fn run(&mut self, mut max_steps: u64) -> SimulationRunResult {
-        const RESET_WIRES_CHANGED: u32 = 0x1;
-        const RESET_COMPONENTS_CHANGED: u32 = 0x2;
+    fn update_buffers(&mut self) {
+        self.wire_states.update(&self.context.queue);
+        self.wire_drives.update(&self.context.queue);
+        self.wire_forces.update(&self.context.queue);
+        self.wire_drivers.update(&self.context.queue);
+        self.wires.update(&self.context.queue);
+
+        self.output_states.update(&self.context.queue);
+        self.outputs.update(&self.context.queue);
+        self.inputs.update(&self.context.queue);
+        self.memory.update(&self.context.queue);
+        self.components.update(&self.context.queue);
+    }
 
-        self.wire_states.update(&self.queue);
-        self.wire_drives.update(&self.queue);
-        self.wire_drivers.update(&self.queue);
-        self.wires.update(&self.queue);
+    /// The GPU is about to change the simulation state, so all copies of it have to be read again
+    fn mark_gpu_state_changed(&mut self) {
+        self.wire_states_need_sync = true;
+        self.wire_state_snapshot = None;
+        self.changed_wires_need_sync = true;
+    }
 
-        self.output_states.update(&self.queue);
-        self.outputs.update(&self.queue);
-        self.inputs.update(&self.queue);
-        self.memory.update(&self.queue);
-        self.components.update(&self.queue);
+    fn begin_run(&mut self) {
+        if let Some(run_profile) = &mut self.run_profile {
+            run_profile.samples.clear();
+        }
+        if let Some(pass_timer) = &mut self.pass_timer {
+            pass_timer.timings = PassTimings::default();
+        }
 
-        self.wire_states_need_sync = true;
-        self.memory_needs_sync = true;
+        self.restart_run();
+    }
 
+    /// Uploads all changes and evaluates the whole circuit again in the next step
+    fn restart_run(&mut self) {
+        self.update_buffers();
+        self.mark_gpu_state_changed();
         self.first_tick();
+    }
 
-        while max_steps > 0 {
-            let mut encoder = self.device.create_command_encoder(&Default::default());
+    /// Records the dispatches that resolve the wire states of one step
+    fn encode_wire_pass<'a>(&'a self, pass: &mut wgpu::ComputePass<'a>) {
+        pass.set_pipeline(&self.pipelines().reset_wires_pipeline);
+        pass.dispatch_workgroups(1, 1, 1);
 
-            {
-                let mut pass = encoder.begin_compute_pass(&Default::default());
+        pass.set_pipeline(&self.pipelines().wire_pipeline);
+        pass.dispatch_workgroups_indirect(&self.dispatch_args_buffer, DispatchArgs::WIRE_OFFSET);
+    }
+
+    /// Records the dispatches that evaluate the components of one step
+    fn encode_component_pass<'a>(&'a self, pass: &mut wgpu::ComputePass<'a>) {
+        pass.set_pipeline(&self.pipelines().reset_components_pipeline);
+        pass.dispatch_workgroups(1, 1, 1);
+
+        pass.set_pipeline(&self.pipelines().component_pipeline);
+        pass.dispatch_workgroups_indirect(
+            &self.dispatch_args_buffer,
+            DispatchArgs::COMPONENT_OFFSET,
+        );
+    }
+
+    /// Submits `batch_count` batches of steps without reading anything back
+    ///
+    /// Whether a step has anything left to do is decided on the GPU: once the simulation has
+    /// settled or a conflict was found, the reset shader sets up the indirect wire and component
+    /// dispatches without any workgroups, so the steps following that point cost next to
+    /// nothing and do not change the state.
+    #[cfg_attr(feature = "tracing-spans", tracing::instrument(skip(self, max_steps)))]
+    fn run_batches(&mut self, batch_count: u32, max_steps: &mut u64) {
+        self.mark_gpu_state_changed();
+
+        let step_count = (*max_steps).min((batch_count * STEPS_PER_BATCH) as u64) as u32;
+        *max_steps -= step_count as u64;
+
+        let mut encoder = self
+            .context
+            .device
+            .create_command_encoder(&Default::default());
+
+        if let Some(pass_timer) = &self.pass_timer {
+            // Timestamps can only be written at pass boundaries, so every step gets two passes.
+            for step in 0..step_count {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: None,
+                    timestamp_writes: Some(pass_timer.wire_pass_writes(step)),
+                });
                 pass.set_bind_group(0, &self.bind_group, &[]);
+                pass.set_bind_group(1, &self.dispatch_bind_group, &[]);
+                self.encode_wire_pass(&mut pass);
+                drop(pass);
+
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: None,
+                    timestamp_writes: Some(pass_timer.component_pass_writes(step)),
+                });
+                pass.set_bind_group(0, &self.bind_group, &[]);
+                pass.set_bind_group(1, &self.dispatch_bind_group, &[]);
+                self.encode_component_pass(&mut pass);
+            }
+
+            pass_timer.resolve(&mut encoder, step_count);
+        } else {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.set_bind_group(1, &self.dispatch_bind_group, &[]);
+
+            for _ in 0..step_count {
+                self.encode_wire_pass(&mut pass);
+                self.encode_component_pass(&mut pass);
+            }
+        }
 
-                for _ in 0..32 {
-                    pass.set_pipeline(&self.reset_pipeline);
-                    pass.set_push_constants(0, bytemuck::bytes_of(&RESET_WIRES_CHANGED));
-                    pass.dispatch_workgroups(1, 1, 1);
+        self.context.queue.submit(Some(encoder.finish()));
+
+        if let Some(pass_timer) = &mut self.pass_timer {
+            pass_timer.pending_steps = step_count;
+        }
 
-                    pass.set_pipeline(&self.wire_pipeline);
-                    pass.dispatch_workgroups(self.wires.len().div_ceil(WORKGROUP_SIZE), 1, 1);
+        self.run_batches_done.store(false, Ordering::Release);
+        let run_batches_done = Arc::clone(&self.run_batches_done);
+        self.context.queue.on_submitted_work_done(move || {
+            run_batches_done.store(true, Ordering::Release);
+        });
+    }
 
-                    pass.set_pipeline(&self.reset_pipeline);
-                    pass.set_push_constants(0, bytemuck::bytes_of(&RESET_COMPONENTS_CHANGED));
-                    pass.dispatch_workgroups(1, 1, 1);
+    /// Advances pending GPU work without blocking
+    ///
+    /// Returns whether the last batch of steps submitted by a run has finished.
+    /// This allows driving a pending `run_async` (with the `wasm` feature) from an event loop
+    /// that already polls the device, e.g. for rendering.
+    pub fn poll(&mut self) -> bool {
+        self.context.device.poll(wgpu::Maintain::Poll);
+        self.run_batches_done.load(Ordering::Acquire)
+    }
 
-                    pass.set_pipeline(&self.component_pipeline);
-                    pass.dispatch_workgroups(self.components.len().div_ceil(WORKGROUP_SIZE), 1, 1);
+    /// The number of conflicting wires in the conflict list
+    ///
+    /// The list may have overflowed, in which case only the wires that fit are reported.
+    fn reported_conflict_count(&self, list_data: &ListData) -> usize {
+        list_data.conflict_list_len.min(self.max_reported_conflicts) as usize
+    }
 
-                    max_steps -= 1;
-                    if max_steps == 0 {
-                        break;
-                    }
-                }
+    fn read_pass_timings(&mut self) {
+        if let Some(pass_timer) = &mut self.pass_timer {
+            let mut timestamps = vec![0u64; pass_timer.pending_timestamp_count()];
+            gpu::read_buffer(
+                &pass_timer.resolve_buffer,
+                &mut timestamps,
+                &self.context.device,
+                &self.context.queue,
+                &mut self.staging_buffer,
+            );
+
+            pass_timer.record(&timestamps, self.context.queue.get_timestamp_period());
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn read_pass_timings_async(&mut self) {
+        if let Some(pass_timer) = &mut self.pass_timer {
+            let mut timestamps = vec![0u64; pass_timer.pending_timestamp_count()];
+            gpu::read_buffer_async(
+                &pass_timer.resolve_buffer,
+                &mut timestamps,
+                &self.context.device,
+                &self.context.queue,
+                &mut self.staging_buffer,
+            )
+            .await;
+
+            pass_timer.record(&timestamps, self.context.queue.get_timestamp_period());
+        }
+    }
+
+    fn unknown_propagation_result(&self) -> SimulationRunResult {
+        let components = graph::find_unknown_propagation(
+            &self.inputs,
+            &self.outputs,
+            &self.components,
+            &self.wire_states,
+            &self.output_states,
+        );
+
+        if components.is_empty() {
+            SimulationRunResult::Ok
+        } else {
+            SimulationRunResult::UnknownPropagated {
+                components: components.into_boxed_slice(),
+            }
+        }
+    }
+
+    /// Produces the result of a run that settled, checking for unknown states in strict mode
+    fn check_unknown_propagation(&mut self) -> SimulationRunResult {
+        if !self.strict_unknown {
+            return SimulationRunResult::Ok;
+        }
+
+        self.sync_wire_states();
+        self.output_states.sync(
+            &self.context.device,
+            &self.context.queue,
+            &mut self.staging_buffer,
+        );
+        self.unknown_propagation_result()
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn check_unknown_propagation_async(&mut self) -> SimulationRunResult {
+        if !self.strict_unknown {
+            return SimulationRunResult::Ok;
+        }
+
+        self.sync_wire_states_async().await;
+        self.output_states
+            .sync_async(
+                &self.context.device,
+                &self.context.queue,
+                &mut self.staging_buffer,
+            )
+            .await;
+        self.unknown_propagation_result()
+    }
+
+    /// Records the counts read back after a batch of steps in the profile and the trace
+    fn record_list_data(&mut self, list_data: &ListData) {
+        #[cfg(feature = "tracing-spans")]
+        tracing::debug!(
+            step_count = list_data.step_count,
+            wires_changed = list_data.wires_changed,
+            components_changed = list_data.components_changed,
+            has_conflicts = list_data.has_conflicts != 0,
+            "read back list data",
+        );
+
+        if let Some(run_profile) = &mut self.run_profile {
+            run_profile.samples.push(RunProfileSample {
+                step_count: list_data.step_count,
+                wires_changed: list_data.wires_changed,
+                components_changed: list_data.components_changed,
+            });
+        }
+    }
+
+    /// Advances the simulation until all wires and components settle, or `max_steps` is reached
+    ///
+    /// A run continues from the state the previous one ended in. Carried over are the resolved
+    /// wire states, the outputs and internal state of all components, e.g. the count of a counter
+    /// or the last clock level seen by an edge triggered component, and the contents of memory.
+    /// Drives, forces and pulls are inputs, not simulated state, and stay until they are changed.
+    ///
+    /// Every component is evaluated in the first step, so a circuit without feedback and without
+    /// sequential components always settles to the same result for the same drives. Anything that
+    /// stores state, including loops such as latches, can depend on the previous runs.
+    /// Use [`run_fresh`](Self::run_fresh) to start from the state at build time instead.
+    #[cfg_attr(feature = "tracing-spans", tracing::instrument(skip(self)))]
+    pub fn run(&mut self, mut max_steps: u64) -> SimulationRunResult {
+        #[cfg(feature = "event-log")]
+        self.record_event(Event::Run { max_steps });
+
+        self.begin_run();
+
+        let mut batch_count = 1;
+        while max_steps > 0 {
+            self.run_batches(batch_count, &mut max_steps);
+            batch_count = (batch_count * 2).min(MAX_BATCHES_PER_READBACK);
+
+            let list_data = self.read_list_data();
+            self.max_steps_observed = self.max_steps_observed.max(list_data.step_count as u64);
+            self.record_list_data(&list_data);
+            self.read_pass_timings();
+            self.publish_wire_states();
+
+            if self.detect_conflicts && (list_data.has_conflicts != 0) {
+                let conflicting_wires = self.read_conflicting_wires(&list_data);
+                return SimulationRunResult::Err { conflicting_wires };
+            } else if (list_data.wires_changed == 0) && (list_data.components_changed == 0) {
+                return self.check_unknown_propagation();
             }
+        }
+
+        SimulationRunResult::MaxStepsReached
+    }
+
+    fn read_conflicting_wires(&mut self, list_data: &ListData) -> Box<[WireId]> {
+        let mut conflicting_wires =
+            vec![WireId::INVALID; self.reported_conflict_count(list_data)].into_boxed_slice();
+
+        gpu::read_buffer(
+            &self.conflict_list_buffer,
+            &mut conflicting_wires,
+            &self.context.device,
+            &self.context.queue,
+            &mut self.staging_buffer,
+        );
+
+        conflicting_wires
+    }
+
+    /// Like [`run`](Self::run), but calls `stimulus` before the first step and after every batch
+    /// of steps, so it can drive wires in response to the current state of the circuit
+    ///
+    /// If `stimulus` changes a drive or force, the whole circuit is evaluated again, exactly like
+    /// at the start of a run. The run only ends once the circuit has settled and a call to
+    /// `stimulus` changed nothing, or after `max_steps`.
+    ///
+    /// With the `event-log` feature, the run is recorded as one [`Event::Run`] per evaluation of
+    /// the circuit, each followed by the changes `stimulus` made to end it. Replaying such a log
+    /// reproduces the same states, but reports one result per recorded run.
+    #[cfg_attr(feature = "tracing-spans", tracing::instrument(skip(self, stimulus)))]
+    pub fn run_with_stimulus<F>(
+        &mut self,
+        mut max_steps: u64,
+        mut stimulus: F,
+    ) -> SimulationRunResult
+    where
+        F: FnMut(&mut Simulator),
+    {
+        stimulus(self);
+        self.begin_run();
+
+        // The steps taken since the circuit was last evaluated from the start
+        #[cfg(feature = "event-log")]
+        let mut segment_steps = 0;
 
-            self.queue.submit(Some(encoder.finish()));
+        while max_steps > 0 {
+            #[cfg(feature = "event-log")]
+            let remaining_steps = max_steps;
+            self.run_batches(1, &mut max_steps);
+            #[cfg(feature = "event-log")]
+            {
+                segment_steps += remaining_steps - max_steps;
+            }
 
             let list_data = self.read_list_data();
-            if list_data.has_conflicts != 0 {
+            self.max_steps_observed = self.max_steps_observed.max(list_data.step_count as u64);
+            self.record_list_data(&list_data);
+            self.read_pass_timings();
+            self.publish_wire_states();
+
+            if self.detect_conflicts && (list_data.has_conflicts != 0) {
+                let conflicting_wires = self.read_conflicting_wires(&list_data);
+                #[cfg(feature = "event-log")]
+                self.record_event(Event::Run {
+                    max_steps: segment_steps,
+                });
+                return SimulationRunResult::Err { conflicting_wires };
+            }
+
+            let settled = (list_data.wires_changed == 0) && (list_data.components_changed == 0);
+            #[cfg(feature = "event-log")]
+            let event_count = self.recorded_event_count();
+            if self.apply_stimulus(&mut stimulus) {
+                // The changes made by `stimulus` have to be replayed after the steps leading up to them.
+                #[cfg(feature = "event-log")]
+                {
+                    self.record_event_at(
+                        event_count,
+                        Event::Run {
+                            max_steps: segment_steps,
+                        },
+                    );
+                    segment_steps = 0;
+                }
+                self.restart_run();
+            } else if settled {
+                #[cfg(feature = "event-log")]
+                self.record_event(Event::Run {
+                    max_steps: segment_steps,
+                });
+                return self.check_unknown_propagation();
+            }
+        }
+
+        #[cfg(feature = "event-log")]
+        self.record_event(Event::Run {
+            max_steps: segment_steps,
+        });
+        SimulationRunResult::MaxStepsReached
+    }
+
+    /// Calls `stimulus` and reports whether it changed any drive or force
+    fn apply_stimulus<F>(&mut self, stimulus: &mut F) -> bool
+    where
+        F: FnMut(&mut Simulator),
+    {
+        let drives = self.wire_drives.as_slice().to_vec();
+        let forces = self.wire_forces.as_slice().to_vec();
+        let forced: Vec<_> = self
+            .wires
+            .as_slice()
+            .iter()
+            .map(|wire| wire.forced)
+            .collect();
+
+        stimulus(self);
+
+        (self.wire_drives.as_slice() != drives)
+            || (self.wire_forces.as_slice() != forces)
+            || self
+                .wires
+                .as_slice()
+                .iter()
+                .map(|wire| wire.forced)
+                .ne(forced)
+    }
+
+    /// Restores all simulated state with [`reset`](Self::reset), keeping the drives, and then
+    /// [`run`](Self::run)s the simulation
+    ///
+    /// Useful for applying independent test vectors, where nothing may leak from one to the next.
+    pub fn run_fresh(&mut self, max_steps: u64) -> SimulationRunResult {
+        self.reset();
+        self.run(max_steps)
+    }
+
+    /// Finds all wires whose drivers currently disagree, without advancing the simulation
+    ///
+    /// This resolves every wire once from the current component outputs and drives and
+    /// reports the conflicts, then restores the previous wire states. Always returns an
+    /// empty list if conflict detection is disabled.
+    pub fn check_conflicts(&mut self) -> Box<[WireId]> {
+        if !self.detect_conflicts {
+            return Box::new([]);
+        }
+
+        self.update_buffers();
+
+        self.context.queue.write_buffer(
+            &self.list_data_buffer,
+            0,
+            bytemuck::bytes_of(&ListData {
+                wires_changed: 0,
+                components_changed: self.components.len().max(1),
+                conflict_list_len: 0,
+                has_conflicts: 0,
+                step_count: 0,
+            }),
+        );
+
+        let create_backup_buffer = |buffer: &wgpu::Buffer| {
+            self.context.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: buffer.size(),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let wire_states_buffer = self.wire_states.gpu_buffer();
+        let wire_states_backup = create_backup_buffer(wire_states_buffer);
+        let changed_wires_backup = create_backup_buffer(&self.changed_wires_buffer);
+
+        let mut encoder = self
+            .context
+            .device
+            .create_command_encoder(&Default::default());
+        let wire_states_size = wire_states_buffer.size();
+        let changed_wires_size = self.changed_wires_buffer.size();
+        encoder.copy_buffer_to_buffer(
+            wire_states_buffer,
+            0,
+            &wire_states_backup,
+            0,
+            wire_states_size,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.changed_wires_buffer,
+            0,
+            &changed_wires_backup,
+            0,
+            changed_wires_size,
+        );
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.set_pipeline(&self.pipelines().wire_pipeline);
+            dispatch_per_item(&mut pass, self.wires.len(), self.pipelines().workgroup_size);
+        }
+        encoder.copy_buffer_to_buffer(
+            &wire_states_backup,
+            0,
+            wire_states_buffer,
+            0,
+            wire_states_size,
+        );
+        encoder.copy_buffer_to_buffer(
+            &changed_wires_backup,
+            0,
+            &self.changed_wires_buffer,
+            0,
+            changed_wires_size,
+        );
+        self.context.queue.submit(Some(encoder.finish()));
+
+        let list_data = self.read_list_data();
+        let mut conflicting_wires =
+            vec![WireId::INVALID; self.reported_conflict_count(&list_data)].into_boxed_slice();
+        gpu::read_buffer(
+            &self.conflict_list_buffer,
+            &mut conflicting_wires,
+            &self.context.device,
+            &self.context.queue,
+            &mut self.staging_buffer,
+        );
+
+        conflicting_wires.sort_by_key(|wire| wire.0);
+        conflicting_wires
+    }
+
+    /// Runs the simulation once for every vector and collects the states of `output_wires`
+    ///
+    /// Every vector holds one state per wire in `input_wires`, which are used as the wire drives.
+    /// The simulation is reset to its initial state before every vector, so the vectors do not
+    /// influence each other.
+    /// The output states stay on the GPU until all vectors have been run, so only a single
+    /// readback of them is required.
+    pub fn run_vectors(
+        &mut self,
+        input_wires: &[WireId],
+        vectors: &[Vec<LogicState>],
+        output_wires: &[WireId],
+        max_steps: u64,
+    ) -> Result<Vec<Vec<LogicState>>, RunVectorsError> {
+        if input_wires
+            .iter()
+            .any(|wire| self.wires.get(wire.0).is_none())
+        {
+            return Err(RunVectorsError::InvalidWireId);
+        }
+
+        let outputs = output_wires
+            .iter()
+            .map(|wire| self.wires.get(wire.0).copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(RunVectorsError::InvalidWireId)?;
+
+        let atom_size = std::mem::size_of::<LogicStateAtom>() as u64;
+        let atoms_per_vector: u32 = outputs
+            .iter()
+            .map(|wire| wire.width.div_ceil(LogicStateAtom::BITS))
+            .sum();
+        let atom_count = (atoms_per_vector as usize) * vectors.len();
+
+        let results_buffer = self.context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (atom_count.max(1) as u64) * atom_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let mut result_offset = 0;
+        for (vector_index, vector) in vectors.iter().enumerate() {
+            if vector.len() != input_wires.len() {
+                return Err(RunVectorsError::VectorLengthMismatch { vector_index });
+            }
+
+            self.reset_to_initial();
+            for (&wire, state) in input_wires.iter().zip(vector) {
+                self.set_wire_drive(wire, state).expect("invalid wire ID");
+            }
+
+            self.run(max_steps)
+                .into_result()
+                .map_err(|error| RunVectorsError::Simulation {
+                    vector_index,
+                    error,
+                })?;
+
+            let mut encoder = self
+                .context
+                .device
+                .create_command_encoder(&Default::default());
+            for wire in &outputs {
+                let state_offset = wire.state_offset.get().expect("invalid wire state offset");
+                let state_width = wire.width.div_ceil(LogicStateAtom::BITS) as u64;
+
+                encoder.copy_buffer_to_buffer(
+                    self.wire_states.gpu_buffer(),
+                    (state_offset as u64) * atom_size,
+                    &results_buffer,
+                    result_offset * atom_size,
+                    state_width * atom_size,
+                );
+                result_offset += state_width;
+            }
+            self.context.queue.submit(Some(encoder.finish()));
+        }
+
+        let mut atoms = vec![LogicStateAtom::HIGH_Z; atom_count];
+        gpu::read_buffer(
+            &results_buffer,
+            &mut atoms,
+            &self.context.device,
+            &self.context.queue,
+            &mut self.staging_buffer,
+        );
+
+        let results = (0..vectors.len())
+            .map(|vector_index| {
+                let start = vector_index * (atoms_per_vector as usize);
+                let mut vector_atoms = &atoms[start..(start + (atoms_per_vector as usize))];
+
+                outputs
+                    .iter()
+                    .map(|wire| {
+                        let state_width = wire.width.div_ceil(LogicStateAtom::BITS) as usize;
+                        let (state, rest) = vector_atoms.split_at(state_width);
+                        vector_atoms = rest;
+
+                        let mut result = LogicState::HIGH_Z;
+                        result.0[..state.len()].copy_from_slice(state);
+                        result
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Like [`run_fresh`](Self::run_fresh), but does not block while reading back from the GPU
+    #[cfg(feature = "wasm")]
+    pub async fn run_fresh_async(&mut self, max_steps: u64) -> SimulationRunResult {
+        self.reset();
+        self.run_async(max_steps).await
+    }
+
+    /// Like [`run`](Self::run), but does not block while reading back from the GPU
+    #[cfg(feature = "wasm")]
+    #[cfg_attr(feature = "tracing-spans", tracing::instrument(skip(self)))]
+    pub async fn run_async(&mut self, mut max_steps: u64) -> SimulationRunResult {
+        #[cfg(feature = "event-log")]
+        self.record_event(Event::Run { max_steps });
+
+        self.begin_run();
+
+        let mut batch_count = 1;
+        while max_steps > 0 {
+            self.run_batches(batch_count, &mut max_steps);
+            batch_count = (batch_count * 2).min(MAX_BATCHES_PER_READBACK);
+
+            let list_data = self.read_list_data_async().await;
+            self.max_steps_observed = self.max_steps_observed.max(list_data.step_count as u64);
+            self.record_list_data(&list_data);
+            self.read_pass_timings_async().await;
+            self.publish_wire_states_async().await;
+
+            if self.detect_conflicts && (list_data.has_conflicts != 0) {
                 let mut conflicting_wires =
-                    vec![WireId::INVALID; list_data.conflict_list_len as usize].into_boxed_slice();
+                    vec![WireId::INVALID; self.reported_conflict_count(&list_data)]
+                        .into_boxed_slice();
 
-                gpu::read_buffer(
+                gpu::read_buffer_async(
                     &self.conflict_list_buffer,
                     &mut conflicting_wires,
-                    &self.device,
-                    &self.queue,
+                    &self.context.device,
+                    &self.context.queue,
                     &mut self.staging_buffer,
-                );
+                )
+                .await;
 
                 return SimulationRunResult::Err { conflicting_wires };
             } else if (list_data.wires_changed == 0) && (list_data.components_changed == 0) {
-                return SimulationRunResult::Ok;
+                return self.check_unknown_propagation_async().await;
             }
         }
 
         SimulationRunResult::MaxStepsReached
     }
 
+    /// Gets the highest number of steps any run took to settle since the simulator was created
+    /// or [`reset_stats`](Self::reset_stats) was last called
+    ///
+    /// Runs that did not settle count with the number of steps they executed.
+    #[inline]
+    pub fn max_steps_observed(&self) -> u64 {
+        self.max_steps_observed
+    }
+
+    /// Gets the convergence data of the last run
+    ///
+    /// Returns `None` if profiling was not enabled with [`SimulatorBuilder::enable_profiling`].
+    #[inline]
+    pub fn last_run_profile(&self) -> Option<&RunProfile> {
+        self.run_profile.as_ref()
+    }
+
+    /// Gets the GPU time spent in the wire and component passes during the last run
+    ///
+    /// Returns `None` if GPU timing was not enabled with [`SimulatorBuilder::enable_gpu_timing`].
+    #[inline]
+    pub fn last_pass_timings(&self) -> Option<PassTimings> {
+        self.pass_timer
+            .as_ref()
+            .map(|pass_timer| pass_timer.timings)
+    }
+
+    /// Resets the statistics collected across runs
+    #[inline]
+    pub fn reset_stats(&mut self) {
+        self.max_steps_observed = 0;
+    }
+
+    /// Restores all wires to high-Z and all components to their state at build time
     pub fn reset(&mut self) {
+        #[cfg(feature = "event-log")]
+        self.record_event(Event::Reset);
+
+        self.wire_states.clear();
+        self.reset_components();
+        self.initial_wire_states_pending = false;
+    }
+
+    /// Like [`reset`](Self::reset), but restores the wires to the states set with
+    /// [`SimulatorBuilder::set_initial_wire_state`] instead of high-Z
+    pub fn reset_to_initial(&mut self) {
+        #[cfg(feature = "event-log")]
+        self.record_event(Event::ResetToInitial);
+
         self.wire_states.reset();
+        self.reset_components();
+        self.initial_wire_states_pending = self.has_initial_wire_states;
+    }
+
+    /// Restores only the given wires to high-Z, leaving all other wires and all components untouched
+    ///
+    /// The drives of the wires are kept, so the next run resolves them again from their drivers.
+    /// No wire is reset if any of the IDs is invalid.
+    pub fn reset_wires(&mut self, wires: &[WireId]) -> Result<(), InvalidWireIdError> {
+        #[cfg(feature = "event-log")]
+        let event = Event::ResetWires {
+            wires: wires.to_vec(),
+        };
+
+        let wires = wires
+            .iter()
+            .map(|wire| self.wires.get(wire.0).ok_or(InvalidWireIdError).copied())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for wire in wires {
+            let state_width = wire.width.div_ceil(LogicStateAtom::BITS);
+            self.wire_states
+                .clear_range(&self.context.queue, wire.state_offset, state_width)
+                .expect("invalid wire state offset");
+        }
+        self.wire_state_snapshot = None;
+
+        #[cfg(feature = "event-log")]
+        self.record_event(event);
+        Ok(())
+    }
+
+    /// Changes the constant `component` was built with, without rebuilding the simulator
+    ///
+    /// This applies to [`CompareConstPorts`], where only as many bits of `value` are used as the
+    /// input of the comparator is wide. The new constant is seen from the next run on and is kept
+    /// by [`reset`](Self::reset) and [`reset_to_initial`](Self::reset_to_initial).
+    pub fn set_component_constant(
+        &mut self,
+        component: ComponentId,
+        value: &LogicState,
+    ) -> Result<(), SetComponentConstantError> {
+        #[cfg(feature = "event-log")]
+        let event = Event::SetComponentConstant {
+            component,
+            value: value.clone(),
+        };
+
+        let component = self
+            .components
+            .get(component.0)
+            .ok_or(SetComponentConstantError::InvalidComponentId)?;
+        if component.kind != ComponentKind::CmpConst {
+            return Err(SetComponentConstantError::NoConstant);
+        }
+
+        let atoms = &value.0[..(component.memory_size as usize)];
+        self.memory
+            .write_initial_range(&self.context.queue, component.memory_offset, atoms)
+            .expect("invalid component memory offset");
+
+        #[cfg(feature = "event-log")]
+        self.record_event(event);
+        Ok(())
+    }
+
+    fn reset_components(&mut self) {
         self.output_states.reset();
         self.memory.reset();
 
         self.wire_states_need_sync = false;
-        self.memory_needs_sync = false;
+        self.wire_state_snapshot = None;
+        self.changed_wires.clear();
+        self.changed_wires_need_sync = false;
+    }
+}
+
+/// A copy of the state of every wire in a simulation, taken between two runs
+/// or at a batch boundary by a [`WireStateReader`]
+#[derive(Debug, Clone)]
+pub struct WireStateSnapshot {
+    wires: Arc<[Wire]>,
+    states: Arc<[LogicStateAtom]>,
+}
+
+impl WireStateSnapshot {
+    /// Gets the state `wire` had when the snapshot was taken
+    pub fn get_wire_state(&self, wire: WireId) -> Result<LogicState, InvalidWireIdError> {
+        let wire = wire
+            .0
+            .get()
+            .and_then(|index| self.wires.get(index as usize))
+            .ok_or(InvalidWireIdError)?;
+
+        let state_width = wire.width.div_ceil(LogicStateAtom::BITS) as usize;
+        let state_offset = wire.state_offset.get().expect("invalid wire state offset") as usize;
+        let state = &self.states[state_offset..(state_offset + state_width)];
+
+        let mut result = LogicState::HIGH_Z;
+        result.0[..state.len()].copy_from_slice(state);
+        Ok(result)
+    }
+}
+
+/// Reads the wire states of a simulation from another thread, see [`Simulator::wire_state_reader`]
+#[derive(Debug, Clone)]
+pub struct WireStateReader {
+    front: Arc<Mutex<WireStateSnapshot>>,
+}
+
+impl WireStateReader {
+    /// Gets the wire states at the last batch boundary the simulation published
+    ///
+    /// The snapshot stays valid while the simulation advances.
+    pub fn snapshot(&self) -> WireStateSnapshot {
+        self.front.lock().unwrap().clone()
+    }
+
+    /// Gets the state `wire` had at the last batch boundary the simulation published
+    pub fn get_wire_state(&self, wire: WireId) -> Result<LogicState, InvalidWireIdError> {
+        self.front.lock().unwrap().get_wire_state(wire)
     }
 }
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use crate::{MAX_WIRE_WIDTH, MIN_WIRE_WIDTH};
+use crate::{LOGIC_STATE_WORD_COUNT, MAX_WIRE_WIDTH, MIN_WIRE_WIDTH};
 use bytemuck::{Pod, Zeroable};
 use std::fmt::{self, Write};
 
@@ -97,6 +97,116 @@ impl LogicBitState {
             LogicBitState::Logic1 => '1',
         }
     }
+
+    /// Logical AND, where a `Logic0` input dominates and `HighZ` inputs are treated as `Undefined`
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicBitState;
+    ///
+    /// assert_eq!(LogicBitState::Logic0.and(LogicBitState::HighZ), LogicBitState::Logic0);
+    /// assert_eq!(LogicBitState::Logic1.and(LogicBitState::HighZ), LogicBitState::Undefined);
+    /// assert_eq!(LogicBitState::Logic1 & LogicBitState::Logic1, LogicBitState::Logic1);
+    /// ```
+    #[inline]
+    pub const fn and(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (Self::Logic0, _) | (_, Self::Logic0) => Self::Logic0,
+            (Self::Logic1, Self::Logic1) => Self::Logic1,
+            _ => Self::Undefined,
+        }
+    }
+
+    /// Logical OR, where a `Logic1` input dominates and `HighZ` inputs are treated as `Undefined`
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicBitState;
+    ///
+    /// assert_eq!(LogicBitState::HighZ.or(LogicBitState::Logic1), LogicBitState::Logic1);
+    /// assert_eq!(LogicBitState::HighZ.or(LogicBitState::Logic0), LogicBitState::Undefined);
+    /// assert_eq!(LogicBitState::Logic0 | LogicBitState::Logic0, LogicBitState::Logic0);
+    /// ```
+    #[inline]
+    pub const fn or(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (Self::Logic1, _) | (_, Self::Logic1) => Self::Logic1,
+            (Self::Logic0, Self::Logic0) => Self::Logic0,
+            _ => Self::Undefined,
+        }
+    }
+
+    /// Logical XOR, which is `Undefined` unless both inputs are `Logic0` or `Logic1`
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicBitState;
+    ///
+    /// assert_eq!(LogicBitState::Logic1.xor(LogicBitState::Logic0), LogicBitState::Logic1);
+    /// assert_eq!(LogicBitState::Logic1.xor(LogicBitState::Undefined), LogicBitState::Undefined);
+    /// assert_eq!(LogicBitState::Logic1 ^ LogicBitState::Logic1, LogicBitState::Logic0);
+    /// ```
+    #[inline]
+    pub const fn xor(self, rhs: Self) -> Self {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(lhs), Some(rhs)) => Self::from_bool(lhs ^ rhs),
+            _ => Self::Undefined,
+        }
+    }
+
+    /// Logical NOT, which is `Undefined` for `HighZ` and `Undefined` inputs
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicBitState;
+    ///
+    /// assert_eq!(LogicBitState::Logic0.not(), LogicBitState::Logic1);
+    /// assert_eq!(LogicBitState::HighZ.not(), LogicBitState::Undefined);
+    /// assert_eq!(!LogicBitState::Logic1, LogicBitState::Logic0);
+    /// ```
+    #[inline]
+    pub const fn not(self) -> Self {
+        match self.to_bool() {
+            Some(value) => Self::from_bool(!value),
+            None => Self::Undefined,
+        }
+    }
+}
+
+impl std::ops::BitAnd for LogicBitState {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.and(rhs)
+    }
+}
+
+impl std::ops::BitOr for LogicBitState {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.or(rhs)
+    }
+}
+
+impl std::ops::BitXor for LogicBitState {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.xor(rhs)
+    }
+}
+
+impl std::ops::Not for LogicBitState {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self::Output {
+        LogicBitState::not(self)
+    }
 }
 
 impl From<bool> for LogicBitState {
@@ -201,6 +311,35 @@ impl LogicStateAtom {
         valid: 0xFFFFFFFF,
     };
 
+    /// Creates a new atom from its raw state and valid bit planes
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::{LogicBitState, LogicStateAtom};
+    ///
+    /// let atom = LogicStateAtom::new(0b0110, 0b0011);
+    /// assert_eq!(atom.get_bit_state(0), LogicBitState::Logic0);
+    /// assert_eq!(atom.get_bit_state(1), LogicBitState::Logic1);
+    /// assert_eq!(atom.get_bit_state(2), LogicBitState::Undefined);
+    /// assert_eq!(atom.get_bit_state(3), LogicBitState::HighZ);
+    /// ```
+    #[inline]
+    pub const fn new(state: u32, valid: u32) -> Self {
+        Self { state, valid }
+    }
+
+    /// The raw state bit plane of this atom
+    #[inline]
+    pub const fn state(self) -> u32 {
+        self.state
+    }
+
+    /// The raw valid bit plane of this atom
+    #[inline]
+    pub const fn valid(self) -> u32 {
+        self.valid
+    }
+
     #[inline]
     pub const fn from_int(value: u32) -> Self {
         Self {
@@ -258,8 +397,9 @@ impl LogicStateAtom {
         })
     }
 
+    /// Gets the logic state of a single bit
     #[inline]
-    const fn get_bit_state(&self, bit_index: u32) -> LogicBitState {
+    pub const fn get_bit_state(&self, bit_index: u32) -> LogicBitState {
         let state_bit = ((self.state >> bit_index) & 0x1) > 0;
         let valid_bit = ((self.valid >> bit_index) & 0x1) > 0;
         LogicBitState::from_bits(state_bit, valid_bit)
@@ -277,37 +417,59 @@ impl fmt::Display for LogicStateAtom {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum FromIntError {
+    /// The width was not between 1 and `MAX_WIRE_WIDTH` inclusive
+    InvalidWidth,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FromBigIntError {
-    /// The number of words was not between 1 and 8 inclusive
+    /// The number of words was not between 1 and [`LOGIC_STATE_WORD_COUNT`] inclusive
     InvalidWordCount,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum FromPlanesError {
+    /// The number of bytes was not between 1 and `MAX_WIRE_WIDTH / 8` inclusive
+    InvalidByteCount,
+    /// The state and valid planes have a different number of bytes
+    LengthMismatch,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FromBitsError {
-    /// The number of bits was not between 1 and 256 inclusive
+    /// The number of bits was not between 1 and `MAX_WIRE_WIDTH` inclusive
     InvalidWidth,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
-    /// The string contained a character other than `x`, `X`, `z`, `Z`, `0` or `1`
+    /// The string contained a character other than `x`, `X`, `z`, `Z`, `0` or `1`,
+    /// or for hex strings other than hex digits, `x`, `X`, `z`, `Z` or `_`
     IllegalCharacter(u8),
-    /// The number of bits was not between 1 and 256 inclusive
+    /// The number of bits was not between 1 and `MAX_WIRE_WIDTH` inclusive
     InvalidWidth,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ToIntError {
-    /// The width was not between 1 and 256 inclusive
+    /// The width was not between 1 and `MAX_WIRE_WIDTH` inclusive
     InvalidWidth,
     /// The first `width` bits of the logic state are not representable by an integer
     Unrepresentable,
+    /// The value does not fit into the requested integer type
+    Overflow,
 }
 
-const MAX_ATOM_COUNT: usize = (MAX_WIRE_WIDTH / LogicStateAtom::BITS) as usize;
+const MAX_ATOM_COUNT: usize = LOGIC_STATE_WORD_COUNT;
 
 /// A `MAX_WIRE_WIDTH` bit wide logic state
+///
+/// Methods that return a `Result` reject a `width` of 0 with an `InvalidWidth` error,
+/// since there is no value to convert. All other methods treat it as an empty range of bits:
+/// comparisons of 0 bits succeed, formatting them produces an empty string and states created
+/// with a width of 0 are all Z. A `width` larger than [`MAX_WIRE_WIDTH`] always panics.
 #[derive(Debug, Clone)]
 #[repr(transparent)]
 pub struct LogicState(pub(crate) [LogicStateAtom; MAX_ATOM_COUNT]);
@@ -328,18 +490,47 @@ impl LogicState {
     /// Creates a new logic state representing the given integer value
     ///
     /// Bits past the first 32 are assigned the value 0
+    ///
+    /// **Note:** the value is never sign- or otherwise extended, so on wires wider than 32 bits
+    /// all upper bits are 0. Use [`from_u128`](Self::from_u128) or
+    /// [`from_big_int`](Self::from_big_int) for wider values.
     #[inline]
     pub const fn from_int(value: u32) -> Self {
-        Self([
-            LogicStateAtom::from_int(value),
-            LogicStateAtom::LOGIC_0,
-            LogicStateAtom::LOGIC_0,
-            LogicStateAtom::LOGIC_0,
-            LogicStateAtom::LOGIC_0,
-            LogicStateAtom::LOGIC_0,
-            LogicStateAtom::LOGIC_0,
-            LogicStateAtom::LOGIC_0,
-        ])
+        let mut atoms = [LogicStateAtom::LOGIC_0; MAX_ATOM_COUNT];
+        atoms[0] = LogicStateAtom::from_int(value);
+        Self(atoms)
+    }
+
+    /// Creates a new logic state representing the first `width` bits of the given integer value
+    ///
+    /// Bits past `width` are assigned the value 0
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::from_u128(u64::MAX as u128, 64).unwrap();
+    /// assert_eq!(state.to_big_int::<Vec<_>>(64).unwrap(), [u32::MAX, u32::MAX]);
+    ///
+    /// let state = LogicState::from_u128(0xFF, 4).unwrap();
+    /// assert_eq!(state.to_int(8).unwrap(), 0x0F);
+    /// ```
+    pub fn from_u128(value: u128, width: u32) -> Result<Self, FromIntError> {
+        if !(MIN_WIRE_WIDTH..=MAX_WIRE_WIDTH).contains(&width) {
+            return Err(FromIntError::InvalidWidth);
+        }
+
+        let value = if width < u128::BITS {
+            value & ((1 << width) - 1)
+        } else {
+            value
+        };
+
+        let mut this = Self::LOGIC_0;
+        for (i, dst) in this.0.iter_mut().take(4).enumerate() {
+            dst.state = (value >> (i as u32 * LogicStateAtom::BITS)) as u32;
+        }
+        Ok(this)
     }
 
     /// Creates a new logic state representing the given boolean value
@@ -365,8 +556,41 @@ impl LogicState {
         }
     }
 
+    /// Creates a new logic state from its state and valid bit planes
+    ///
+    /// The planes are given as little endian bytes and use the same encoding as [`LogicStateAtom`],
+    /// so every bit keeps its exact value including X and Z. Bits past the end are assigned the value Z
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::from_planes(&[0b0110], &[0b0011]).unwrap();
+    /// assert_eq!(state.to_string(4), "ZX10");
+    /// ```
+    pub fn from_planes(state: &[u8], valid: &[u8]) -> Result<Self, FromPlanesError> {
+        if state.len() != valid.len() {
+            return Err(FromPlanesError::LengthMismatch);
+        }
+        if !(1..=(MAX_WIRE_WIDTH / u8::BITS) as usize).contains(&state.len()) {
+            return Err(FromPlanesError::InvalidByteCount);
+        }
+
+        let mut atoms = [LogicStateAtom::HIGH_Z; MAX_ATOM_COUNT];
+        for (i, (&state, &valid)) in state.iter().zip(valid).enumerate() {
+            let atom = &mut atoms[i / 4];
+            let shift = (i % 4) * 8;
+            atom.state |= (state as u32) << shift;
+            atom.valid |= (valid as u32) << shift;
+        }
+
+        Ok(Self(atoms))
+    }
+
     /// Creates a new logic state from the given bits (most significant bit first)
     ///
+    /// The last bit in the slice becomes bit 0 of the state, so the bits read the same way as a
+    /// written number. Use [`from_bits_lsb_first`](Self::from_bits_lsb_first) for the opposite order.
     /// Bits past the specified ones are implicitely assigned the value Z
     ///
     /// ### Example:
@@ -414,8 +638,80 @@ impl LogicState {
         Ok(Self(atoms))
     }
 
+    /// Creates a new logic state from the given bits (least significant bit first)
+    ///
+    /// The first bit in the slice becomes bit 0 of the state.
+    /// Bits past the specified ones are implicitely assigned the value Z
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::{LogicState, LogicBitState};
+    ///
+    /// let state = LogicState::from_bits_lsb_first(&[
+    ///     LogicBitState::Logic1,
+    ///     LogicBitState::Logic0,
+    ///     LogicBitState::Undefined,
+    ///     LogicBitState::HighZ,
+    /// ]).unwrap();
+    /// assert_eq!(state.to_string(5), "ZZX01");
+    /// ```
+    pub fn from_bits_lsb_first(bits: &[LogicBitState]) -> Result<Self, FromBitsError> {
+        if !((MIN_WIRE_WIDTH as usize)..=(MAX_WIRE_WIDTH as usize)).contains(&bits.len()) {
+            return Err(FromBitsError::InvalidWidth);
+        }
+
+        let mut state = Self::HIGH_Z;
+        for (i, &bit) in bits.iter().enumerate() {
+            state.set_bit_state(i as u32, bit);
+        }
+
+        Ok(state)
+    }
+
+    /// Creates a new logic state with the first `width` bits set to `bit`
+    ///
+    /// Bits past `width` are assigned the value Z
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::{LogicState, LogicBitState};
+    ///
+    /// let state = LogicState::splat(LogicBitState::Logic1, 4);
+    /// assert_eq!(state.to_string(6), "ZZ1111");
+    ///
+    /// let state = LogicState::splat(LogicBitState::Undefined, 3);
+    /// assert_eq!(state.to_string(4), "ZXXX");
+    /// ```
+    pub fn splat(bit: LogicBitState, width: u32) -> Self {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        let (state, valid) = bit.to_bits();
+        let atom = LogicStateAtom {
+            state: if state { u32::MAX } else { 0 },
+            valid: if valid { u32::MAX } else { 0 },
+        };
+
+        let mut atoms = [LogicStateAtom::HIGH_Z; MAX_ATOM_COUNT];
+
+        let full_count = (width / LogicStateAtom::BITS) as usize;
+        atoms[..full_count].fill(atom);
+
+        let tail_width = width % LogicStateAtom::BITS;
+        if tail_width > 0 {
+            let mask = (1 << tail_width) - 1;
+            atoms[full_count] = LogicStateAtom {
+                state: atom.state & mask,
+                valid: atom.valid & mask,
+            };
+        }
+
+        Self(atoms)
+    }
+
     /// Constructs a logic state from a string of bits (most significant bit first)
     ///
+    /// The last character becomes bit 0 of the state, so the string reads the same way as a
+    /// written number. Use [`parse_lsb_first`](Self::parse_lsb_first) for the opposite order.
     /// Bits past the specified ones are implicitely assigned the value Z
     ///
     /// ### Example:
@@ -460,6 +756,118 @@ impl LogicState {
         Ok(Self(atoms))
     }
 
+    /// Constructs a logic state from a string of bits (least significant bit first)
+    ///
+    /// The first character becomes bit 0 of the state.
+    /// Bits past the specified ones are implicitely assigned the value Z
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::parse_lsb_first("10XZ").unwrap();
+    /// assert_eq!(state.to_string(5), "ZZX01");
+    /// ```
+    pub fn parse_lsb_first(s: &str) -> Result<Self, ParseError> {
+        let s = s.as_bytes();
+
+        if !((MIN_WIRE_WIDTH as usize)..=(MAX_WIRE_WIDTH as usize)).contains(&s.len()) {
+            return Err(ParseError::InvalidWidth);
+        }
+
+        let mut state = Self::HIGH_Z;
+        for (i, &c) in s.iter().enumerate() {
+            let bit = LogicBitState::parse_byte(c).ok_or(ParseError::IllegalCharacter(c))?;
+            state.set_bit_state(i as u32, bit);
+        }
+
+        Ok(state)
+    }
+
+    /// Constructs a logic state from a string of hex digits (most significant digit first)
+    ///
+    /// Every digit sets four bits, `x`/`X` and `z`/`Z` set all four bits to X or Z.
+    /// Underscores are ignored and can be used to group digits.
+    /// Bits past the specified ones are implicitely assigned the value Z
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::from_hex_string("DEAD_BEEF").unwrap();
+    /// assert_eq!(state.to_int(32).unwrap(), 0xDEAD_BEEF);
+    ///
+    /// let state = LogicState::from_hex_string("Xa").unwrap();
+    /// assert_eq!(state.to_string(9), "ZXXXX1010");
+    /// ```
+    pub const fn from_hex_string(s: &str) -> Result<Self, ParseError> {
+        let s = s.as_bytes();
+
+        let mut atoms = [LogicStateAtom::HIGH_Z; MAX_ATOM_COUNT];
+        let mut width = 0;
+        let mut i = s.len();
+        while i > 0 {
+            i -= 1;
+
+            let (state, valid) = match s[i] {
+                b'_' => continue,
+                c @ b'0'..=b'9' => ((c - b'0') as u32, 0xF),
+                c @ b'a'..=b'f' => ((c - b'a' + 10) as u32, 0xF),
+                c @ b'A'..=b'F' => ((c - b'A' + 10) as u32, 0xF),
+                b'x' | b'X' => (0xF, 0x0),
+                b'z' | b'Z' => (0x0, 0x0),
+                c => return Err(ParseError::IllegalCharacter(c)),
+            };
+
+            if width >= MAX_WIRE_WIDTH {
+                return Err(ParseError::InvalidWidth);
+            }
+
+            let index = (width / LogicStateAtom::BITS) as usize;
+            let shift = width % LogicStateAtom::BITS;
+            atoms[index].state |= state << shift;
+            atoms[index].valid |= valid << shift;
+            width += 4;
+        }
+
+        if width < MIN_WIRE_WIDTH {
+            return Err(ParseError::InvalidWidth);
+        }
+
+        Ok(Self(atoms))
+    }
+
+    /// Constructs a logic state of `width` bits from a string of at most `width` bits
+    /// (most significant bit first)
+    ///
+    /// Missing bits at the most significant end are filled with `pad`.
+    /// Bits past `width` are implicitely assigned the value Z.
+    /// Fails with [`ParseError::InvalidWidth`] if the string is longer than `width`.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::{LogicBitState, LogicState};
+    ///
+    /// let state = LogicState::parse_sized("1X", 4, LogicBitState::Logic0).unwrap();
+    /// assert_eq!(state.to_string(5), "Z001X");
+    ///
+    /// let state = LogicState::parse_sized("", 2, LogicBitState::HighZ).unwrap();
+    /// assert_eq!(state.to_string(2), "ZZ");
+    /// ```
+    pub fn parse_sized(s: &str, width: u32, pad: LogicBitState) -> Result<Self, ParseError> {
+        if !(MIN_WIRE_WIDTH..=MAX_WIRE_WIDTH).contains(&width) || (s.len() > (width as usize)) {
+            return Err(ParseError::InvalidWidth);
+        }
+
+        let mut padded = String::with_capacity(width as usize);
+        for _ in s.len()..(width as usize) {
+            padded.push(pad.to_char());
+        }
+        padded.push_str(s);
+
+        Self::parse(&padded)
+    }
+
     /// Converts the first `width` bits of the logic state into an integer
     ///
     /// ### Example:
@@ -484,6 +892,37 @@ impl LogicState {
         }
     }
 
+    /// Converts the first `width` bits of the logic state into an index
+    ///
+    /// `width` may exceed the size of `usize`, as long as all bits past it are 0.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::{LogicState, ToIntError};
+    ///
+    /// let state = LogicState::from_big_int(&[5, 0, 0, 0]).unwrap();
+    /// assert_eq!(state.to_usize(128), Ok(5));
+    ///
+    /// let state = LogicState::from_big_int(&[5, 0, 1, 0]).unwrap();
+    /// assert_eq!(state.to_usize(128), Err(ToIntError::Overflow));
+    /// assert_eq!(LogicState::UNDEFINED.to_usize(8), Err(ToIntError::Unrepresentable));
+    /// ```
+    pub fn to_usize(&self, width: u32) -> Result<usize, ToIntError> {
+        const USIZE_WORDS: usize = (usize::BITS / u32::BITS) as usize;
+
+        let words = self.to_words(width)?;
+        let word_count = width.div_ceil(LogicStateAtom::BITS) as usize;
+        let (low, high) = words[..word_count].split_at(word_count.min(USIZE_WORDS));
+        if high.iter().any(|&word| word != 0) {
+            return Err(ToIntError::Overflow);
+        }
+
+        Ok(low
+            .iter()
+            .enumerate()
+            .fold(0, |value, (i, &word)| value | ((word as usize) << (i * 32))))
+    }
+
     /// Converts the first bit of the logic state into a boolean
     ///
     /// ### Example:
@@ -528,34 +967,155 @@ impl LogicState {
             .collect()
     }
 
+    /// Converts the first `width` bits of the logic state into integer words
+    ///
+    /// Integer words are given in little endian order, bits past `width` are zeroed
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::from_big_int(&[u32::MAX, u32::MAX]).unwrap();
+    /// let words = state.to_words(40).unwrap();
+    /// assert_eq!(words[..3], [u32::MAX, 0xFF, 0]);
+    /// ```
+    pub fn to_words(&self, width: u32) -> Result<[u32; MAX_ATOM_COUNT], ToIntError> {
+        if !(MIN_WIRE_WIDTH..=MAX_WIRE_WIDTH).contains(&width) {
+            return Err(ToIntError::InvalidWidth);
+        }
+
+        let word_count = width.div_ceil(LogicStateAtom::BITS) as usize;
+
+        let last_index = (width / LogicStateAtom::BITS) as usize;
+        let last_width = width % LogicStateAtom::BITS;
+        let last_mask = ((1u64 << last_width) - 1) as u32;
+
+        let mut words = [0; MAX_ATOM_COUNT];
+        for (i, (word, atom)) in words.iter_mut().zip(&self.0[..word_count]).enumerate() {
+            let mask = if i == last_index { last_mask } else { u32::MAX };
+
+            if (atom.valid & mask) != mask {
+                return Err(ToIntError::Unrepresentable);
+            }
+
+            *word = atom.state & mask;
+        }
+
+        Ok(words)
+    }
+
+    /// Gets the state and valid bit planes of the first `width` bits as little endian bytes
+    ///
+    /// This is the inverse of [`from_planes`](Self::from_planes). Bits past `width`
+    /// in the last byte are cleared in both planes, which makes them Z.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::parse("ZX10").unwrap();
+    /// assert_eq!(state.to_planes(4), (vec![0b0110], vec![0b0011]));
+    /// ```
+    pub fn to_planes(&self, width: u32) -> (Vec<u8>, Vec<u8>) {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        let byte_count = width.div_ceil(u8::BITS) as usize;
+        let mut state: Vec<u8> = self
+            .0
+            .iter()
+            .flat_map(|atom| atom.state.to_le_bytes())
+            .take(byte_count)
+            .collect();
+        let mut valid: Vec<u8> = self
+            .0
+            .iter()
+            .flat_map(|atom| atom.valid.to_le_bytes())
+            .take(byte_count)
+            .collect();
+
+        let tail_width = width % u8::BITS;
+        if tail_width > 0 {
+            let mask = (1 << tail_width) - 1;
+            state[byte_count - 1] &= mask;
+            valid[byte_count - 1] &= mask;
+        }
+
+        (state, valid)
+    }
+
+    /// Gets the number of bits up to and including the highest bit that is not Z
+    ///
+    /// Returns 0 if all bits are Z.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// assert_eq!(LogicState::HIGH_Z.significant_width(), 0);
+    /// assert_eq!(LogicState::parse("X0Z").unwrap().significant_width(), 3);
+    /// assert_eq!(LogicState::parse("Z1ZZ").unwrap().significant_width(), 3);
+    /// ```
+    pub fn significant_width(&self) -> u32 {
+        self.0
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, atom)| {
+                let driven = atom.state | atom.valid;
+                (driven != 0).then(|| {
+                    (i as u32) * LogicStateAtom::BITS + (u32::BITS - driven.leading_zeros())
+                })
+            })
+            .unwrap_or(0)
+    }
+
     /// Gets the logic state of a single bit
-    pub const fn get_bit_state(&self, bit_index: u8) -> LogicBitState {
-        let atom_index = (bit_index as usize) / (LogicStateAtom::BITS as usize);
-        let bit_index = (bit_index as u32) % LogicStateAtom::BITS;
+    pub const fn get_bit_state(&self, bit_index: u32) -> LogicBitState {
+        let atom_index = (bit_index / LogicStateAtom::BITS) as usize;
+        let bit_index = bit_index % LogicStateAtom::BITS;
         self.0[atom_index].get_bit_state(bit_index)
     }
 
+    fn set_bit_state(&mut self, bit_index: u32, bit: LogicBitState) {
+        let atom = &mut self.0[(bit_index / LogicStateAtom::BITS) as usize];
+        let bit_index = bit_index % LogicStateAtom::BITS;
+
+        let (state_bit, valid_bit) = bit.to_bits();
+        let mask = !(1 << bit_index);
+        atom.state = (atom.state & mask) | ((state_bit as u32) << bit_index);
+        atom.valid = (atom.valid & mask) | ((valid_bit as u32) << bit_index);
+    }
+
     /// Creates a string representing the first `width` bits of this state
     pub fn to_string(&self, width: u32) -> String {
-        assert!(
-            (width >= MIN_WIRE_WIDTH) && (width <= MAX_WIRE_WIDTH),
-            "invalid bit width",
-        );
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
 
         let mut s = String::with_capacity(width as usize);
         for i in (0..width).rev() {
-            let bit = self.get_bit_state(i as u8);
+            let bit = self.get_bit_state(i);
             s.push(bit.to_char());
         }
         s
     }
 
+    /// Returns an object that formats the first `width` bits of this state without allocating
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::parse("10XZ").unwrap();
+    /// assert_eq!(format!("{}", state.display(5)), "Z10XZ");
+    /// ```
+    pub fn display(&self, width: u32) -> LogicStateDisplay<'_> {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        LogicStateDisplay { state: self, width }
+    }
+
     /// Tests the first `width` bits of this state and another for equality
     pub fn eq(&self, other: &Self, width: u32) -> bool {
-        assert!(
-            (width >= MIN_WIRE_WIDTH) && (width <= MAX_WIRE_WIDTH),
-            "invalid bit width",
-        );
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
 
         let atom_count = width.div_ceil(LogicStateAtom::BITS) as usize;
 
@@ -573,6 +1133,283 @@ impl LogicState {
 
         true
     }
+
+    /// Compares the logic levels of the first `width` bits of this state and another
+    ///
+    /// Unlike [`eq`](Self::eq), which compares the exact states, this treats X and Z as unknown
+    /// levels: the result is `Logic0` if any bit is known to differ, `Undefined` if the
+    /// comparison depends on an unknown bit and `Logic1` if all bits are known and equal.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::{LogicBitState, LogicState};
+    ///
+    /// let a = LogicState::parse("10X1").unwrap();
+    /// let b = LogicState::parse("10X1").unwrap();
+    /// assert!(a.eq(&b, 4));
+    /// assert_eq!(a.compare_eq(&b, 4), LogicBitState::Undefined);
+    ///
+    /// let c = LogicState::parse("00X1").unwrap();
+    /// assert_eq!(a.compare_eq(&c, 4), LogicBitState::Logic0);
+    /// assert_eq!(a.compare_eq(&b, 1), LogicBitState::Logic1);
+    /// ```
+    pub fn compare_eq(&self, other: &Self, width: u32) -> LogicBitState {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        let atom_count = width.div_ceil(LogicStateAtom::BITS) as usize;
+
+        let last_index = (width / LogicStateAtom::BITS) as usize;
+        let last_width = width % LogicStateAtom::BITS;
+        let last_mask = ((1u64 << last_width) - 1) as u32;
+
+        let mut any_unknown = false;
+        for (i, (a, b)) in self.0.into_iter().zip(other.0).enumerate().take(atom_count) {
+            let mask = if i == last_index { last_mask } else { u32::MAX };
+
+            let known = a.valid & b.valid & mask;
+            if ((a.state ^ b.state) & known) != 0 {
+                return LogicBitState::Logic0;
+            }
+
+            any_unknown |= known != mask;
+        }
+
+        if any_unknown {
+            LogicBitState::Undefined
+        } else {
+            LogicBitState::Logic1
+        }
+    }
+
+    /// Finds the lowest of the first `width` bits in which this state and `other` differ
+    ///
+    /// Returns `None` if all `width` bits are equal.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let a = LogicState::from_int(0x1000);
+    /// let b = LogicState::from_int(0x0000);
+    /// assert_eq!(a.first_difference(&b, 16), Some(12));
+    /// assert_eq!(a.first_difference(&b, 12), None);
+    ///
+    /// let x = LogicState::UNDEFINED;
+    /// let z = LogicState::HIGH_Z;
+    /// assert_eq!(x.first_difference(&z, 8), Some(0));
+    /// ```
+    pub fn first_difference(&self, other: &Self, width: u32) -> Option<u32> {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        let atom_count = width.div_ceil(LogicStateAtom::BITS) as usize;
+
+        let last_index = (width / LogicStateAtom::BITS) as usize;
+        let last_width = width % LogicStateAtom::BITS;
+        let last_mask = ((1u64 << last_width) - 1) as u32;
+
+        for (i, (a, b)) in self.0.into_iter().zip(other.0).enumerate().take(atom_count) {
+            let mask = if i == last_index { last_mask } else { u32::MAX };
+
+            let diff = ((a.state ^ b.state) | (a.valid ^ b.valid)) & mask;
+            if diff != 0 {
+                return Some((i as u32) * LogicStateAtom::BITS + diff.trailing_zeros());
+            }
+        }
+
+        None
+    }
+
+    /// Tests the first `width` bits of this state against a pattern
+    ///
+    /// Bits that are Z in the pattern match any state, all other bits have to be equal.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let state = LogicState::parse("10X1").unwrap();
+    /// assert!(state.matches(&LogicState::parse("1ZXZ").unwrap(), 4));
+    /// assert!(!state.matches(&LogicState::parse("0ZZZ").unwrap(), 4));
+    /// ```
+    pub fn matches(&self, pattern: &Self, width: u32) -> bool {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        let atom_count = width.div_ceil(LogicStateAtom::BITS) as usize;
+
+        let last_index = (width / LogicStateAtom::BITS) as usize;
+        let last_width = width % LogicStateAtom::BITS;
+        let last_mask = ((1u64 << last_width) - 1) as u32;
+
+        for (i, (a, p)) in self
+            .0
+            .into_iter()
+            .zip(pattern.0)
+            .enumerate()
+            .take(atom_count)
+        {
+            let mask = if i == last_index { last_mask } else { u32::MAX };
+            let mask = mask & (p.state | p.valid);
+
+            if ((a.state & mask) != (p.state & mask)) || ((a.valid & mask) != (p.valid & mask)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// The number of low bits within the first `width` bits that are all logic 0 or 1
+    fn known_width(&self, width: u32) -> u32 {
+        self.0
+            .iter()
+            .enumerate()
+            .find_map(|(i, atom)| {
+                (atom.valid != u32::MAX)
+                    .then(|| (i as u32) * LogicStateAtom::BITS + atom.valid.trailing_ones())
+            })
+            .unwrap_or(MAX_WIRE_WIDTH)
+            .min(width)
+    }
+
+    fn arithmetic_op<F>(&self, other: &Self, width: u32, op: F) -> Self
+    where
+        F: FnOnce([u32; MAX_ATOM_COUNT], [u32; MAX_ATOM_COUNT]) -> [u32; MAX_ATOM_COUNT],
+    {
+        assert!(width <= MAX_WIRE_WIDTH, "invalid bit width");
+
+        // Every result bit only depends on the input bits at or below it, so the result
+        // is known up to the lowest unknown input bit, exactly like on the GPU.
+        let known_width = self.known_width(width).min(other.known_width(width));
+        let result = op(
+            self.0.map(|atom| atom.state),
+            other.0.map(|atom| atom.state),
+        );
+
+        let bits_below = |limit: u32, atom_index: u32| {
+            let start = atom_index * LogicStateAtom::BITS;
+            match limit.saturating_sub(start) {
+                0 => 0,
+                bits if bits >= LogicStateAtom::BITS => u32::MAX,
+                bits => (1 << bits) - 1,
+            }
+        };
+
+        let mut state = Self::HIGH_Z;
+        for (i, atom) in state.0.iter_mut().enumerate() {
+            let width_mask = bits_below(width, i as u32);
+            let known_mask = bits_below(known_width, i as u32);
+
+            *atom = LogicStateAtom {
+                state: (result[i] & known_mask) | (width_mask & !known_mask),
+                valid: known_mask,
+            };
+        }
+
+        state
+    }
+
+    /// Adds the first `width` bits of this state and another, wrapping around on overflow
+    ///
+    /// Matches the add component: all bits from the lowest bit that is not logic 0 or 1
+    /// in either input upwards are undefined. Bits past `width` are Z.
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let a = LogicState::from_int(0xF0);
+    /// let b = LogicState::from_int(0x20);
+    /// assert_eq!(a.wrapping_add(&b, 8).to_int(8).unwrap(), 0x10);
+    ///
+    /// let x = LogicState::parse("X000").unwrap();
+    /// assert_eq!(x.wrapping_add(&b, 8).to_string(8), "XXXXX000");
+    /// ```
+    pub fn wrapping_add(&self, other: &Self, width: u32) -> Self {
+        self.arithmetic_op(other, width, |a, b| {
+            let mut sum = [0; MAX_ATOM_COUNT];
+            let mut carry = false;
+            for (sum, (a, b)) in sum.iter_mut().zip(a.into_iter().zip(b)) {
+                let (partial, carry_a) = a.overflowing_add(b);
+                let (partial, carry_b) = partial.overflowing_add(carry as u32);
+                *sum = partial;
+                carry = carry_a | carry_b;
+            }
+            sum
+        })
+    }
+
+    /// Subtracts the first `width` bits of another state from this one, wrapping around on overflow
+    ///
+    /// Undefined bits are handled the same way as in [`wrapping_add`](Self::wrapping_add).
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let a = LogicState::from_int(0x10);
+    /// let b = LogicState::from_int(0x20);
+    /// assert_eq!(a.wrapping_sub(&b, 8).to_int(8).unwrap(), 0xF0);
+    /// ```
+    pub fn wrapping_sub(&self, other: &Self, width: u32) -> Self {
+        self.arithmetic_op(other, width, |a, b| {
+            let mut difference = [0; MAX_ATOM_COUNT];
+            let mut borrow = false;
+            for (difference, (a, b)) in difference.iter_mut().zip(a.into_iter().zip(b)) {
+                let (partial, borrow_a) = a.overflowing_sub(b);
+                let (partial, borrow_b) = partial.overflowing_sub(borrow as u32);
+                *difference = partial;
+                borrow = borrow_a | borrow_b;
+            }
+            difference
+        })
+    }
+
+    /// Multiplies the first `width` bits of this state and another, keeping the low `width` bits
+    ///
+    /// Undefined bits are handled the same way as in [`wrapping_add`](Self::wrapping_add).
+    ///
+    /// ### Example:
+    /// ```
+    /// use gsim2::LogicState;
+    ///
+    /// let a = LogicState::from_int(0x13);
+    /// let b = LogicState::from_int(0x11);
+    /// assert_eq!(a.wrapping_mul(&b, 8).to_int(8).unwrap(), 0x43);
+    /// ```
+    pub fn wrapping_mul(&self, other: &Self, width: u32) -> Self {
+        self.arithmetic_op(other, width, |a, b| {
+            let mut product = [0; MAX_ATOM_COUNT];
+            for (i, &a) in a.iter().enumerate() {
+                let mut carry = 0u64;
+                for (j, &b) in b.iter().enumerate().take(MAX_ATOM_COUNT - i) {
+                    let partial = (a as u64) * (b as u64) + (product[i + j] as u64) + carry;
+                    product[i + j] = partial as u32;
+                    carry = partial >> u32::BITS;
+                }
+            }
+            product
+        })
+    }
+}
+
+/// Formats the first `width` bits of a logic state, created by [`LogicState::display`]
+#[derive(Debug, Clone, Copy)]
+pub struct LogicStateDisplay<'a> {
+    state: &'a LogicState,
+    width: u32,
+}
+
+impl fmt::Display for LogicStateDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+
+        for i in (0..self.width).rev() {
+            let bit = self.state.get_bit_state(i);
+            f.write_char(bit.to_char())?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for LogicState {
@@ -605,6 +1442,25 @@ impl From<u32> for LogicState {
     }
 }
 
+impl From<u64> for LogicState {
+    #[inline]
+    fn from(value: u64) -> Self {
+        let mut atoms = [LogicStateAtom::LOGIC_0; MAX_ATOM_COUNT];
+        atoms[0] = LogicStateAtom::from_int(value as u32);
+        atoms[1] = LogicStateAtom::from_int((value >> 32) as u32);
+        Self(atoms)
+    }
+}
+
+impl TryFrom<&[u32]> for LogicState {
+    type Error = FromBigIntError;
+
+    #[inline]
+    fn try_from(value: &[u32]) -> Result<Self, Self::Error> {
+        Self::from_big_int(value)
+    }
+}
+
 #[cfg(feature = "serde")]
 impl serde::Serialize for LogicState {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -661,7 +1517,7 @@ macro_rules! bits {
     (@BIT 1) => { $crate::LogicBitState::Logic1 };
     ($($bit:tt),+) => {{
         const BITS: &'static [$crate::LogicBitState] = &[$($crate::bits!(@BIT $bit)),+];
-        const _ASSERT_MAX: usize = (u8::MAX as usize) - BITS.len();
+        const _ASSERT_MAX: usize = ($crate::MAX_WIRE_WIDTH as usize) - BITS.len();
         const _ASSERT_MIN: usize = BITS.len() - 1;
         $crate::LogicState::from_bits(BITS).unwrap()
     }}
@@ -0,0 +1,401 @@
+use crate::*;
+use std::mem;
+
+const MAGIC: [u8; 4] = *b"GSIM";
+
+/// Written in the byte order of the machine, to detect snapshots from a different byte order
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+const FLAG_HAS_INITIAL_WIRE_STATES: u32 = 0x01;
+const FLAG_DETECT_CONFLICTS: u32 = 0x02;
+const FLAG_STRICT_UNKNOWN: u32 = 0x04;
+const FLAG_ENABLE_PROFILING: u32 = 0x08;
+const FLAG_ENABLE_GPU_TIMING: u32 = 0x10;
+
+/// The buffers of a circuit exactly as they are uploaded to the GPU, together with the
+/// options of the builder it was taken from
+///
+/// Building a simulator from a snapshot skips all work done by [`SimulatorBuilder`], so a
+/// large circuit only has to be constructed once and can afterwards be stored with
+/// [`to_bytes`](Self::to_bytes) and loaded with [`from_bytes`](Self::from_bytes).
+#[derive(Clone)]
+pub struct CircuitSnapshot {
+    /// A builder whose wires have been merged and whose evaluation order is complete
+    builder: SimulatorBuilder,
+}
+
+/// An error produced by [`CircuitSnapshot::from_bytes`]
+#[derive(Debug, Clone)]
+pub enum DeserializeError {
+    /// The bytes are not a snapshot, are truncated, or refer to data outside of the buffers
+    InvalidFormat,
+    /// The snapshot was written on a machine with a different byte order
+    ByteOrderMismatch,
+    /// The snapshot was written by a version of the crate with a different buffer layout
+    UnsupportedVersion { version: u32 },
+    /// The snapshot was written with a different [`MAX_WIRE_WIDTH`], i.e. with the
+    /// `wide-wires` feature set differently
+    MaxWireWidthMismatch { max_wire_width: u32 },
+}
+
+impl CircuitSnapshot {
+    /// The version of the layout of the buffers stored in a snapshot
+    ///
+    /// This has to be increased whenever one of the `#[repr(C)]` structs shared with the
+    /// shaders changes, so older snapshots are rejected instead of being misread.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Writes the snapshot into a self-contained blob
+    ///
+    /// The header is little-endian. The buffers are stored exactly as they are uploaded to the
+    /// GPU, in the byte order of the machine writing them, which is recorded in the header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let builder = &self.builder;
+
+        let mut flags = 0;
+        for (flag, set) in [
+            (
+                FLAG_HAS_INITIAL_WIRE_STATES,
+                builder.has_initial_wire_states,
+            ),
+            (FLAG_DETECT_CONFLICTS, builder.detect_conflicts),
+            (FLAG_STRICT_UNKNOWN, builder.strict_unknown),
+            (FLAG_ENABLE_PROFILING, builder.enable_profiling),
+            (FLAG_ENABLE_GPU_TIMING, builder.enable_gpu_timing),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC);
+        write_u32(&mut bytes, Self::FORMAT_VERSION);
+        write_u32(&mut bytes, MAX_WIRE_WIDTH);
+        bytes.extend_from_slice(&BYTE_ORDER_MARK.to_ne_bytes());
+        write_u32(&mut bytes, flags);
+        write_u32(&mut bytes, builder.max_reported_conflicts);
+        match &builder.debug_label {
+            Some(label) => write_slice(&mut bytes, label.as_bytes()),
+            None => write_u32(&mut bytes, u32::MAX),
+        }
+
+        write_slice(&mut bytes, builder.wire_states.as_slice());
+        write_slice(&mut bytes, builder.wire_drives.as_slice());
+        write_slice(&mut bytes, builder.wire_forces.as_slice());
+        write_slice(&mut bytes, builder.wire_drivers.as_slice());
+        write_slice(&mut bytes, builder.wires.as_slice());
+
+        write_slice(&mut bytes, builder.output_states.as_slice());
+        write_slice(&mut bytes, builder.outputs.as_slice());
+        write_slice(&mut bytes, builder.inputs.as_slice());
+        write_slice(&mut bytes, builder.memory.as_slice());
+        write_slice(&mut bytes, builder.components.as_slice());
+        write_slice(&mut bytes, builder.evaluation_order.as_slice());
+
+        bytes
+    }
+
+    /// Reads a snapshot written by [`to_bytes`](Self::to_bytes)
+    ///
+    /// All offsets and indices stored in the buffers are checked against the buffer lengths,
+    /// so a corrupted snapshot is rejected instead of producing a simulator that reads out of
+    /// bounds. Snapshots can only be read on machines with the byte order they were written on.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let mut reader = Reader { bytes };
+
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(DeserializeError::InvalidFormat);
+        }
+
+        let version = reader.read_u32()?;
+        if version != Self::FORMAT_VERSION {
+            return Err(DeserializeError::UnsupportedVersion { version });
+        }
+
+        let max_wire_width = reader.read_u32()?;
+        if max_wire_width != MAX_WIRE_WIDTH {
+            return Err(DeserializeError::MaxWireWidthMismatch { max_wire_width });
+        }
+
+        if reader.read_bytes(mem::size_of::<u32>())? != BYTE_ORDER_MARK.to_ne_bytes() {
+            return Err(DeserializeError::ByteOrderMismatch);
+        }
+
+        let flags = reader.read_u32()?;
+        let max_reported_conflicts = reader.read_u32()?;
+        if max_reported_conflicts > MAX_REPORTED_CONFLICTS_LIMIT {
+            return Err(DeserializeError::InvalidFormat);
+        }
+        let debug_label = match reader.read_u32()? {
+            u32::MAX => None,
+            len => {
+                let label = reader.read_bytes(len as usize)?;
+                let label =
+                    std::str::from_utf8(label).map_err(|_| DeserializeError::InvalidFormat)?;
+                Some(label.to_owned())
+            }
+        };
+
+        let builder = SimulatorBuilder {
+            wire_states: LogicStateBuffer::from_vec(reader.read_vec()?),
+            wire_drives: LogicStateBuffer::from_vec(reader.read_vec()?),
+            wire_forces: LogicStateBuffer::from_vec(reader.read_vec()?),
+            wire_drivers: Buffer::from_vec(reader.read_vec()?),
+            wires: Buffer::from_vec(reader.read_vec()?),
+
+            output_states: LogicStateBuffer::from_vec(reader.read_vec()?),
+            outputs: Buffer::from_vec(reader.read_vec()?),
+            inputs: Buffer::from_vec(reader.read_vec()?),
+            memory: LogicStateBuffer::from_vec(reader.read_vec()?),
+            components: Buffer::from_vec(reader.read_vec()?),
+            evaluation_order: Buffer::from_vec(reader.read_vec()?),
+
+            wire_aliases: Vec::new(),
+            wire_slices: Vec::new(),
+            has_initial_wire_states: (flags & FLAG_HAS_INITIAL_WIRE_STATES) != 0,
+            debug_label,
+            detect_conflicts: (flags & FLAG_DETECT_CONFLICTS) != 0,
+            strict_unknown: (flags & FLAG_STRICT_UNKNOWN) != 0,
+            max_reported_conflicts,
+            enable_profiling: (flags & FLAG_ENABLE_PROFILING) != 0,
+            enable_gpu_timing: (flags & FLAG_ENABLE_GPU_TIMING) != 0,
+        };
+
+        if !reader.bytes.is_empty() || !is_consistent(&builder) {
+            return Err(DeserializeError::InvalidFormat);
+        }
+
+        Ok(Self { builder })
+    }
+}
+
+/// Checks that every offset and index in the buffers of `builder` stays within the buffer
+/// it refers to, so the simulator never accesses memory outside of them
+fn is_consistent(builder: &SimulatorBuilder) -> bool {
+    /// Whether `count` elements starting at `start` fit into a buffer of length `len`
+    fn fits(start: Option<u32>, count: u32, len: u32) -> bool {
+        start.is_some_and(|start| (start as u64) + (count as u64) <= (len as u64))
+    }
+
+    fn atom_count(width: u32) -> u32 {
+        width.div_ceil(LogicStateAtom::BITS)
+    }
+
+    fn is_valid_width(width: u32) -> bool {
+        (MIN_WIRE_WIDTH..=MAX_WIRE_WIDTH).contains(&width)
+    }
+
+    let wires = builder.wires.as_slice();
+    let output_states_len = builder.output_states.len();
+    let wire_drivers_len = builder.wire_drivers.len();
+
+    if builder.wire_forces.len() != builder.wire_drives.len() {
+        return false;
+    }
+
+    // Component inputs are mapped back to their wires by the state offset,
+    // which requires the wires to be sorted by it.
+    if !wires
+        .windows(2)
+        .all(|pair| pair[0].state_offset < pair[1].state_offset)
+    {
+        return false;
+    }
+
+    for wire in wires {
+        let state_width = atom_count(wire.width);
+        if !is_valid_width(wire.width)
+            || !fits(
+                wire.state_offset.get(),
+                state_width,
+                builder.wire_states.len(),
+            )
+            || !fits(
+                wire.drive_offset.get(),
+                state_width,
+                builder.wire_drives.len(),
+            )
+        {
+            return false;
+        }
+
+        let driver_fits =
+            |width: u32, bit_offset: u32, output_state_offset: Offset<OutputState>| {
+                is_valid_width(width)
+                    && (bit_offset as u64) + (width as u64) <= (wire.width as u64)
+                    && fits(
+                        output_state_offset.get(),
+                        atom_count(width),
+                        output_states_len,
+                    )
+            };
+
+        if !wire.first_driver_offset.is_invalid()
+            && !driver_fits(
+                wire.first_driver_width,
+                wire.first_driver_bit_offset,
+                wire.first_driver_offset,
+            )
+        {
+            return false;
+        }
+
+        // A list longer than the buffer has to contain a cycle.
+        let mut driver_index = wire.driver_list;
+        let mut list_len = 0;
+        while !driver_index.is_invalid() {
+            let Some(driver) = builder.wire_drivers.get(driver_index) else {
+                return false;
+            };
+            list_len += 1;
+            if (list_len > wire_drivers_len)
+                || !driver_fits(driver.width, driver.bit_offset, driver.output_state_offset)
+            {
+                return false;
+            }
+            driver_index = driver.next_driver;
+        }
+    }
+
+    let output_fits = |output: &ComponentOutput| {
+        is_valid_width(output.width)
+            && fits(
+                output.state_offset.get(),
+                atom_count(output.width),
+                output_states_len,
+            )
+    };
+    if !builder.outputs.as_slice().iter().all(output_fits) {
+        return false;
+    }
+
+    let input_fits = |input: &ComponentInput| {
+        is_valid_width(input.width)
+            && wires
+                .binary_search_by_key(&input.wire_state_offset, |wire| wire.state_offset)
+                .is_ok_and(|wire_index| input.width <= wires[wire_index].width)
+    };
+    if !builder.inputs.as_slice().iter().all(input_fits) {
+        return false;
+    }
+
+    for component in builder.components.as_slice() {
+        let outputs_fit = match component.output_count {
+            0 => true,
+            1 => output_fits(unsafe { &component.output.output }),
+            count => fits(
+                unsafe { component.output.first_output.first_output }.get(),
+                count as u32,
+                builder.outputs.len(),
+            ),
+        };
+
+        let inputs_fit = (component.input_count == 0)
+            || fits(
+                component.first_input.get(),
+                component.input_count as u32,
+                builder.inputs.len(),
+            );
+
+        let memory_fits = (component.memory_size == 0)
+            || fits(
+                component.memory_offset.get(),
+                component.memory_size,
+                builder.memory.len(),
+            );
+
+        if !outputs_fit || !inputs_fit || !memory_fits {
+            return false;
+        }
+    }
+
+    let component_count = builder.components.len();
+    (builder.evaluation_order.len() == component_count)
+        && builder
+            .evaluation_order
+            .as_slice()
+            .iter()
+            .all(|index| fits(index.get(), 1, component_count))
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Writes the number of elements in `data`, followed by their bytes
+fn write_slice<T: Pod>(bytes: &mut Vec<u8>, data: &[T]) {
+    write_u32(bytes, data.len() as u32);
+    bytes.extend_from_slice(bytemuck::cast_slice(data));
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DeserializeError> {
+        if len > self.bytes.len() {
+            return Err(DeserializeError::InvalidFormat);
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DeserializeError> {
+        let bytes = self.read_bytes(mem::size_of::<u32>())?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// Reads a slice written by [`write_slice`]
+    fn read_vec<T: Pod>(&mut self) -> Result<Vec<T>, DeserializeError> {
+        let len = self.read_u32()? as usize;
+        let byte_len = len
+            .checked_mul(mem::size_of::<T>())
+            .ok_or(DeserializeError::InvalidFormat)?;
+
+        // The bytes are not necessarily aligned for `T`, so they have to be copied.
+        let bytes = self.read_bytes(byte_len)?;
+        let mut data = vec![T::zeroed(); len];
+        bytemuck::cast_slice_mut(&mut data).copy_from_slice(bytes);
+        Ok(data)
+    }
+}
+
+impl SimulatorBuilder {
+    /// Takes a snapshot of the circuit as it would be uploaded by [`build`](Self::build)
+    ///
+    /// The builder itself is left unchanged.
+    pub fn snapshot(&self) -> Result<CircuitSnapshot, SimulatorBuildError> {
+        let mut builder = self.clone();
+        builder.merge_wires()?;
+        builder.complete_evaluation_order()?;
+        Ok(CircuitSnapshot { builder })
+    }
+}
+
+impl Simulator {
+    /// Builds a simulator from `snapshot` on the shared device matching `options`
+    ///
+    /// This is equivalent to building the [`SimulatorBuilder`] the snapshot was taken from
+    /// with [`SimulatorBuilder::build_with_options`].
+    pub fn from_snapshot(
+        snapshot: &CircuitSnapshot,
+        options: GpuOptions,
+    ) -> Result<Simulator, SimulatorBuildError> {
+        gpu::create_simulator(snapshot.builder.clone(), options).map_err(Into::into)
+    }
+
+    /// Like [`from_snapshot`](Self::from_snapshot), but does not block on device creation
+    #[cfg(feature = "wasm")]
+    pub async fn from_snapshot_async(
+        snapshot: &CircuitSnapshot,
+        options: GpuOptions,
+    ) -> Result<Simulator, SimulatorBuildError> {
+        gpu::create_simulator_async(snapshot.builder.clone(), options)
+            .await
+            .map_err(Into::into)
+    }
+}
@@ -1,5 +1,11 @@
 use super::*;
 
+/// The number of drivers connected directly to `wire` in the builder
+fn builder_driver_count(builder: &SimulatorBuilder, wire: WireId) -> usize {
+    let wire = builder.wires.get(wire.0).unwrap();
+    wire.drivers(&builder.wire_drivers).count()
+}
+
 fn add_and_gate(
     builder: &mut SimulatorBuilder,
     inputs: &[WireId],
@@ -48,6 +54,19 @@ fn add_xnor_gate(
     builder.add_component(XnorGatePorts { inputs, output })
 }
 
+fn add_xor_gate_2(
+    builder: &mut SimulatorBuilder,
+    input_a: WireId,
+    input_b: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(XorGate2Ports {
+        input_a,
+        input_b,
+        output,
+    })
+}
+
 fn add_not_gate(
     builder: &mut SimulatorBuilder,
     input: WireId,
@@ -56,6 +75,22 @@ fn add_not_gate(
     builder.add_component(NotGatePorts { input, output })
 }
 
+fn add_horizontal_xor_gate(
+    builder: &mut SimulatorBuilder,
+    input: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(HorizontalXorGatePorts { input, output })
+}
+
+fn add_horizontal_xnor_gate(
+    builder: &mut SimulatorBuilder,
+    input: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(HorizontalXnorGatePorts { input, output })
+}
+
 fn add_add(
     builder: &mut SimulatorBuilder,
     input_lhs: WireId,
@@ -82,10 +117,92 @@ fn add_sub(
     })
 }
 
+fn add_sat_add(
+    builder: &mut SimulatorBuilder,
+    input_lhs: WireId,
+    input_rhs: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(SaturatingAddPorts {
+        input_lhs,
+        input_rhs,
+        output,
+    })
+}
+
+fn add_sat_sub(
+    builder: &mut SimulatorBuilder,
+    input_lhs: WireId,
+    input_rhs: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(SaturatingSubtractPorts {
+        input_lhs,
+        input_rhs,
+        output,
+    })
+}
+
+fn add_min(
+    builder: &mut SimulatorBuilder,
+    input_lhs: WireId,
+    input_rhs: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(MinPorts {
+        input_lhs,
+        input_rhs,
+        output,
+    })
+}
+
+fn add_max(
+    builder: &mut SimulatorBuilder,
+    input_lhs: WireId,
+    input_rhs: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(MaxPorts {
+        input_lhs,
+        input_rhs,
+        output,
+    })
+}
+
+fn add_signed_min(
+    builder: &mut SimulatorBuilder,
+    input_lhs: WireId,
+    input_rhs: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(SignedMinPorts {
+        input_lhs,
+        input_rhs,
+        output,
+    })
+}
+
+fn add_signed_max(
+    builder: &mut SimulatorBuilder,
+    input_lhs: WireId,
+    input_rhs: WireId,
+    output: WireId,
+) -> AddComponentResult {
+    builder.add_component(SignedMaxPorts {
+        input_lhs,
+        input_rhs,
+        output,
+    })
+}
+
 fn add_neg(builder: &mut SimulatorBuilder, input: WireId, output: WireId) -> AddComponentResult {
     builder.add_component(NegatePorts { input, output })
 }
 
+fn add_abs(builder: &mut SimulatorBuilder, input: WireId, output: WireId) -> AddComponentResult {
+    builder.add_component(AbsPorts { input, output })
+}
+
 #[test]
 fn and_gate() {
     const TEST_DATA: &[WideGateTestData<2>] = wide_gate_test_data!(
@@ -167,6 +284,20 @@ fn xor_gate() {
     test_wide_gate(add_xor_gate, 64, TEST_DATA, 2);
 }
 
+#[test]
+fn xor_gate_2() {
+    const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, LOGIC_0)  -> UNDEFINED,
+        (LOGIC_0, LOGIC_0) -> LOGIC_0,
+        (LOGIC_0, LOGIC_1) -> LOGIC_1,
+        (LOGIC_1, LOGIC_0) -> LOGIC_1,
+        (LOGIC_1, LOGIC_1) -> LOGIC_0,
+    );
+
+    test_binary_gate(add_xor_gate_2, 1, TEST_DATA, 2);
+    test_binary_gate(add_xor_gate_2, 33, TEST_DATA, 2);
+}
+
 #[test]
 fn nand_gate() {
     const TEST_DATA: &[WideGateTestData<2>] = wide_gate_test_data!(
@@ -737,6 +868,8 @@ fn add() {
         (1, {u32::MAX}) -> 0,
         ({u32::MAX}, 1) -> 0,
         ({u32::MAX}, {u32::MAX}) -> {u32::MAX - 1},
+        ("DEAD_BEEF", 0x2152_4111) -> 0,
+        ("0000_7FFF", 0b1) -> 0x8000,
     );
 
     test_binary_gate(add_add, 16, TEST_DATA, 2);
@@ -769,131 +902,2283 @@ fn sub() {
 }
 
 #[test]
-fn neg() {
-    const TEST_DATA: &[UnaryGateTestData] = unary_gate_test_data!(
-        HIGH_Z -> UNDEFINED,
-        UNDEFINED -> UNDEFINED,
+fn sat_add() {
+    const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, HIGH_Z) -> UNDEFINED,
+        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, HIGH_Z) -> UNDEFINED,
+        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+        (HIGH_Z, 0) -> UNDEFINED,
+        (UNDEFINED, 0) -> UNDEFINED,
+        (0, HIGH_Z) -> UNDEFINED,
+        (0, UNDEFINED) -> UNDEFINED,
 
-        0 -> 0,
-        1 -> LOGIC_1,
-        LOGIC_1 -> 1,
+        (0, 0) -> 0,
+        (0, 1) -> 1,
+        (1, 0) -> 1,
+        (1, 1) -> 2,
+        (0, {u32::MAX}) -> {u32::MAX},
+        ({u32::MAX}, 0) -> {u32::MAX},
+        ({u32::MAX}, 1) -> {u32::MAX},
+        (1, {u32::MAX}) -> {u32::MAX},
+        ({u32::MAX}, {u32::MAX}) -> {u32::MAX},
     );
 
-    test_unary_gate(add_neg, 16, TEST_DATA, 2);
-    test_unary_gate(add_neg, 32, TEST_DATA, 2);
-    test_unary_gate(add_neg, 33, TEST_DATA, 2);
-    test_unary_gate(add_neg, 64, TEST_DATA, 2);
+    test_binary_gate(add_sat_add, 16, TEST_DATA, 2);
+    test_binary_gate(add_sat_add, 32, TEST_DATA, 2);
 }
 
-//#[test]
-//fn mul() {
-//    let test_data: &[BinaryGateTestData] = binary_gate_test_data!(
-//        (HIGH_Z, HIGH_Z) -> UNDEFINED,
-//        (HIGH_Z, UNDEFINED) -> UNDEFINED,
-//        (UNDEFINED, HIGH_Z) -> UNDEFINED,
-//        (UNDEFINED, UNDEFINED) -> UNDEFINED,
-//        (HIGH_Z, 0) -> UNDEFINED,
-//        (UNDEFINED, 0) -> UNDEFINED,
-//        (0, HIGH_Z) -> UNDEFINED,
-//        (0, UNDEFINED) -> UNDEFINED,
-//
-//        (0, 0) -> 0,
-//        (0, 1) -> 0,
-//        (1, 0) -> 0,
-//        (1, 1) -> 1,
-//        (0, {u32::MAX}) -> 0,
-//        ({u32::MAX}, 0) -> 0,
-//        (1, {u32::MAX}) -> {u32::MAX},
-//        ({u32::MAX}, 1) -> {u32::MAX},
-//        ({u32::MAX}, {u32::MAX}) -> [1, u32::MAX - 1],
-//        ([u32::MAX, u32::MAX], [u32::MAX, u32::MAX]) -> [1, 0, u32::MAX - 1, u32::MAX],
-//        ([0x658c0c38, 0xd50cebfb], [0x901cfad8, 0xc0083189]) -> [0x4838ff40, 0x2201c171, 0xe109006d, 0x9fd0829d],
-//    );
-//
-//    test_binary_gate(SimulatorBuilder::add_mul, 16, test_data, 2);
-//    test_binary_gate(SimulatorBuilder::add_mul, 32, test_data, 2);
-//    test_binary_gate(SimulatorBuilder::add_mul, 33, test_data, 2);
-//    test_binary_gate(SimulatorBuilder::add_mul, 64, test_data, 2);
-//    test_binary_gate(SimulatorBuilder::add_mul, 128, test_data, 2);
-//}
+#[test]
+fn sat_sub() {
+    const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, HIGH_Z) -> UNDEFINED,
+        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, HIGH_Z) -> UNDEFINED,
+        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+        (HIGH_Z, 0) -> UNDEFINED,
+        (UNDEFINED, 0) -> UNDEFINED,
+        (0, HIGH_Z) -> UNDEFINED,
+        (0, UNDEFINED) -> UNDEFINED,
+
+        (0, 0) -> 0,
+        (0, 1) -> 0,
+        (1, 0) -> 1,
+        (1, 1) -> 0,
+        (2, 1) -> 1,
+        (0, {u32::MAX}) -> 0,
+        ({u32::MAX}, 0) -> {u32::MAX},
+        ({u32::MAX}, 1) -> {u32::MAX - 1},
+        ({u32::MAX}, {u32::MAX}) -> 0,
+    );
+
+    test_binary_gate(add_sat_sub, 16, TEST_DATA, 2);
+    test_binary_gate(add_sat_sub, 32, TEST_DATA, 2);
+}
 
 #[test]
-fn not_gate() {
-    const TEST_DATA: &[UnaryGateTestData] = unary_gate_test_data!(
-        HIGH_Z -> UNDEFINED,
-        UNDEFINED -> UNDEFINED,
-        LOGIC_0 -> LOGIC_1,
-        LOGIC_1 -> LOGIC_0,
+fn min() {
+    const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, HIGH_Z) -> UNDEFINED,
+        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, HIGH_Z) -> UNDEFINED,
+        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+        (HIGH_Z, 0) -> UNDEFINED,
+        (UNDEFINED, 0) -> UNDEFINED,
+        (0, HIGH_Z) -> UNDEFINED,
+        (0, UNDEFINED) -> UNDEFINED,
+        ("X000_0000", 0) -> UNDEFINED,
+
+        (0, 0) -> 0,
+        (0, 1) -> 0,
+        (1, 0) -> 0,
+        (2, 1) -> 1,
+        (0x1_0000, 0x0_FFFF) -> 0x0_FFFF,
+        (LOGIC_1, 1) -> 1,
+        (1, LOGIC_1) -> 1,
+        (LOGIC_1, LOGIC_1) -> LOGIC_1,
     );
 
-    test_unary_gate(add_not_gate, 1, TEST_DATA, 2);
-    test_unary_gate(add_not_gate, 32, TEST_DATA, 2);
-    test_unary_gate(add_not_gate, 33, TEST_DATA, 2);
-    test_unary_gate(add_not_gate, 64, TEST_DATA, 2);
+    test_binary_gate(add_min, 32, TEST_DATA, 2);
+    test_binary_gate(add_min, 33, TEST_DATA, 2);
+    test_binary_gate(add_min, 64, TEST_DATA, 2);
 }
 
 #[test]
-fn buffer() {
+fn max() {
     const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
-        (HIGH_Z, HIGH_Z) -> HIGH_Z,
-        (UNDEFINED, HIGH_Z) -> HIGH_Z,
-        (LOGIC_0, HIGH_Z) -> HIGH_Z,
-        (LOGIC_1, HIGH_Z) -> HIGH_Z,
+        (HIGH_Z, HIGH_Z) -> UNDEFINED,
+        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, HIGH_Z) -> UNDEFINED,
+        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+        (HIGH_Z, 0) -> UNDEFINED,
+        (UNDEFINED, 0) -> UNDEFINED,
+        (0, HIGH_Z) -> UNDEFINED,
+        (0, UNDEFINED) -> UNDEFINED,
+        ("X000_0000", 0) -> UNDEFINED,
+
+        (0, 0) -> 0,
+        (0, 1) -> 1,
+        (1, 0) -> 1,
+        (2, 1) -> 2,
+        (0x1_0000, 0x0_FFFF) -> 0x1_0000,
+        (LOGIC_1, 1) -> LOGIC_1,
+        (1, LOGIC_1) -> LOGIC_1,
+        (LOGIC_1, LOGIC_1) -> LOGIC_1,
+    );
 
+    test_binary_gate(add_max, 32, TEST_DATA, 2);
+    test_binary_gate(add_max, 33, TEST_DATA, 2);
+    test_binary_gate(add_max, 64, TEST_DATA, 2);
+}
+
+#[test]
+fn signed_min() {
+    const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, HIGH_Z) -> UNDEFINED,
         (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, HIGH_Z) -> UNDEFINED,
         (UNDEFINED, UNDEFINED) -> UNDEFINED,
-        (LOGIC_0, UNDEFINED) -> UNDEFINED,
-        (LOGIC_1, UNDEFINED) -> UNDEFINED,
+        (HIGH_Z, 0) -> UNDEFINED,
+        (UNDEFINED, 0) -> UNDEFINED,
+        (0, HIGH_Z) -> UNDEFINED,
+        (0, UNDEFINED) -> UNDEFINED,
 
-        (HIGH_Z, LOGIC_0) -> HIGH_Z,
-        (UNDEFINED, LOGIC_0) -> HIGH_Z,
-        (LOGIC_0, LOGIC_0) -> HIGH_Z,
-        (LOGIC_1, LOGIC_0) -> HIGH_Z,
+        (0, 0) -> 0,
+        (0, 1) -> 0,
+        (2, 1) -> 1,
+        (LOGIC_1, 1) -> LOGIC_1,
+        (1, LOGIC_1) -> LOGIC_1,
+        (LOGIC_1, 0) -> LOGIC_1,
+    );
+
+    test_binary_gate(add_signed_min, 16, TEST_DATA, 2);
+    test_binary_gate(add_signed_min, 32, TEST_DATA, 2);
+    test_binary_gate(add_signed_min, 33, TEST_DATA, 2);
+
+    const TEST_DATA_16: &[BinaryGateTestData] = binary_gate_test_data!(
+        (0x8000, 0x7FFF) -> 0x8000,
+        (0x7FFF, 0x8000) -> 0x8000,
+        (0xFFFE, 0xFFFF) -> 0xFFFE,
+    );
+
+    test_binary_gate(add_signed_min, 16, TEST_DATA_16, 2);
+}
+
+#[test]
+fn signed_max() {
+    const TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, HIGH_Z) -> UNDEFINED,
+        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, HIGH_Z) -> UNDEFINED,
+        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+        (HIGH_Z, 0) -> UNDEFINED,
+        (UNDEFINED, 0) -> UNDEFINED,
+        (0, HIGH_Z) -> UNDEFINED,
+        (0, UNDEFINED) -> UNDEFINED,
+
+        (0, 0) -> 0,
+        (0, 1) -> 1,
+        (2, 1) -> 2,
+        (LOGIC_1, 1) -> 1,
+        (1, LOGIC_1) -> 1,
+        (LOGIC_1, 0) -> 0,
+    );
+
+    test_binary_gate(add_signed_max, 16, TEST_DATA, 2);
+    test_binary_gate(add_signed_max, 32, TEST_DATA, 2);
+    test_binary_gate(add_signed_max, 33, TEST_DATA, 2);
+
+    const TEST_DATA_16: &[BinaryGateTestData] = binary_gate_test_data!(
+        (0x8000, 0x7FFF) -> 0x7FFF,
+        (0x7FFF, 0x8000) -> 0x7FFF,
+        (0xFFFE, 0xFFFF) -> 0xFFFF,
+    );
+
+    test_binary_gate(add_signed_max, 16, TEST_DATA_16, 2);
+}
+
+fn test_extension<F>(
+    add_extension: F,
+    input_width: u32,
+    output_width: u32,
+    test_data: &[UnaryGateTestData],
+) where
+    F: FnOnce(&mut SimulatorBuilder, WireId, WireId) -> AddComponentResult,
+{
+    let mut builder = SimulatorBuilder::default();
+    let input = builder.add_wire(input_width).unwrap();
+    let output = builder.add_wire(output_width).unwrap();
+    add_extension(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    for (i, test_data) in test_data.iter().enumerate() {
+        sim.set_wire_drive(input, &test_data.input).unwrap();
+        assert!(matches!(sim.run(2), SimulationRunResult::Ok));
+
+        let output_state = sim.get_wire_state(output).unwrap();
+        assert!(
+            output_state.eq(&test_data.output, output_width),
+            "[TEST {i}]  expected: {}  actual: {}",
+            test_data.output.to_string(output_width),
+            output_state.to_string(output_width),
+        );
+    }
+}
+
+#[test]
+fn zero_extend() {
+    let add_zext = |builder: &mut SimulatorBuilder, input, output| {
+        builder.add_component(ZeroExtendPorts { input, output })
+    };
+
+    let test_data = [
+        UnaryGateTestData {
+            input: LogicState::from_int(0x00),
+            output: LogicState::from_int(0x0000),
+        },
+        UnaryGateTestData {
+            input: LogicState::from_int(0x7F),
+            output: LogicState::from_int(0x007F),
+        },
+        UnaryGateTestData {
+            input: LogicState::from_int(0x80),
+            output: LogicState::from_int(0x0080),
+        },
+        UnaryGateTestData {
+            input: LogicState::parse("X000000Z").unwrap(),
+            output: LogicState::parse_sized("X000000Z", 40, LogicBitState::Logic0).unwrap(),
+        },
+    ];
+
+    test_extension(add_zext, 8, 16, &test_data);
+    test_extension(add_zext, 8, 40, &test_data);
+}
+
+#[test]
+fn sign_extend() {
+    let add_sext = |builder: &mut SimulatorBuilder, input, output| {
+        builder.add_component(SignExtendPorts { input, output })
+    };
+
+    let test_data = [
+        UnaryGateTestData {
+            input: LogicState::from_int(0x00),
+            output: LogicState::from_int(0x0000),
+        },
+        UnaryGateTestData {
+            input: LogicState::from_int(0x7F),
+            output: LogicState::from_int(0x007F),
+        },
+        UnaryGateTestData {
+            input: LogicState::from_int(0x80),
+            output: LogicState::from_int(0xFF80),
+        },
+        UnaryGateTestData {
+            input: LogicState::parse("X0000001").unwrap(),
+            output: LogicState::parse("XXXXXXXXX0000001").unwrap(),
+        },
+        UnaryGateTestData {
+            input: LogicState::parse("Z0000001").unwrap(),
+            output: LogicState::parse("ZZZZZZZZZ0000001").unwrap(),
+        },
+    ];
+
+    test_extension(add_sext, 8, 16, &test_data);
+
+    let test_data = [
+        UnaryGateTestData {
+            input: LogicState::from_big_int(&[0, 0x8000_0000]).unwrap(),
+            output: LogicState::from_big_int(&[0, 0x8000_0000, u32::MAX]).unwrap(),
+        },
+        UnaryGateTestData {
+            input: LogicState::from_big_int(&[u32::MAX, 0x7FFF_FFFF]).unwrap(),
+            output: LogicState::from_big_int(&[u32::MAX, 0x7FFF_FFFF, 0]).unwrap(),
+        },
+    ];
+
+    test_extension(add_sext, 64, 96, &test_data);
+}
+
+fn binary_to_gray(value: u64) -> u64 {
+    value ^ (value >> 1)
+}
+
+fn gray_to_binary(mut value: u64) -> u64 {
+    let mut result = 0;
+    while value != 0 {
+        result ^= value;
+        value >>= 1;
+    }
+    result
+}
+
+fn gray_code_test_data(width: u32, convert: fn(u64) -> u64) -> Vec<UnaryGateTestData> {
+    let mask = u64::MAX >> (u64::BITS - width);
+    let values: Vec<u64> = if width <= 8 {
+        (0..=mask).collect()
+    } else {
+        [0, 1, 0x8000_0001, 0xDEAD_BEEF_1234_5678, u64::MAX]
+            .into_iter()
+            .map(|value| value & mask)
+            .collect()
+    };
+
+    values
+        .into_iter()
+        .map(|value| UnaryGateTestData {
+            input: LogicState::from_u128(value as u128, width).unwrap(),
+            output: LogicState::from_u128(convert(value) as u128, width).unwrap(),
+        })
+        .collect()
+}
+
+#[test]
+fn binary_to_gray_code() {
+    let add_bin2gray = |builder: &mut SimulatorBuilder, input, output| {
+        builder.add_component(BinaryToGrayPorts { input, output })
+    };
+
+    for width in [4, 8, 33, 64] {
+        test_unary_gate(
+            add_bin2gray,
+            width,
+            &gray_code_test_data(width, binary_to_gray),
+            2,
+        );
+    }
+
+    let test_data = [UnaryGateTestData {
+        input: LogicState::parse("0110X001").unwrap(),
+        output: LogicState::parse("0101XX01").unwrap(),
+    }];
+    test_unary_gate(add_bin2gray, 8, &test_data, 2);
+}
+
+#[test]
+fn gray_to_binary_code() {
+    let add_gray2bin = |builder: &mut SimulatorBuilder, input, output| {
+        builder.add_component(GrayToBinaryPorts { input, output })
+    };
+
+    for width in [4, 8, 33, 64] {
+        test_unary_gate(
+            add_gray2bin,
+            width,
+            &gray_code_test_data(width, gray_to_binary),
+            2,
+        );
+    }
+
+    let test_data = [UnaryGateTestData {
+        input: LogicState::parse("0110Z001").unwrap(),
+        output: LogicState::parse("0100XXXX").unwrap(),
+    }];
+    test_unary_gate(add_gray2bin, 8, &test_data, 2);
+}
+
+#[test]
+fn neg() {
+    const TEST_DATA: &[UnaryGateTestData] = unary_gate_test_data!(
+        HIGH_Z -> UNDEFINED,
+        UNDEFINED -> UNDEFINED,
+
+        0 -> 0,
+        1 -> LOGIC_1,
+        LOGIC_1 -> 1,
+    );
+
+    test_unary_gate(add_neg, 16, TEST_DATA, 2);
+    test_unary_gate(add_neg, 32, TEST_DATA, 2);
+    test_unary_gate(add_neg, 33, TEST_DATA, 2);
+    test_unary_gate(add_neg, 64, TEST_DATA, 2);
+}
+
+#[test]
+fn abs() {
+    const TEST_DATA: &[UnaryGateTestData] = unary_gate_test_data!(
+        HIGH_Z -> UNDEFINED,
+        UNDEFINED -> UNDEFINED,
+
+        0 -> 0,
+        1 -> 1,
+        LOGIC_1 -> 1,
+    );
+
+    test_unary_gate(add_abs, 16, TEST_DATA, 2);
+    test_unary_gate(add_abs, 32, TEST_DATA, 2);
+    test_unary_gate(add_abs, 33, TEST_DATA, 2);
+    test_unary_gate(add_abs, 64, TEST_DATA, 2);
+
+    const TEST_DATA_16: &[UnaryGateTestData] = unary_gate_test_data!(
+        0x7FFF -> 0x7FFF,
+        0x8000 -> 0x8000,
+        0xFFFB -> 5,
+        "X005" -> UNDEFINED,
+    );
+
+    test_unary_gate(add_abs, 16, TEST_DATA_16, 2);
+
+    const TEST_DATA_32: &[UnaryGateTestData] = unary_gate_test_data!(
+        0x7FFF_FFFF -> 0x7FFF_FFFF,
+        0x8000_0000 -> 0x8000_0000,
+        0xFFFF_FFFB -> 5,
+        "0XXX_FFFF" -> "XXXX_FFFF",
+    );
+
+    test_unary_gate(add_abs, 32, TEST_DATA_32, 2);
+}
+
+//#[test]
+//fn mul() {
+//    let test_data: &[BinaryGateTestData] = binary_gate_test_data!(
+//        (HIGH_Z, HIGH_Z) -> UNDEFINED,
+//        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+//        (UNDEFINED, HIGH_Z) -> UNDEFINED,
+//        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+//        (HIGH_Z, 0) -> UNDEFINED,
+//        (UNDEFINED, 0) -> UNDEFINED,
+//        (0, HIGH_Z) -> UNDEFINED,
+//        (0, UNDEFINED) -> UNDEFINED,
+//
+//        (0, 0) -> 0,
+//        (0, 1) -> 0,
+//        (1, 0) -> 0,
+//        (1, 1) -> 1,
+//        (0, {u32::MAX}) -> 0,
+//        ({u32::MAX}, 0) -> 0,
+//        (1, {u32::MAX}) -> {u32::MAX},
+//        ({u32::MAX}, 1) -> {u32::MAX},
+//        ({u32::MAX}, {u32::MAX}) -> [1, u32::MAX - 1],
+//        ([u32::MAX, u32::MAX], [u32::MAX, u32::MAX]) -> [1, 0, u32::MAX - 1, u32::MAX],
+//        ([0x658c0c38, 0xd50cebfb], [0x901cfad8, 0xc0083189]) -> [0x4838ff40, 0x2201c171, 0xe109006d, 0x9fd0829d],
+//    );
+//
+//    test_binary_gate(SimulatorBuilder::add_mul, 16, test_data, 2);
+//    test_binary_gate(SimulatorBuilder::add_mul, 32, test_data, 2);
+//    test_binary_gate(SimulatorBuilder::add_mul, 33, test_data, 2);
+//    test_binary_gate(SimulatorBuilder::add_mul, 64, test_data, 2);
+//    test_binary_gate(SimulatorBuilder::add_mul, 128, test_data, 2);
+//}
+
+#[test]
+fn not_gate() {
+    const TEST_DATA: &[UnaryGateTestData] = unary_gate_test_data!(
+        HIGH_Z -> UNDEFINED,
+        UNDEFINED -> UNDEFINED,
+        LOGIC_0 -> LOGIC_1,
+        LOGIC_1 -> LOGIC_0,
+    );
+
+    test_unary_gate(add_not_gate, 1, TEST_DATA, 2);
+    test_unary_gate(add_not_gate, 32, TEST_DATA, 2);
+    test_unary_gate(add_not_gate, 33, TEST_DATA, 2);
+    test_unary_gate(add_not_gate, 64, TEST_DATA, 2);
+    test_unary_gate(add_not_gate, MAX_WIRE_WIDTH, TEST_DATA, 2);
+}
+
+#[test]
+fn not_gate_max_width() {
+    const ATOM_COUNT: usize = (MAX_WIRE_WIDTH / 32) as usize;
+
+    // A different pattern in every atom, so misplaced atoms are detected.
+    let mut words = [0u32; ATOM_COUNT];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = 0x8000_0001 | ((i as u32) << 8);
+    }
+    let inverted = words.map(|word| !word);
+
+    let test_data = [
+        UnaryGateTestData {
+            input: LogicState::from_big_int(&words).unwrap(),
+            output: LogicState::from_big_int(&inverted).unwrap(),
+        },
+        UnaryGateTestData {
+            input: LogicState::from_big_int(&inverted).unwrap(),
+            output: LogicState::from_big_int(&words).unwrap(),
+        },
+    ];
+
+    test_unary_gate(add_not_gate, MAX_WIRE_WIDTH, &test_data, 2);
+}
+
+const BUFFER_TEST_DATA: &[BinaryGateTestData] = binary_gate_test_data!(
+        (HIGH_Z, HIGH_Z) -> HIGH_Z,
+        (UNDEFINED, HIGH_Z) -> HIGH_Z,
+        (LOGIC_0, HIGH_Z) -> HIGH_Z,
+        (LOGIC_1, HIGH_Z) -> HIGH_Z,
+
+        (HIGH_Z, UNDEFINED) -> UNDEFINED,
+        (UNDEFINED, UNDEFINED) -> UNDEFINED,
+        (LOGIC_0, UNDEFINED) -> UNDEFINED,
+        (LOGIC_1, UNDEFINED) -> UNDEFINED,
+
+        (HIGH_Z, LOGIC_0) -> HIGH_Z,
+        (UNDEFINED, LOGIC_0) -> HIGH_Z,
+        (LOGIC_0, LOGIC_0) -> HIGH_Z,
+        (LOGIC_1, LOGIC_0) -> HIGH_Z,
+
+        (HIGH_Z, LOGIC_1) -> UNDEFINED,
+        (UNDEFINED, LOGIC_1) -> UNDEFINED,
+        (LOGIC_0, LOGIC_1) -> LOGIC_0,
+        (LOGIC_1, LOGIC_1) -> LOGIC_1,
+);
+
+fn test_buffer<F>(add_buffer: F)
+where
+    F: Fn(&mut SimulatorBuilder, WireId, WireId, WireId) -> AddComponentResult,
+{
+    for width in [1, 32, 33, 64] {
+        let mut builder = SimulatorBuilder::default();
+
+        let input = builder.add_wire(width).unwrap();
+        let enable = builder.add_wire(1).unwrap();
+        let output = builder.add_wire(width).unwrap();
+        let _gate = add_buffer(&mut builder, input, enable, output).unwrap();
+
+        let mut sim = builder.build().unwrap();
+
+        for (i, test_data) in BUFFER_TEST_DATA.iter().enumerate() {
+            sim.set_wire_drive(input, &test_data.input_a).unwrap();
+            sim.set_wire_drive(enable, &test_data.input_b).unwrap();
+
+            match sim.run(2) {
+                SimulationRunResult::Ok => {}
+                SimulationRunResult::MaxStepsReached => panic!("[TEST {i}] exceeded max steps"),
+                SimulationRunResult::Err { conflicting_wires } => {
+                    panic!("[TEST {i}] wire conflicts: {conflicting_wires:?}");
+                }
+                SimulationRunResult::UnknownPropagated { components } => {
+                    panic!("[TEST {i}] unknown propagation: {components:?}");
+                }
+            }
+
+            let output_state = sim.get_wire_state(output).unwrap();
+
+            assert!(
+                output_state.eq(&test_data.output, width),
+                "[TEST {i}]  expected: {}  actual: {}",
+                test_data.output.to_string(width),
+                output_state.to_string(width),
+            );
+        }
+    }
+}
+
+#[test]
+fn buffer() {
+    test_buffer(|builder, input, enable, output| {
+        builder.add_component(BufferPorts {
+            input,
+            enable,
+            output,
+        })
+    });
+}
+
+#[test]
+fn output_enable() {
+    test_buffer(|builder, source, enable, output| {
+        builder.add_component(OutputEnablePorts {
+            source,
+            enable,
+            output,
+        })
+    });
+}
+
+#[test]
+fn output_enable_shared_bus() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(8).unwrap();
+    let b = builder.add_wire(8).unwrap();
+    let sum = builder.add_wire(8).unwrap();
+    let difference = builder.add_wire(8).unwrap();
+    let enable_sum = builder.add_wire(1).unwrap();
+    let enable_difference = builder.add_wire(1).unwrap();
+    let bus = builder.add_wire(8).unwrap();
+
+    add_add(&mut builder, a, b, sum).unwrap();
+    add_sub(&mut builder, a, b, difference).unwrap();
+    builder
+        .add_component(OutputEnablePorts {
+            source: sum,
+            enable: enable_sum,
+            output: bus,
+        })
+        .unwrap();
+    builder
+        .add_component(OutputEnablePorts {
+            source: difference,
+            enable: enable_difference,
+            output: bus,
+        })
+        .unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(a, &LogicState::from_int(7)).unwrap();
+    sim.set_wire_drive(b, &LogicState::from_int(3)).unwrap();
+
+    sim.set_wire_drive(enable_sum, &LogicState::LOGIC_1)
+        .unwrap();
+    sim.set_wire_drive(enable_difference, &LogicState::LOGIC_0)
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(bus).unwrap().to_int(8).unwrap(), 10);
+
+    sim.set_wire_drive(enable_sum, &LogicState::LOGIC_0)
+        .unwrap();
+    sim.set_wire_drive(enable_difference, &LogicState::LOGIC_1)
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(bus).unwrap().to_int(8).unwrap(), 4);
+
+    sim.set_wire_drive(enable_difference, &LogicState::LOGIC_0)
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.get_wire_state(bus).unwrap().eq(&LogicState::HIGH_Z, 8));
+
+    sim.set_wire_drive(enable_sum, &LogicState::LOGIC_1)
+        .unwrap();
+    sim.set_wire_drive(enable_difference, &LogicState::LOGIC_1)
+        .unwrap();
+    assert!(matches!(
+        sim.run(8),
+        SimulationRunResult::Err { conflicting_wires } if conflicting_wires[..] == [bus]
+    ));
+}
+
+#[test]
+fn gate_inputs_from_iterator() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input_wires: Vec<_> = (0..=MAX_GATE_INPUTS)
+        .map(|_| builder.add_wire(1).unwrap())
+        .collect();
+    let output = builder.add_wire(1).unwrap();
+
+    let result = builder.add_component(AndGatePorts {
+        inputs: input_wires.iter().copied().take(MAX_GATE_INPUTS),
+        output,
+    });
+    assert!(result.is_ok());
+
+    let result = builder.add_component(AndGatePorts {
+        inputs: input_wires.iter().copied(),
+        output,
+    });
+    assert!(matches!(result, Err(AddComponentError::TooManyInputs)));
+}
+
+#[test]
+fn truth_table() {
+    struct TestData {
+        input_a: LogicState,
+        input_b: LogicState,
+        output: LogicState,
+    }
+
+    let table: Vec<_> = (0..8).map(|i| LogicState::from_int(i * 5)).collect();
+
+    let test_data = [
+        TestData {
+            input_a: logic_state!(0),
+            input_b: logic_state!(0),
+            output: logic_state!(0),
+        },
+        TestData {
+            input_a: logic_state!(1),
+            input_b: logic_state!(0),
+            output: logic_state!(5),
+        },
+        TestData {
+            input_a: logic_state!(0),
+            input_b: logic_state!(1),
+            output: logic_state!(10),
+        },
+        TestData {
+            input_a: logic_state!(1),
+            input_b: logic_state!(3),
+            output: logic_state!(35),
+        },
+        TestData {
+            input_a: logic_state!(HIGH_Z),
+            input_b: logic_state!(3),
+            output: logic_state!(UNDEFINED),
+        },
+        TestData {
+            input_a: logic_state!(1),
+            input_b: logic_state!({% X, 1}),
+            output: logic_state!(UNDEFINED),
+        },
+    ];
+
+    let mut builder = SimulatorBuilder::default();
+
+    let input_a = builder.add_wire(1).unwrap();
+    let input_b = builder.add_wire(2).unwrap();
+    let output = builder.add_wire(6).unwrap();
+    let _lut = builder
+        .add_component(TruthTablePorts {
+            inputs: &[input_a, input_b],
+            output,
+            table: &table,
+        })
+        .unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    for (i, test_data) in test_data.iter().enumerate() {
+        sim.set_wire_drive(input_a, &test_data.input_a).unwrap();
+        sim.set_wire_drive(input_b, &test_data.input_b).unwrap();
+
+        match sim.run(2) {
+            SimulationRunResult::Ok => {}
+            SimulationRunResult::MaxStepsReached => panic!("[TEST {i}] exceeded max steps"),
+            SimulationRunResult::Err { conflicting_wires } => {
+                panic!("[TEST {i}] wire conflicts: {conflicting_wires:?}");
+            }
+            SimulationRunResult::UnknownPropagated { components } => {
+                panic!("[TEST {i}] unknown propagation: {components:?}");
+            }
+        }
+
+        let output_state = sim.get_wire_state(output).unwrap();
+
+        assert!(
+            output_state.eq(&test_data.output, 6),
+            "[TEST {i}]  expected: {}  actual: {}",
+            test_data.output.to_string(6),
+            output_state.to_string(6),
+        );
+    }
+}
+
+#[test]
+fn truth_table_invalid_length() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(2).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    let result = builder.add_component(TruthTablePorts {
+        inputs: &[input],
+        output,
+        table: &[LogicState::LOGIC_0; 3],
+    });
+
+    assert!(matches!(result, Err(AddComponentError::InvalidTableLength)));
+    // The failed component must not leave a driver behind on its output.
+    assert_eq!(builder_driver_count(&builder, output), 0);
+}
+
+fn build_conflicting_drivers(detect_conflicts: bool) -> (Simulator, WireId) {
+    let mut builder = SimulatorBuilder::default();
+    if !detect_conflicts {
+        builder.disable_conflict_detection();
+    }
+
+    let input_a = builder.add_wire(1).unwrap();
+    let input_b = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input_a, output).unwrap();
+    add_not_gate(&mut builder, input_b, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+    sim.set_wire_drive(input_a, &LogicState::LOGIC_0).unwrap();
+    sim.set_wire_drive(input_b, &LogicState::LOGIC_1).unwrap();
+
+    (sim, output)
+}
+
+#[test]
+fn driver_conflict() {
+    let (mut sim, output) = build_conflicting_drivers(true);
+
+    match sim.run(4) {
+        SimulationRunResult::Err { conflicting_wires } => {
+            assert_eq!(conflicting_wires.as_ref(), &[output]);
+        }
+        _ => panic!("conflict was not detected"),
+    }
+}
+
+#[test]
+fn driver_conflict_detection_disabled() {
+    let (mut sim, _) = build_conflicting_drivers(false);
+
+    assert!(matches!(sim.run(4), SimulationRunResult::Ok));
+}
+
+#[test]
+fn driver_conflict_into_result() {
+    let (mut sim, output) = build_conflicting_drivers(true);
+
+    match sim.run(4).into_result() {
+        Err(SimulationError::Conflict { conflicting_wires }) => {
+            assert_eq!(conflicting_wires.as_ref(), &[output]);
+        }
+        _ => panic!("conflict was not detected"),
+    }
+}
+
+#[test]
+fn clock_divider() {
+    let mut builder = SimulatorBuilder::default();
+
+    let clock_in = builder.add_wire(1).unwrap();
+    let clock_out = builder.add_wire(1).unwrap();
+    let _divider = builder
+        .add_component(ClockDividerPorts {
+            clock_in,
+            clock_out,
+            divisor: 3,
+        })
+        .unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    // The expected output after each rising edge
+    const EXPECTED: &[bool] = &[false, false, true, true, true, false, false, false, true];
+
+    sim.set_wire_drive(clock_in, &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    for (i, &expected) in EXPECTED.iter().enumerate() {
+        for clock in [LogicState::LOGIC_1, LogicState::LOGIC_0] {
+            sim.set_wire_drive(clock_in, &clock).unwrap();
+
+            match sim.run(8) {
+                SimulationRunResult::Ok => {}
+                SimulationRunResult::MaxStepsReached => panic!("[TEST {i}] exceeded max steps"),
+                SimulationRunResult::Err { conflicting_wires } => {
+                    panic!("[TEST {i}] wire conflicts: {conflicting_wires:?}");
+                }
+                SimulationRunResult::UnknownPropagated { components } => {
+                    panic!("[TEST {i}] unknown propagation: {components:?}");
+                }
+            }
+
+            let output_state = sim.get_wire_state(clock_out).unwrap();
+            assert_eq!(output_state.to_bool(), Some(expected), "[TEST {i}]");
+        }
+    }
+}
+
+#[test]
+fn clock_divider_invalid_divisor() {
+    let mut builder = SimulatorBuilder::default();
+
+    let clock_in = builder.add_wire(1).unwrap();
+    let clock_out = builder.add_wire(1).unwrap();
+    let result = builder.add_component(ClockDividerPorts {
+        clock_in,
+        clock_out,
+        divisor: 0,
+    });
+
+    assert!(matches!(result, Err(AddComponentError::InvalidDivisor)));
+    assert_eq!(builder_driver_count(&builder, clock_out), 0);
+}
+
+#[test]
+fn counter() {
+    let mut builder = SimulatorBuilder::default();
+
+    let clock = builder.add_wire(1).unwrap();
+    let enable = builder.add_wire(1).unwrap();
+    let reset = builder.add_wire(1).unwrap();
+    let count_out = builder.add_wire(2).unwrap();
+    let wrap_out = builder.add_wire(1).unwrap();
+    let counter = builder
+        .add_component(CounterPorts {
+            clock,
+            enable,
+            reset,
+            count_out,
+            wrap_out,
+        })
+        .unwrap();
+
+    // A wrap output wider than one bit is rejected before the count output is connected.
+    let wide_wrap_out = builder.add_wire(2).unwrap();
+    let result = builder.add_component(CounterPorts {
+        clock,
+        enable,
+        reset,
+        count_out,
+        wrap_out: wide_wrap_out,
+    });
+    assert!(matches!(result, Err(AddComponentError::WidthMismatch)));
+    assert_eq!(builder_driver_count(&builder, count_out), 1);
+
+    let mut sim = builder.build().unwrap();
+    assert_eq!(sim.wire_drivers(count_out).unwrap(), [(counter, 0)]);
+    assert_eq!(sim.wire_drivers(wrap_out).unwrap(), [(counter, 1)]);
+
+    sim.set_wire_drive(clock, &LogicState::LOGIC_0).unwrap();
+    sim.set_wire_drive(reset, &LogicState::LOGIC_0).unwrap();
+    sim.set_wire_drive(enable, &LogicState::LOGIC_1).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(count_out).unwrap().to_int(2), Ok(0));
+    assert_eq!(sim.get_wire_state(wrap_out).unwrap().to_bool(), Some(false));
+
+    // (enable, reset, expected count, expected wrap) at each rising edge
+    const EXPECTED: &[(bool, bool, u32, bool)] = &[
+        (true, false, 1, false),
+        (true, false, 2, false),
+        (true, false, 3, false),
+        (true, false, 0, true),
+        (true, false, 1, false),
+        (false, false, 1, false),
+        (true, true, 0, false),
+        (true, false, 1, false),
+    ];
+
+    for (i, &(enable_state, reset_state, count, wrap)) in EXPECTED.iter().enumerate() {
+        sim.set_wire_drive(enable, &LogicState::from_bool(enable_state))
+            .unwrap();
+        sim.set_wire_drive(reset, &LogicState::from_bool(reset_state))
+            .unwrap();
+
+        for clock_state in [LogicState::LOGIC_1, LogicState::LOGIC_0] {
+            sim.set_wire_drive(clock, &clock_state).unwrap();
+            assert!(matches!(sim.run(8), SimulationRunResult::Ok), "[TEST {i}]");
+
+            let count_state = sim.get_wire_state(count_out).unwrap();
+            let wrap_state = sim.get_wire_state(wrap_out).unwrap();
+            assert_eq!(count_state.to_int(2), Ok(count), "[TEST {i}]");
+            assert_eq!(wrap_state.to_bool(), Some(wrap), "[TEST {i}]");
+        }
+    }
+}
+
+#[test]
+fn wire_alias() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(8).unwrap();
+    let wire_a = builder.add_wire(8).unwrap();
+    let wire_b = builder.add_wire(8).unwrap();
+    let output = builder.add_wire(8).unwrap();
+    add_not_gate(&mut builder, input, wire_b).unwrap();
+    add_not_gate(&mut builder, wire_a, output).unwrap();
+    builder.alias_wire(wire_a, wire_b).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::from_int(0x0F))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    let state_a = sim.get_wire_state(wire_a).unwrap();
+    let state_b = sim.get_wire_state(wire_b).unwrap();
+    let output_state = sim.get_wire_state(output).unwrap();
+    assert_eq!(state_a.to_int(8), Ok(0xF0));
+    assert_eq!(state_b.to_int(8), Ok(0xF0));
+    assert_eq!(output_state.to_int(8), Ok(0x0F));
+}
+
+#[test]
+fn force_wire_alias() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(8).unwrap();
+    let wire_a = builder.add_wire(8).unwrap();
+    let wire_b = builder.add_wire(8).unwrap();
+    let output = builder.add_wire(8).unwrap();
+    add_not_gate(&mut builder, input, wire_a).unwrap();
+    add_not_gate(&mut builder, wire_b, output).unwrap();
+    builder.alias_wire(wire_a, wire_b).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::from_int(0x0F))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_int(8), Ok(0x0F));
+
+    // Forcing one alias forces the other one too.
+    sim.force_wire(wire_a, &LogicState::from_int(0x3C)).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(wire_b).unwrap().to_int(8), Ok(0x3C));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_int(8), Ok(0xC3));
+
+    sim.release_wire(wire_b).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(wire_a).unwrap().to_int(8), Ok(0xF0));
+    assert_eq!(sim.get_wire_state(wire_b).unwrap().to_int(8), Ok(0xF0));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_int(8), Ok(0x0F));
+}
+
+#[test]
+fn wire_alias_width_mismatch() {
+    let mut builder = SimulatorBuilder::default();
+
+    let wire_a = builder.add_wire(8).unwrap();
+    let wire_b = builder.add_wire(16).unwrap();
+
+    let result = builder.alias_wire(wire_a, wire_b);
+    assert!(matches!(result, Err(AliasWireError::WidthMismatch)));
+}
+
+#[test]
+fn wire_slice() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input_a = builder.add_wire(16).unwrap();
+    let input_b = builder.add_wire(8).unwrap();
+    let bus = builder.add_wire(40).unwrap();
+    let slice_a = builder.add_wire_slice(bus, 0, 16).unwrap();
+    let slice_b = builder.add_wire_slice(bus, 28, 8).unwrap();
+    add_not_gate(&mut builder, input_a, slice_a).unwrap();
+    add_not_gate(&mut builder, input_b, slice_b).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input_a, &LogicState::from_int(0x00FF))
+        .unwrap();
+    sim.set_wire_drive(input_b, &LogicState::from_int(0x0F))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    let expected = LogicState::parse("ZZZZ11110000ZZZZZZZZZZZZ1111111100000000").unwrap();
+    let bus_state = sim.get_wire_state(bus).unwrap();
+    assert!(
+        bus_state.eq(&expected, 40),
+        "expected: {}\nactual: {}",
+        expected.to_string(40),
+        bus_state.to_string(40),
+    );
+
+    let slice_state = sim.get_wire_state(slice_b).unwrap();
+    assert_eq!(slice_state.to_int(8), Ok(0xF0));
+}
+
+#[test]
+fn wire_slice_overlap_conflict() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input_a = builder.add_wire(8).unwrap();
+    let input_b = builder.add_wire(8).unwrap();
+    let bus = builder.add_wire(16).unwrap();
+    let slice_a = builder.add_wire_slice(bus, 0, 8).unwrap();
+    let slice_b = builder.add_wire_slice(bus, 4, 8).unwrap();
+    add_not_gate(&mut builder, input_a, slice_a).unwrap();
+    add_not_gate(&mut builder, input_b, slice_b).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input_a, &LogicState::from_int(0x00))
+        .unwrap();
+    sim.set_wire_drive(input_b, &LogicState::from_int(0xFF))
+        .unwrap();
+    assert!(matches!(
+        sim.run(8),
+        SimulationRunResult::Err { conflicting_wires } if conflicting_wires[..] == [bus]
+    ));
+}
+
+#[test]
+fn wire_slice_out_of_range() {
+    let mut builder = SimulatorBuilder::default();
+
+    let bus = builder.add_wire(16).unwrap();
+    let result = builder.add_wire_slice(bus, 12, 8);
+    assert!(matches!(result, Err(AddWireError::SliceOutOfRange)));
+}
+
+#[test]
+fn combinational_cycles() {
+    let mut builder = SimulatorBuilder::default();
+
+    let loop_wire = builder.add_wire(1).unwrap();
+    let self_loop = add_not_gate(&mut builder, loop_wire, loop_wire).unwrap();
+
+    let a = builder.add_wire(1).unwrap();
+    let b = builder.add_wire(1).unwrap();
+    let b_alias = builder.add_wire(1).unwrap();
+    let c = builder.add_wire(1).unwrap();
+    let ring_a = add_not_gate(&mut builder, a, b).unwrap();
+    let ring_b = add_not_gate(&mut builder, b_alias, c).unwrap();
+    let ring_c = add_and_gate(&mut builder, &[c, c], a).unwrap();
+    builder.alias_wire(b, b_alias).unwrap();
+
+    let input = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    let _gate = add_not_gate(&mut builder, input, output).unwrap();
+
+    let cycles = builder.detect_combinational_cycles();
+    assert_eq!(cycles, [vec![self_loop], vec![ring_a, ring_b, ring_c]]);
+}
+
+#[test]
+fn check_widths() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(8).unwrap();
+    let b = builder.add_wire(8).unwrap();
+    let sum = builder.add_wire(8).unwrap();
+    add_add(&mut builder, a, b, sum).unwrap();
+    assert!(builder.check_widths().is_ok());
+
+    let narrow = builder.add_wire(4).unwrap();
+    let bad_add = add_add(&mut builder, a, narrow, sum).unwrap();
+
+    let clock = builder.add_wire(2).unwrap();
+    let divided = builder.add_wire(1).unwrap();
+    let bad_div = builder
+        .add_component(ClockDividerPorts {
+            clock_in: clock,
+            clock_out: divided,
+            divisor: 2,
+        })
+        .unwrap();
+
+    // Outputs with a fixed width are rejected right away.
+    let result = builder.add_component(CompareEqual {
+        input_lhs: a,
+        input_rhs: b,
+        output: sum,
+    });
+    assert!(matches!(result, Err(AddComponentError::WidthMismatch)));
+
+    let errors = builder.check_widths().unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            WidthError {
+                component: bad_add,
+                port: ComponentPort::Input(1),
+                expected: 8,
+                actual: 4,
+            },
+            WidthError {
+                component: bad_div,
+                port: ComponentPort::Input(0),
+                expected: 1,
+                actual: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn assert_same_width() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(8).unwrap();
+    let b = builder.add_wire(8).unwrap();
+    let c = builder.add_wire(4).unwrap();
+
+    assert!(matches!(builder.assert_same_width(&[a]), Ok(8)));
+    assert!(matches!(builder.assert_same_width(&[a, b]), Ok(8)));
+    assert!(matches!(
+        builder.assert_same_width(&[a, b, c]),
+        Err(AddComponentError::WidthMismatch)
+    ));
+    assert!(matches!(
+        builder.assert_same_width(&[]),
+        Err(AddComponentError::WidthMismatch)
+    ));
+    assert!(matches!(
+        builder.assert_same_width(&[a, WireId::INVALID]),
+        Err(AddComponentError::InvalidWireId)
+    ));
+}
+
+#[test]
+fn component_info() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(4).unwrap();
+    let b = builder.add_wire(4).unwrap();
+    let sum = builder.add_wire(4).unwrap();
+    let inverted = builder.add_wire(4).unwrap();
+    let add = add_add(&mut builder, a, b, sum).unwrap();
+    let not = add_not_gate(&mut builder, sum, inverted).unwrap();
+
+    assert_eq!(builder.component_kind(add).unwrap(), ComponentKind::Add);
+    assert_eq!(builder.component_kind(not).unwrap(), ComponentKind::Not);
+    assert_eq!(builder.component_inputs(add).unwrap(), [a, b]);
+    assert_eq!(builder.component_inputs(not).unwrap(), [sum]);
+    assert!(builder.component_kind(ComponentId::INVALID).is_err());
+    assert!(builder.component_inputs(ComponentId::INVALID).is_err());
+}
+
+#[test]
+fn evaluation_order() {
+    let mut builder = SimulatorBuilder::default();
+
+    let mut wires = vec![builder.add_wire(1).unwrap()];
+    let mut gates = Vec::new();
+    for _ in 0..8 {
+        let output = builder.add_wire(1).unwrap();
+        gates.push(add_not_gate(&mut builder, *wires.last().unwrap(), output).unwrap());
+        wires.push(output);
+    }
+
+    assert!(builder.set_evaluation_order(&gates[1..]).is_err());
+    assert!(builder.set_evaluation_order(&[gates[0]; 8]).is_err());
+    assert!(builder
+        .set_evaluation_order(&[ComponentId::INVALID; 8])
+        .is_err());
+
+    gates.reverse();
+    builder.set_evaluation_order(&gates).unwrap();
+
+    // Evaluated after all listed components.
+    let output = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, *wires.last().unwrap(), output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(wires[0], &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(64), SimulationRunResult::Ok));
+    assert_eq!(
+        sim.get_wire_state(wires[8]).unwrap().get_bit_state(0),
+        LogicBitState::Logic0
+    );
+    assert_eq!(
+        sim.get_wire_state(output).unwrap().get_bit_state(0),
+        LogicBitState::Logic1
+    );
+}
+
+#[test]
+fn combinational_cycles_sequential_boundary() {
+    let mut builder = SimulatorBuilder::default();
+
+    let clock = builder.add_wire(1).unwrap();
+    let divided = builder.add_wire(1).unwrap();
+    builder
+        .add_component(ClockDividerPorts {
+            clock_in: clock,
+            clock_out: divided,
+            divisor: 2,
+        })
+        .unwrap();
+    add_not_gate(&mut builder, divided, clock).unwrap();
+
+    assert!(builder.detect_combinational_cycles().is_empty());
+}
+
+#[test]
+fn initial_wire_state() {
+    let mut builder = SimulatorBuilder::default();
+
+    let set = builder.add_wire(1).unwrap();
+    let reset = builder.add_wire(1).unwrap();
+    let q = builder.add_wire(1).unwrap();
+    let q_inv = builder.add_wire(1).unwrap();
+    add_nor_gate(&mut builder, &[reset, q_inv], q).unwrap();
+    add_nor_gate(&mut builder, &[set, q], q_inv).unwrap();
+    builder
+        .set_initial_wire_state(q, &LogicState::LOGIC_0)
+        .unwrap();
+    builder
+        .set_initial_wire_state(q_inv, &LogicState::LOGIC_1)
+        .unwrap();
+    builder.set_wire_drive(set, &LogicState::LOGIC_0).unwrap();
+    builder.set_wire_drive(reset, &LogicState::LOGIC_0).unwrap();
+
+    let mut sim = builder.build().unwrap();
+    assert!(sim.get_wire_state(q).unwrap().eq(&LogicState::LOGIC_0, 1));
+
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.get_wire_state(q).unwrap().eq(&LogicState::LOGIC_0, 1));
+    assert!(sim
+        .get_wire_state(q_inv)
+        .unwrap()
+        .eq(&LogicState::LOGIC_1, 1));
+
+    sim.reset();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.get_wire_state(q).unwrap().eq(&LogicState::UNDEFINED, 1));
+
+    sim.reset_to_initial();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.get_wire_state(q).unwrap().eq(&LogicState::LOGIC_0, 1));
+    assert!(sim
+        .get_wire_state(q_inv)
+        .unwrap()
+        .eq(&LogicState::LOGIC_1, 1));
+}
+
+#[test]
+fn check_conflicts() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(1).unwrap();
+    let bus = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input, bus).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::LOGIC_1).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.check_conflicts().is_empty());
+
+    sim.set_wire_drive(bus, &LogicState::LOGIC_1).unwrap();
+    assert_eq!(sim.check_conflicts()[..], [bus]);
+    assert!(sim.get_wire_state(bus).unwrap().eq(&LogicState::LOGIC_0, 1));
+}
+
+#[test]
+fn max_reported_conflicts() {
+    fn build(max_reported_conflicts: u32) -> (Simulator, [WireId; 3]) {
+        let mut builder = SimulatorBuilder::default();
+        builder
+            .max_reported_conflicts(max_reported_conflicts)
+            .unwrap();
+
+        let input = builder.add_wire(1).unwrap();
+        let buses = [(); 3].map(|_| builder.add_wire(1).unwrap());
+        for bus in buses {
+            add_not_gate(&mut builder, input, bus).unwrap();
+            builder.set_wire_drive(bus, &LogicState::LOGIC_1).unwrap();
+        }
+        builder.set_wire_drive(input, &LogicState::LOGIC_1).unwrap();
+
+        (builder.build().unwrap(), buses)
+    }
+
+    let (mut sim, buses) = build(DEFAULT_MAX_REPORTED_CONFLICTS);
+    match sim.run(8) {
+        SimulationRunResult::Err { conflicting_wires } => {
+            let mut conflicting_wires = conflicting_wires.into_vec();
+            conflicting_wires.sort_by_key(|wire| buses.iter().position(|bus| bus == wire));
+            assert_eq!(conflicting_wires, buses);
+        }
+        _ => panic!("expected a conflict"),
+    }
+
+    let (mut sim, buses) = build(2);
+    match sim.run(8) {
+        SimulationRunResult::Err { conflicting_wires } => {
+            assert_eq!(conflicting_wires.len(), 2);
+            assert!(conflicting_wires.iter().all(|wire| buses.contains(wire)));
+        }
+        _ => panic!("expected a conflict"),
+    }
+    assert_eq!(sim.check_conflicts().len(), 2);
+
+    let (mut sim, _) = build(0);
+    match sim.run(8) {
+        SimulationRunResult::Err { conflicting_wires } => assert!(conflicting_wires.is_empty()),
+        _ => panic!("expected a conflict"),
+    }
+
+    let mut builder = SimulatorBuilder::default();
+    assert!(builder
+        .max_reported_conflicts(MAX_REPORTED_CONFLICTS_LIMIT)
+        .is_ok());
+    assert!(builder
+        .max_reported_conflicts(MAX_REPORTED_CONFLICTS_LIMIT + 1)
+        .is_err());
+}
+
+#[test]
+fn wires_equal() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(8).unwrap();
+    let b = builder.add_wire(16).unwrap();
+    let c = builder.add_wire(8).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(a, &LogicState::from_int(0x34)).unwrap();
+    sim.set_wire_drive(b, &LogicState::from_int(0x1234))
+        .unwrap();
+    sim.set_wire_drive(c, &LogicState::from_int(0x43)).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    assert!(sim.wires_equal(a, b).unwrap());
+    assert!(!sim.wires_equal(a, c).unwrap());
+    assert!(sim.wires_equal(a, WireId::INVALID).is_err());
+}
+
+#[test]
+fn wire_drivers() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(8).unwrap();
+    let bus = builder.add_wire(16).unwrap();
+    let slice = builder.add_wire_slice(bus, 8, 8).unwrap();
+    let gate_a = add_not_gate(&mut builder, input, bus).unwrap();
+    let gate_b = add_not_gate(&mut builder, input, slice).unwrap();
+    let gate_c = add_not_gate(&mut builder, input, bus).unwrap();
+
+    let sim = builder.build().unwrap();
+
+    assert_eq!(
+        sim.wire_drivers(bus).unwrap(),
+        [(gate_a, 0), (gate_c, 0), (gate_b, 0)]
+    );
+    assert_eq!(sim.wire_drivers(slice).unwrap(), [(gate_b, 0)]);
+    assert!(sim.wire_drivers(input).unwrap().is_empty());
+    assert!(sim.wire_drivers(WireId::INVALID).is_err());
+}
+
+#[test]
+fn wire_drivers_failed_component() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    let gate = add_not_gate(&mut builder, input, output).unwrap();
+
+    // The output is connected before the invalid input is found.
+    let result = add_not_gate(&mut builder, WireId::INVALID, output);
+    assert!(matches!(result, Err(AddComponentError::InvalidWireId)));
+
+    let sim = builder.build().unwrap();
+    assert_eq!(sim.wire_drivers(output).unwrap(), [(gate, 0)]);
+}
+
+#[test]
+fn pull() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let enable = builder.add_wire(1).unwrap();
+    let bus = builder.add_wire(4).unwrap();
+    let _buffer = builder
+        .add_component(BufferPorts {
+            input,
+            enable,
+            output: bus,
+        })
+        .unwrap();
+    builder
+        .add_pull(PullPorts {
+            wire: bus,
+            value: LogicBitState::Logic1,
+        })
+        .unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    let test_data: &[BinaryGateTestData] = binary_gate_test_data!(
+        (0x5, LOGIC_0) -> 0xF,
+        (0x5, LOGIC_1) -> 0x5,
+        (0x0, LOGIC_1) -> 0x0,
+        (HIGH_Z, LOGIC_0) -> 0xF,
+    );
+
+    for (i, test_data) in test_data.iter().enumerate() {
+        sim.reset();
+
+        sim.set_wire_drive(input, &test_data.input_a).unwrap();
+        sim.set_wire_drive(enable, &test_data.input_b).unwrap();
+
+        match sim.run(8) {
+            SimulationRunResult::Ok => {}
+            SimulationRunResult::MaxStepsReached => panic!("[TEST {i}] exceeded max steps"),
+            SimulationRunResult::Err { conflicting_wires } => {
+                panic!("[TEST {i}] wire conflicts: {conflicting_wires:?}");
+            }
+            SimulationRunResult::UnknownPropagated { components } => {
+                panic!("[TEST {i}] unknown propagation: {components:?}");
+            }
+        }
+
+        let output_state = sim.get_wire_state(bus).unwrap();
+
+        assert!(
+            output_state.eq(&test_data.output, 4),
+            "[TEST {i}]  expected: {}  actual: {}",
+            test_data.output.to_string(4),
+            output_state.to_string(4),
+        );
+    }
+}
+
+#[test]
+fn driven_wires() {
+    let mut builder = SimulatorBuilder::default();
+
+    let wire_a = builder.add_wire(8).unwrap();
+    let wire_b = builder.add_wire(40).unwrap();
+    let wire_c = builder.add_wire(4).unwrap();
+    builder
+        .set_wire_drive(wire_b, &LogicState::parse("Z0").unwrap())
+        .unwrap();
+    builder
+        .set_wire_drive(wire_c, &LogicState::parse("1Z").unwrap())
+        .unwrap();
+    assert_eq!(builder.driven_wires(), [wire_b, wire_c]);
+
+    builder.set_wire_drive(wire_c, &LogicState::HIGH_Z).unwrap();
+    builder
+        .set_wire_drive(wire_a, &LogicState::LOGIC_1)
+        .unwrap();
+    assert_eq!(builder.driven_wires(), [wire_a, wire_b]);
+}
+
+#[test]
+fn set_wire_drive_bits() {
+    let mut builder = SimulatorBuilder::default();
+
+    let wire = builder.add_wire(3).unwrap();
+    let bits = [
+        LogicBitState::Logic1,
+        LogicBitState::HighZ,
+        LogicBitState::Logic0,
+    ];
+    builder.set_wire_drive_bits(wire, &bits).unwrap();
+    assert!(builder
+        .get_wire_drive(wire)
+        .unwrap()
+        .eq(&LogicState::from_bits(&bits).unwrap(), 3));
+
+    assert!(matches!(
+        builder.set_wire_drive_bits(wire, &bits[..2]),
+        Err(SetWireDriveBitsError::WidthMismatch)
+    ));
+    assert!(matches!(
+        builder.set_wire_drive_bits(WireId::INVALID, &bits),
+        Err(SetWireDriveBitsError::InvalidWireId)
+    ));
+}
+
+#[test]
+fn set_wire_drive_range() {
+    let mut builder = SimulatorBuilder::default();
+
+    let wire = builder.add_wire(40).unwrap();
+    builder.set_wire_drive(wire, &LogicState::LOGIC_0).unwrap();
+
+    // The range crosses an atom boundary.
+    builder
+        .set_wire_drive_range(wire, 30, &LogicState::parse("1X01").unwrap(), 4)
+        .unwrap();
+    assert_eq!(
+        builder.get_wire_drive(wire).unwrap().to_string(40),
+        "0000001X01000000000000000000000000000000",
+    );
+
+    // Bits of the new drive past the range are ignored.
+    builder
+        .set_wire_drive_range(wire, 0, &LogicState::LOGIC_1, 2)
+        .unwrap();
+    assert_eq!(
+        builder.get_wire_drive(wire).unwrap().to_string(40),
+        "0000001X01000000000000000000000000000011",
+    );
+
+    assert!(matches!(
+        builder.set_wire_drive_range(wire, 38, &LogicState::LOGIC_1, 3),
+        Err(SetWireDriveRangeError::OutOfRange)
+    ));
+    assert!(matches!(
+        builder.set_wire_drive_range(wire, u32::MAX, &LogicState::LOGIC_1, 2),
+        Err(SetWireDriveRangeError::OutOfRange)
+    ));
+    assert!(matches!(
+        builder.set_wire_drive_range(WireId::INVALID, 0, &LogicState::LOGIC_1, 1),
+        Err(SetWireDriveRangeError::InvalidWireId)
+    ));
+}
+
+#[test]
+fn deterministic_layout() {
+    fn build_design() -> SimulatorBuilder {
+        let mut builder = SimulatorBuilder::default();
+
+        let a = builder.add_wire(8).unwrap();
+        let b = builder.add_wire(8).unwrap();
+        let c = builder.add_wire(8).unwrap();
+        let d = builder.add_wire(8).unwrap();
+        let e = builder.add_wire(8).unwrap();
+        add_and_gate(&mut builder, &[a, b], c).unwrap();
+        add_not_gate(&mut builder, c, d).unwrap();
+        add_not_gate(&mut builder, a, e).unwrap();
+        builder.alias_wire(d, e).unwrap();
+        builder.alias_wire(a, b).unwrap();
+        builder.merge_wire_aliases().unwrap();
+
+        builder
+    }
+
+    let builder_a = build_design();
+    let builder_b = build_design();
+
+    fn bytes<T: bytemuck::Pod>(data: &[T]) -> &[u8] {
+        bytemuck::cast_slice(data)
+    }
+
+    assert_eq!(
+        bytes(builder_a.wires.as_slice()),
+        bytes(builder_b.wires.as_slice())
+    );
+    assert_eq!(
+        bytes(builder_a.wire_drivers.as_slice()),
+        bytes(builder_b.wire_drivers.as_slice())
+    );
+    assert_eq!(
+        bytes(builder_a.inputs.as_slice()),
+        bytes(builder_b.inputs.as_slice())
+    );
+    assert_eq!(
+        bytes(builder_a.components.as_slice()),
+        bytes(builder_b.components.as_slice())
+    );
+}
+
+#[test]
+fn changed_wires() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input_a = builder.add_wire(1).unwrap();
+    let output_a = builder.add_wire(1).unwrap();
+    let input_b = builder.add_wire(1).unwrap();
+    let output_b = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input_a, output_a).unwrap();
+    add_not_gate(&mut builder, input_b, output_b).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input_a, &LogicState::LOGIC_0).unwrap();
+    sim.set_wire_drive(input_b, &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    sim.set_wire_drive(input_a, &LogicState::LOGIC_1).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.changed_wires(), &[input_a, output_a]);
+
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.changed_wires(), &[]);
+}
+
+#[test]
+fn wire_state_reader() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let mut builder = SimulatorBuilder::default();
+
+    // Long enough to take several batches to settle.
+    let mut wires = vec![builder.add_wire(1).unwrap()];
+    for _ in 0..100 {
+        let output = builder.add_wire(1).unwrap();
+        add_not_gate(&mut builder, *wires.last().unwrap(), output).unwrap();
+        wires.push(output);
+    }
+
+    let mut sim = builder.build().unwrap();
+    let reader = sim.wire_state_reader();
+    assert_eq!(
+        reader.get_wire_state(wires[100]).unwrap().get_bit_state(0),
+        LogicBitState::HighZ
+    );
+
+    let run_done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            // The states of one batch boundary are consistent: the chain has a defined state up
+            // to some wire and none after it.
+            while !run_done.load(Ordering::Acquire) {
+                let snapshot = reader.snapshot();
+                let resolved = wires
+                    .iter()
+                    .map(|&wire| snapshot.get_wire_state(wire).unwrap().to_bool().is_some());
+                let resolved_count = resolved.clone().take_while(|&resolved| resolved).count();
+                assert!(resolved.skip(resolved_count).all(|resolved| !resolved));
+            }
+        });
+
+        sim.set_wire_drive(wires[0], &LogicState::LOGIC_0).unwrap();
+        assert!(matches!(sim.run(1024), SimulationRunResult::Ok));
+        run_done.store(true, Ordering::Release);
+    });
+
+    assert_eq!(
+        reader.get_wire_state(wires[100]).unwrap().get_bit_state(0),
+        LogicBitState::Logic1
+    );
+    assert!(reader
+        .get_wire_state(wires[100])
+        .unwrap()
+        .eq(&sim.get_wire_state(wires[100]).unwrap(), 1));
+}
+
+#[test]
+fn nop() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    let nop = builder.add_component(NopPorts).unwrap();
+    let not = add_not_gate(&mut builder, input, output).unwrap();
+    assert_ne!(nop, not);
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::LOGIC_1, 1));
+}
+
+#[test]
+fn max_steps_observed() {
+    let mut builder = SimulatorBuilder::default();
+
+    let mut wires = vec![builder.add_wire(1).unwrap()];
+    for _ in 0..8 {
+        let output = builder.add_wire(1).unwrap();
+        add_not_gate(&mut builder, *wires.last().unwrap(), output).unwrap();
+        wires.push(output);
+    }
+
+    let mut sim = builder.build().unwrap();
+    assert_eq!(sim.max_steps_observed(), 0);
+
+    sim.set_wire_drive(wires[0], &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(64), SimulationRunResult::Ok));
+    let steps = sim.max_steps_observed();
+    assert!((steps > 0) && (steps <= 64));
+
+    sim.set_wire_drive(wires[0], &LogicState::LOGIC_1).unwrap();
+    assert!(matches!(sim.run(64), SimulationRunResult::Ok));
+    assert!(sim.max_steps_observed() >= steps);
+
+    sim.reset_stats();
+    assert_eq!(sim.max_steps_observed(), 0);
+}
+
+#[test]
+fn force_wire() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let middle = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, middle).unwrap();
+    add_not_gate(&mut builder, middle, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0101), 4));
+
+    // Forcing a driven wire does not produce a conflict.
+    sim.force_wire(middle, &LogicState::parse("1Z10").unwrap())
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(middle)
+        .unwrap()
+        .eq(&LogicState::parse("1Z10").unwrap(), 4));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::parse("0X01").unwrap(), 4));
+    assert!(sim
+        .get_wire_drive(middle)
+        .unwrap()
+        .eq(&LogicState::HIGH_Z, 4));
+
+    sim.release_wire(middle).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(middle)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0101), 4));
+}
+
+#[test]
+fn empty_circuit() {
+    let builder = SimulatorBuilder::default();
+    let mut sim = builder.build().unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.changed_wires().is_empty());
+
+    let mut builder = SimulatorBuilder::default();
+    let wire = builder.add_wire(4).unwrap();
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(wire, &LogicState::from_int(0b0110))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(wire)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0110), 4));
+}
+
+#[test]
+fn dump_shader_sources() {
+    let sim = SimulatorBuilder::default().build().unwrap();
+    let sources = sim.dump_shader_sources();
+    let workgroup_size = format!("const WORKGROUP_SIZE = {}u;", sim.workgroup_size());
+
+    let names = sources.each_ref().map(|(name, _)| *name);
+    assert_eq!(names, ["wire.wgsl", "component.wgsl", "reset.wgsl"]);
+
+    for (_, source) in &sources {
+        assert!(source.starts_with("const MAX_WIRE_WIDTH = "));
+        assert!(source.contains("struct LogicStateAtom"));
+        assert!(source.contains("fn main("));
+        assert!(source.contains(&workgroup_size));
+    }
+}
+
+#[test]
+fn debug_label() {
+    let mut builder = SimulatorBuilder::default();
+    builder.set_debug_label("gsim2");
+
+    let input = builder.add_wire(8).unwrap();
+    let output = builder.add_wire(8).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+    sim.set_wire_drive(input, &LogicState::from_int(0x0F))
+        .unwrap();
+    assert!(matches!(sim.run(2), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_int(8), Ok(0xF0));
+
+    let mut fork = sim.fork();
+    assert!(matches!(fork.run(2), SimulationRunResult::Ok));
+    assert_eq!(fork.get_wire_state(output).unwrap().to_int(8), Ok(0xF0));
+}
+
+#[test]
+fn build_with_instance() {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::VULKAN | wgpu::Backends::METAL,
+        ..Default::default()
+    });
+
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build_with_instance(&instance).unwrap();
+
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+}
+
+#[test]
+fn build_with_low_power_preference() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let options = GpuOptions {
+        power_preference: wgpu::PowerPreference::LowPower,
+    };
+    let mut sim = builder.build_with_options(options).unwrap();
+
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+}
+
+#[test]
+fn build_cloned() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let middle = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, middle).unwrap();
+    builder
+        .set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+
+    let mut first = builder.build_cloned().unwrap();
+
+    // The first simulator must not see components added afterwards.
+    add_not_gate(&mut builder, middle, output).unwrap();
+    let mut second = builder.build().unwrap();
+
+    assert!(matches!(first.run(8), SimulationRunResult::Ok));
+    assert!(first
+        .get_wire_state(middle)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+    assert!(first
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::HIGH_Z, 4));
+
+    assert!(matches!(second.run(8), SimulationRunResult::Ok));
+    assert!(second
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0101), 4));
+}
+
+#[test]
+fn add_component_detailed() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(8).unwrap();
+    let b = builder.add_wire(8).unwrap();
+    let wide_a = builder.add_wire(40).unwrap();
+    let wide_b = builder.add_wire(40).unwrap();
+    let wide_sum = builder.add_wire(40).unwrap();
+
+    let not = builder
+        .add_component_detailed(NotGatePorts {
+            input: a,
+            output: b,
+        })
+        .unwrap();
+    assert_eq!(not.output_state_offset, Some(0));
+    assert_eq!(not.output_count, 1);
+    assert_eq!(not.input_count, 1);
+
+    let add = builder
+        .add_component_detailed(AddPorts {
+            input_lhs: wide_a,
+            input_rhs: wide_b,
+            output: wide_sum,
+        })
+        .unwrap();
+    assert_ne!(add.id, not.id);
+    assert_eq!(add.output_state_offset, Some(1));
+    assert_eq!(add.output_count, 1);
+    assert_eq!(add.input_count, 2);
+
+    // The wide output occupies two atoms.
+    let not = builder
+        .add_component_detailed(NotGatePorts {
+            input: b,
+            output: a,
+        })
+        .unwrap();
+    assert_eq!(not.output_state_offset, Some(3));
+    assert_eq!(builder.component_count(), 3);
+}
+
+#[test]
+fn poll() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+    assert!(sim.poll());
+
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    // Running blocks until the last batch was read back, so it is always finished.
+    assert!(sim.poll());
+}
+
+#[test]
+fn fork() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    let mut fork = sim.fork();
+    assert!(fork
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+
+    fork.set_wire_drive(input, &LogicState::from_int(0b0011))
+        .unwrap();
+    assert!(matches!(fork.run(8), SimulationRunResult::Ok));
+    assert!(fork
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1100), 4));
+
+    assert!(sim
+        .get_wire_drive(input)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0101), 4));
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(output)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+}
+
+#[test]
+fn run_vectors() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input_a = builder.add_wire(8).unwrap();
+    let input_b = builder.add_wire(8).unwrap();
+    let sum = builder.add_wire(8).unwrap();
+    let difference = builder.add_wire(8).unwrap();
+    add_add(&mut builder, input_a, input_b, sum).unwrap();
+    add_sub(&mut builder, input_a, input_b, difference).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    let mut vectors = Vec::new();
+    for a in 0..16 {
+        for b in 0..16 {
+            vectors.push(vec![LogicState::from_int(a), LogicState::from_int(b)]);
+        }
+    }
+
+    let results = sim
+        .run_vectors(&[input_a, input_b], &vectors, &[sum, difference], 8)
+        .unwrap();
+    assert_eq!(results.len(), vectors.len());
+
+    for (i, result) in results.iter().enumerate() {
+        let a = (i / 16) as u32;
+        let b = (i % 16) as u32;
+        assert_eq!(result[0].to_int(8).unwrap(), a + b);
+        assert_eq!(result[1].to_int(8).unwrap(), a.wrapping_sub(b) & 0xFF);
+    }
+
+    assert!(matches!(
+        sim.run_vectors(&[input_a, input_b], &[vec![LogicState::LOGIC_0]], &[sum], 8),
+        Err(RunVectorsError::VectorLengthMismatch { vector_index: 0 })
+    ));
+}
+
+#[test]
+fn wire_and_component_count() {
+    let mut builder = SimulatorBuilder::default();
+    assert_eq!(builder.wire_count(), 0);
+    assert_eq!(builder.component_count(), 0);
+
+    let input = builder.add_wire(1).unwrap();
+    let middle = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input, middle).unwrap();
+    add_not_gate(&mut builder, middle, output).unwrap();
+    assert_eq!(builder.wire_count(), 3);
+    assert_eq!(builder.component_count(), 2);
+
+    let sim = builder.build().unwrap();
+    assert_eq!(sim.wire_count(), 3);
+    assert_eq!(sim.component_count(), 2);
+}
+
+#[test]
+fn run_profile() {
+    let mut builder = SimulatorBuilder::default();
+
+    let mut wires = vec![builder.add_wire(1).unwrap()];
+    for _ in 0..8 {
+        let output = builder.add_wire(1).unwrap();
+        add_not_gate(&mut builder, *wires.last().unwrap(), output).unwrap();
+        wires.push(output);
+    }
+
+    builder.enable_profiling();
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(wires[0], &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(64), SimulationRunResult::Ok));
+
+    let profile = sim.last_run_profile().unwrap();
+    let last_sample = profile.samples().last().unwrap();
+    assert_eq!(last_sample.wires_changed, 0);
+    assert_eq!(last_sample.components_changed, 0);
+    assert!((profile.step_count() > 0) && (profile.step_count() <= 64));
+    assert_eq!(profile.step_count() as u64, sim.max_steps_observed());
+}
+
+#[test]
+fn run_profile_disabled() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim.last_run_profile().is_none());
+    assert!(sim.last_pass_timings().is_none());
+}
+
+#[test]
+fn strict_unknown() {
+    let mut builder = SimulatorBuilder::default();
+
+    let floating = builder.add_wire(1).unwrap();
+    let inverted = builder.add_wire(1).unwrap();
+    let not_gate = add_not_gate(&mut builder, floating, inverted).unwrap();
+
+    // A 0 on the other input masks the unknown one.
+    let enable = builder.add_wire(1).unwrap();
+    let masked = builder.add_wire(1).unwrap();
+    add_and_gate(&mut builder, &[floating, enable], masked).unwrap();
+
+    builder.strict_unknown();
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(enable, &LogicState::LOGIC_0).unwrap();
+    match sim.run(64) {
+        SimulationRunResult::UnknownPropagated { components } => {
+            assert_eq!(&*components, [not_gate]);
+        }
+        result => panic!("{result:?}"),
+    }
+
+    sim.set_wire_drive(floating, &LogicState::LOGIC_1).unwrap();
+    assert!(matches!(sim.run(64), SimulationRunResult::Ok));
+}
+
+#[cfg(feature = "event-log")]
+#[test]
+fn event_log_replay() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build_cloned().unwrap();
+    let mut replay_sim = builder.build().unwrap();
+
+    sim.start_recording();
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    sim.set_wire_drive_range(input, 2, &LogicState::from_int(0b11), 2)
+        .unwrap();
+    sim.force_wire(output, &LogicState::from_int(0b1001))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    let log = sim.take_event_log().unwrap();
+    assert_eq!(log.events().len(), 4);
+
+    let run_results = replay_sim.replay(&log).unwrap();
+    assert_eq!(run_results.len(), 2);
+    assert_eq!(
+        replay_sim.get_wire_drive(input).unwrap().to_int(4),
+        Ok(0b1101)
+    );
+    assert_eq!(
+        replay_sim.get_wire_state(output).unwrap().to_int(4),
+        sim.get_wire_state(output).unwrap().to_int(4)
+    );
+}
+
+#[cfg(feature = "event-log")]
+#[test]
+fn event_log_replay_stimulus() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build_cloned().unwrap();
+    let mut replay_sim = builder.build().unwrap();
+
+    sim.start_recording();
+    sim.set_wire_drive(input, &LogicState::LOGIC_0).unwrap();
+    // Feed the output back into the input twice, after the first and second evaluation.
+    let mut call_count = 0;
+    let result = sim.run_with_stimulus(64, |sim| {
+        if (1..3).contains(&call_count) {
+            let output_state = sim.get_wire_state(output).unwrap();
+            sim.set_wire_drive(input, &output_state).unwrap();
+        }
+        call_count += 1;
+    });
+    assert!(matches!(result, SimulationRunResult::Ok));
+    let log = sim.take_event_log().unwrap();
+
+    // Every drive set by the stimulus after the start ends one recorded run.
+    let run_count = log
+        .events()
+        .iter()
+        .filter(|event| matches!(event, Event::Run { .. }))
+        .count();
+    assert_eq!(run_count, 3);
+    assert!(matches!(log.events().last(), Some(Event::Run { .. })));
+
+    let run_results = replay_sim.replay(&log).unwrap();
+    assert_eq!(run_results.len(), 3);
+    assert!(matches!(run_results.last(), Some(SimulationRunResult::Ok)));
+    assert_eq!(
+        replay_sim.get_wire_state(input).unwrap().to_bool(),
+        sim.get_wire_state(input).unwrap().to_bool()
+    );
+    assert_eq!(
+        replay_sim.get_wire_state(output).unwrap().to_bool(),
+        sim.get_wire_state(output).unwrap().to_bool()
+    );
+}
+
+#[test]
+fn gpu_timing() {
+    let mut builder = SimulatorBuilder::default();
+
+    let mut wires = vec![builder.add_wire(1).unwrap()];
+    for _ in 0..8 {
+        let output = builder.add_wire(1).unwrap();
+        add_not_gate(&mut builder, *wires.last().unwrap(), output).unwrap();
+        wires.push(output);
+    }
 
-        (HIGH_Z, LOGIC_1) -> UNDEFINED,
-        (UNDEFINED, LOGIC_1) -> UNDEFINED,
-        (LOGIC_0, LOGIC_1) -> LOGIC_0,
-        (LOGIC_1, LOGIC_1) -> LOGIC_1,
-    );
+    builder.enable_gpu_timing();
+    let mut sim = match builder.build() {
+        Ok(sim) => sim,
+        // Not every device supports timestamp queries.
+        Err(SimulatorBuildError::GpuTimingNotSupported) => return,
+        Err(err) => panic!("{err:?}"),
+    };
 
-    for width in [1, 32, 33, 64] {
-        let mut builder = SimulatorBuilder::default();
+    sim.set_wire_drive(wires[0], &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(64), SimulationRunResult::Ok));
+    assert_eq!(
+        sim.get_wire_state(wires[8]).unwrap().get_bit_state(0),
+        LogicBitState::Logic0
+    );
 
-        let input = builder.add_wire(width).unwrap();
-        let enable = builder.add_wire(1).unwrap();
-        let output = builder.add_wire(width).unwrap();
-        let _gate = builder
-            .add_component(BufferPorts {
-                input,
-                enable,
-                output,
-            })
-            .unwrap();
+    let timings = sim.last_pass_timings().unwrap();
+    assert!((timings.step_count > 0) && (timings.step_count <= 64));
+}
 
-        let mut sim = builder.build().unwrap();
+#[test]
+fn dump_all_wire_states() {
+    let mut builder = SimulatorBuilder::default();
 
-        for (i, test_data) in TEST_DATA.iter().enumerate() {
-            sim.set_wire_drive(input, &test_data.input_a).unwrap();
-            sim.set_wire_drive(enable, &test_data.input_b).unwrap();
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
 
-            match sim.run(2) {
-                SimulationRunResult::Ok => {}
-                SimulationRunResult::MaxStepsReached => panic!("[TEST {i}] exceeded max steps"),
-                SimulationRunResult::Err { conflicting_wires } => {
-                    panic!("[TEST {i}] wire conflicts: {conflicting_wires:?}");
-                }
-            }
+    let mut sim = builder.build().unwrap();
 
-            let output_state = sim.get_wire_state(output).unwrap();
+    sim.set_wire_drive(input, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
 
-            assert!(
-                output_state.eq(&test_data.output, width),
-                "[TEST {i}]  expected: {}  actual: {}",
-                test_data.output.to_string(width),
-                output_state.to_string(width),
-            );
-        }
-    }
+    let states = sim.dump_all_wire_states();
+    assert_eq!(states.len(), 2);
+    assert_eq!(states[0].0, input);
+    assert!(states[0].1.eq(&LogicState::from_int(0b0101), 4));
+    assert_eq!(states[1].0, output);
+    assert!(states[1].1.eq(&LogicState::from_int(0b1010), 4));
 }
 
 //#[test]
@@ -2131,71 +4416,125 @@ fn buffer() {
 //    );
 //}
 //
-//#[test]
-//fn horizontal_xnor_gate() {
-//    const TEST_DATA_EVEN: &[UnaryGateTestData] = unary_gate_test_data!(
-//        HIGH_Z -> UNDEFINED,
-//        UNDEFINED -> UNDEFINED,
-//        LOGIC_0 -> LOGIC_1,
-//        LOGIC_1 -> LOGIC_1,
-//
-//        0b0000 -> 1,
-//
-//        0b0001 -> 0,
-//        0b0010 -> 0,
-//        0b0100 -> 0,
-//        0b1000 -> 0,
-//
-//        0b0011 -> 1,
-//        0b0101 -> 1,
-//        0b1001 -> 1,
-//
-//        0b0111 -> 0,
-//        0b1011 -> 0,
-//    );
-//
-//    test_horizontal_gate(
-//        SimulatorBuilder::add_horizontal_xnor_gate,
-//        WIDTH_4,
-//        TEST_DATA_EVEN,
-//        2,
-//    );
-//
-//    const TEST_DATA_ODD: &[UnaryGateTestData] = unary_gate_test_data!(
-//        HIGH_Z -> UNDEFINED,
-//        UNDEFINED -> UNDEFINED,
-//        LOGIC_0 -> LOGIC_1,
-//        LOGIC_1 -> LOGIC_0,
-//
-//        0b00000 -> 1,
-//
-//        0b00001 -> 0,
-//        0b00010 -> 0,
-//        0b00100 -> 0,
-//        0b01000 -> 0,
-//        0b10000 -> 0,
-//
-//        0b00011 -> 1,
-//        0b00101 -> 1,
-//        0b01001 -> 1,
-//        0b10001 -> 1,
-//
-//        0b00111 -> 0,
-//        0b01011 -> 0,
-//        0b10011 -> 0,
-//
-//        0b01111 -> 1,
-//        0b10111 -> 1,
-//    );
-//
-//    test_horizontal_gate(
-//        SimulatorBuilder::add_horizontal_xnor_gate,
-//        WIDTH_5,
-//        TEST_DATA_ODD,
-//        2,
-//    );
-//}
-//
+#[test]
+fn horizontal_xnor_gate() {
+    const TEST_DATA_EVEN: &[UnaryGateTestData] = unary_gate_test_data!(
+        HIGH_Z -> UNDEFINED,
+        UNDEFINED -> UNDEFINED,
+        LOGIC_0 -> LOGIC_1,
+        LOGIC_1 -> LOGIC_1,
+
+        0b0000 -> 1,
+
+        0b0001 -> 0,
+        0b0010 -> 0,
+        0b0100 -> 0,
+        0b1000 -> 0,
+
+        0b0011 -> 1,
+        0b0101 -> 1,
+        0b1001 -> 1,
+
+        0b0111 -> 0,
+        0b1011 -> 0,
+    );
+
+    test_horizontal_gate(add_horizontal_xnor_gate, 4, TEST_DATA_EVEN, 2);
+
+    const TEST_DATA_ODD: &[UnaryGateTestData] = unary_gate_test_data!(
+        HIGH_Z -> UNDEFINED,
+        UNDEFINED -> UNDEFINED,
+        LOGIC_0 -> LOGIC_1,
+        LOGIC_1 -> LOGIC_0,
+
+        0b00000 -> 1,
+
+        0b00001 -> 0,
+        0b00010 -> 0,
+        0b00100 -> 0,
+        0b01000 -> 0,
+        0b10000 -> 0,
+
+        0b00011 -> 1,
+        0b00101 -> 1,
+        0b01001 -> 1,
+        0b10001 -> 1,
+
+        0b00111 -> 0,
+        0b01011 -> 0,
+        0b10011 -> 0,
+
+        0b01111 -> 1,
+        0b10111 -> 1,
+    );
+
+    test_horizontal_gate(add_horizontal_xnor_gate, 5, TEST_DATA_ODD, 2);
+}
+
+#[test]
+fn horizontal_parity() {
+    let mut even_parity = Vec::new();
+    let mut odd_parity = Vec::new();
+    for value in [
+        0x0000, 0x0001, 0x8000, 0x00FF, 0x0F0F, 0x7FFF, 0xFFFF, 0xA5A4,
+    ] {
+        let parity = u32::count_ones(value) % 2;
+
+        even_parity.push(UnaryGateTestData {
+            input: LogicState::from_int(value),
+            output: LogicState::from_int(parity),
+        });
+        odd_parity.push(UnaryGateTestData {
+            input: LogicState::from_int(value),
+            output: LogicState::from_int(parity ^ 1),
+        });
+    }
+
+    for input in ["X000000000000000", "000000000000000Z", "11111111X1111111"] {
+        for test_data in [&mut even_parity, &mut odd_parity] {
+            test_data.push(UnaryGateTestData {
+                input: LogicState::parse(input).unwrap(),
+                output: LogicState::UNDEFINED,
+            });
+        }
+    }
+
+    test_horizontal_gate(add_horizontal_xor_gate, 16, &even_parity, 2);
+    test_horizontal_gate(add_horizontal_xnor_gate, 16, &odd_parity, 2);
+}
+
+#[test]
+fn horizontal_xnor_gate_wide() {
+    let test_data = [
+        UnaryGateTestData {
+            input: logic_state!([0x0000_0000, 0x0000_0000]),
+            output: logic_state!(1),
+        },
+        UnaryGateTestData {
+            input: logic_state!([0x0000_0001, 0x0000_0000]),
+            output: logic_state!(0),
+        },
+        UnaryGateTestData {
+            input: logic_state!([0x0000_0000, 0x8000_0000]),
+            output: logic_state!(0),
+        },
+        UnaryGateTestData {
+            input: logic_state!([0x0000_0001, 0x0000_0001]),
+            output: logic_state!(1),
+        },
+        UnaryGateTestData {
+            input: logic_state!([0xFFFF_FFFF, 0x7FFF_FFFF]),
+            output: logic_state!(0),
+        },
+        UnaryGateTestData {
+            input: LogicState::parse(&format!("{}{}1", "X".repeat(32), "0".repeat(31))).unwrap(),
+            output: logic_state!(UNDEFINED),
+        },
+    ];
+
+    test_horizontal_gate(add_horizontal_xnor_gate, 64, &test_data, 2);
+}
+
 //#[test]
 //fn compare_equal() {
 //    test_comparator(SimulatorBuilder::add_compare_equal, |a, b| a == b);
@@ -2525,3 +4864,638 @@ fn buffer() {
 //        );
 //    }
 //}
+
+#[test]
+fn buffer_swap_remove_and_compact() {
+    let index = |value| Index::new(value).unwrap();
+
+    let mut buffer = Buffer::<u32, Building>::new();
+    for value in 0..6 {
+        buffer.push(value * 10).unwrap();
+    }
+
+    assert_eq!(buffer.swap_remove(index(1)), Some(10));
+    assert_eq!(buffer.swap_remove(index(5)), None);
+    assert_eq!(buffer.swap_remove(Index::INVALID), None);
+    assert_eq!(buffer.as_slice(), &[0, 50, 20, 30, 40]);
+
+    let remap = buffer.compact(|_, &value| (value != 20) && (value != 40));
+    assert_eq!(buffer.as_slice(), &[0, 50, 30]);
+    assert_eq!(
+        remap,
+        [index(0), index(1), Index::INVALID, index(2), Index::INVALID]
+    );
+}
+
+#[test]
+fn get_wire_bits() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(input, &LogicState::parse("Z100").unwrap())
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(
+        sim.get_wire_bits(output).unwrap(),
+        [
+            LogicBitState::Logic1,
+            LogicBitState::Logic1,
+            LogicBitState::Logic0,
+            LogicBitState::Undefined,
+        ]
+    );
+    assert!(matches!(
+        sim.get_wire_bits(WireId::INVALID),
+        Err(InvalidWireIdError)
+    ));
+}
+
+#[test]
+fn reset_wires() {
+    let mut builder = SimulatorBuilder::default();
+
+    let a = builder.add_wire(4).unwrap();
+    let b = builder.add_wire(4).unwrap();
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(a, &LogicState::from_int(0b1010))
+        .unwrap();
+    sim.set_wire_drive(b, &LogicState::from_int(0b0101))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    sim.reset_wires(&[a]).unwrap();
+    assert!(sim.get_wire_state(a).unwrap().eq(&LogicState::HIGH_Z, 4));
+    assert!(sim
+        .get_wire_state(b)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0101), 4));
+
+    // The drive is kept, so the next run restores the wire.
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert!(sim
+        .get_wire_state(a)
+        .unwrap()
+        .eq(&LogicState::from_int(0b1010), 4));
+
+    assert!(matches!(
+        sim.reset_wires(&[b, WireId::INVALID]),
+        Err(InvalidWireIdError)
+    ));
+    assert!(sim
+        .get_wire_state(b)
+        .unwrap()
+        .eq(&LogicState::from_int(0b0101), 4));
+}
+
+#[test]
+fn run_fresh() {
+    let mut builder = SimulatorBuilder::default();
+
+    let clock = builder.add_wire(1).unwrap();
+    let enable = builder.add_wire(1).unwrap();
+    let reset = builder.add_wire(1).unwrap();
+    let count_out = builder.add_wire(2).unwrap();
+    let wrap_out = builder.add_wire(1).unwrap();
+    builder
+        .add_component(CounterPorts {
+            clock,
+            enable,
+            reset,
+            count_out,
+            wrap_out,
+        })
+        .unwrap();
+
+    let mut sim = builder.build().unwrap();
+
+    sim.set_wire_drive(reset, &LogicState::LOGIC_0).unwrap();
+    sim.set_wire_drive(enable, &LogicState::LOGIC_1).unwrap();
+    for _ in 0..2 {
+        sim.set_wire_drive(clock, &LogicState::LOGIC_0).unwrap();
+        assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+        sim.set_wire_drive(clock, &LogicState::LOGIC_1).unwrap();
+        assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    }
+    assert_eq!(sim.get_wire_state(count_out).unwrap().to_int(2), Ok(2));
+
+    // A plain run keeps the count, a fresh run starts over while keeping the drives.
+    sim.set_wire_drive(clock, &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(count_out).unwrap().to_int(2), Ok(2));
+    assert!(matches!(sim.run_fresh(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(count_out).unwrap().to_int(2), Ok(0));
+    assert_eq!(sim.get_wire_state(enable).unwrap().to_bool(), Some(true));
+}
+
+#[test]
+fn add_gate_with_output() {
+    let mut builder = SimulatorBuilder::default();
+
+    let inputs = [
+        builder.add_wire(4).unwrap(),
+        builder.add_wire(4).unwrap(),
+        builder.add_wire(4).unwrap(),
+    ];
+    let (and_gate, and_output) = builder.add_and_gate(&inputs).unwrap();
+    let (_, xor_output) = builder.add_xor_gate(&inputs[..2]).unwrap();
+    assert_eq!(
+        builder.component_kind(and_gate).unwrap(),
+        ComponentKind::And
+    );
+    assert_eq!(builder.component_inputs(and_gate).unwrap(), inputs);
+    assert!(matches!(
+        builder.assert_same_width(&[and_output, xor_output]),
+        Ok(4)
+    ));
+
+    let narrow = builder.add_wire(1).unwrap();
+    let wire_count = builder.wire_count();
+    assert!(matches!(
+        builder.add_or_gate(&[inputs[0], narrow]),
+        Err(AddComponentError::WidthMismatch)
+    ));
+    assert_eq!(builder.wire_count(), wire_count);
+
+    let mut sim = builder.build().unwrap();
+    sim.set_wire_drive(inputs[0], &LogicState::from_int(0b1100))
+        .unwrap();
+    sim.set_wire_drive(inputs[1], &LogicState::from_int(0b1010))
+        .unwrap();
+    sim.set_wire_drive(inputs[2], &LogicState::from_int(0b1111))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(
+        sim.get_wire_state(and_output).unwrap().to_int(4),
+        Ok(0b1000)
+    );
+    assert_eq!(
+        sim.get_wire_state(xor_output).unwrap().to_int(4),
+        Ok(0b0110)
+    );
+}
+
+#[test]
+fn structural_hash() {
+    fn build(drive: &LogicState, output_width: u32) -> SimulatorBuilder {
+        let mut builder = SimulatorBuilder::default();
+        let a = builder.add_wire(4).unwrap();
+        let b = builder.add_wire(4).unwrap();
+        let output = builder.add_wire(output_width).unwrap();
+        add_and_gate(&mut builder, &[a, b], output).unwrap();
+        builder.set_wire_drive(a, drive).unwrap();
+        builder
+    }
+
+    let hash = build(&LogicState::LOGIC_0, 4).structural_hash();
+    assert_eq!(build(&LogicState::LOGIC_1, 4).structural_hash(), hash);
+    assert_ne!(build(&LogicState::LOGIC_0, 3).structural_hash(), hash);
+
+    let mut builder = build(&LogicState::LOGIC_0, 4);
+    builder.add_wire(1).unwrap();
+    assert_ne!(builder.structural_hash(), hash);
+}
+
+#[test]
+fn arithmetic_flags() {
+    // (subtract, lhs, rhs, result, zero, negative, overflow)
+    const TEST_DATA: &[(bool, u32, u32, u32, bool, bool, bool)] = &[
+        (false, 0x00, 0x00, 0x00, true, false, false),
+        (false, 0x01, 0x02, 0x03, false, false, false),
+        (false, 0x7F, 0x01, 0x80, false, true, true),
+        (false, 0xFF, 0x01, 0x00, true, false, false),
+        (false, 0x80, 0x80, 0x00, true, false, true),
+        (true, 0x05, 0x05, 0x00, true, false, false),
+        (true, 0x00, 0x01, 0xFF, false, true, false),
+        (true, 0x80, 0x01, 0x7F, false, false, true),
+        (true, 0x7F, 0xFF, 0x80, false, true, true),
+    ];
+
+    let mut builder = SimulatorBuilder::default();
+
+    let input_lhs = builder.add_wire(8).unwrap();
+    let input_rhs = builder.add_wire(8).unwrap();
+    let wires: [[WireId; 4]; 2] = std::array::from_fn(|_| {
+        [
+            builder.add_wire(8).unwrap(),
+            builder.add_wire(1).unwrap(),
+            builder.add_wire(1).unwrap(),
+            builder.add_wire(1).unwrap(),
+        ]
+    });
+    let [add_wires, sub_wires] = wires;
+
+    builder
+        .add_component(AddWithFlagsPorts {
+            input_lhs,
+            input_rhs,
+            output: add_wires[0],
+            zero: add_wires[1],
+            negative: add_wires[2],
+            overflow: add_wires[3],
+        })
+        .unwrap();
+    builder
+        .add_component(SubtractWithFlagsPorts {
+            input_lhs,
+            input_rhs,
+            output: sub_wires[0],
+            zero: sub_wires[1],
+            negative: sub_wires[2],
+            overflow: sub_wires[3],
+        })
+        .unwrap();
+
+    // The flags must be single bits.
+    let unused = builder.add_wire(8).unwrap();
+    let result = builder.add_component(AddWithFlagsPorts {
+        input_lhs,
+        input_rhs,
+        output: unused,
+        zero: unused,
+        negative: unused,
+        overflow: unused,
+    });
+    assert!(matches!(result, Err(AddComponentError::WidthMismatch)));
+    assert_eq!(builder_driver_count(&builder, unused), 0);
+
+    let mut sim = builder.build().unwrap();
+
+    for (i, &(subtract, lhs, rhs, result, zero, negative, overflow)) in TEST_DATA.iter().enumerate()
+    {
+        sim.set_wire_drive(input_lhs, &LogicState::from_int(lhs))
+            .unwrap();
+        sim.set_wire_drive(input_rhs, &LogicState::from_int(rhs))
+            .unwrap();
+        assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+        let [output, zero_out, negative_out, overflow_out] =
+            if subtract { sub_wires } else { add_wires };
+        assert_eq!(
+            sim.get_wire_state(output).unwrap().to_int(8),
+            Ok(result),
+            "[TEST {i}]"
+        );
+        assert_eq!(
+            sim.get_wire_state(zero_out).unwrap().to_bool(),
+            Some(zero),
+            "[TEST {i}]"
+        );
+        assert_eq!(
+            sim.get_wire_state(negative_out).unwrap().to_bool(),
+            Some(negative),
+            "[TEST {i}]"
+        );
+        assert_eq!(
+            sim.get_wire_state(overflow_out).unwrap().to_bool(),
+            Some(overflow),
+            "[TEST {i}]"
+        );
+    }
+
+    // A known 1 in the result decides the zero flag, all other flags depend on unknown bits.
+    sim.set_wire_drive(input_lhs, &LogicState::parse("X0000001").unwrap())
+        .unwrap();
+    sim.set_wire_drive(input_rhs, &LogicState::from_int(0))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(
+        sim.get_wire_state(add_wires[1]).unwrap().to_bool(),
+        Some(false)
+    );
+    assert_eq!(sim.get_wire_state(add_wires[2]).unwrap().to_bool(), None);
+    assert_eq!(sim.get_wire_state(add_wires[3]).unwrap().to_bool(), None);
+}
+
+#[test]
+fn run_with_stimulus() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(1).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    add_not_gate(&mut builder, input, output).unwrap();
+
+    let mut sim = builder.build().unwrap();
+    sim.set_wire_drive(input, &LogicState::LOGIC_0).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+
+    // Feeding the output back into the input makes the circuit oscillate
+    // for as long as the stimulus keeps doing it.
+    let mut feedback_count = 0;
+    let result = sim.run_with_stimulus(64, |sim| {
+        if feedback_count < 4 {
+            let output_state = sim.get_wire_state(output).unwrap();
+            sim.set_wire_drive(input, &output_state).unwrap();
+            feedback_count += 1;
+        }
+    });
+    assert!(matches!(result, SimulationRunResult::Ok));
+    assert_eq!(feedback_count, 4);
+    assert_eq!(sim.get_wire_state(input).unwrap().to_bool(), Some(false));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_bool(), Some(true));
+
+    // A stimulus that never stops changing the drives runs out of steps.
+    let result = sim.run_with_stimulus(64, |sim| {
+        let output_state = sim.get_wire_state(output).unwrap();
+        sim.set_wire_drive(input, &output_state).unwrap();
+    });
+    assert!(matches!(result, SimulationRunResult::MaxStepsReached));
+}
+
+#[test]
+fn compare_const() {
+    const TEST_DATA: &[(&str, Option<bool>)] = &[
+        ("1010_0101", Some(true)),
+        ("1010_0100", Some(false)),
+        ("0010_0101", Some(false)),
+        ("1010_010X", None),
+        ("Z010_0101", None),
+    ];
+
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(8).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    builder
+        .add_component(CompareConstPorts {
+            input,
+            constant: LogicState::from_int(0xA5),
+            output,
+        })
+        .unwrap();
+
+    let result = builder.add_component(CompareConstPorts {
+        input,
+        constant: LogicState::from_int(0xA5),
+        output: input,
+    });
+    assert!(matches!(result, Err(AddComponentError::WidthMismatch)));
+
+    let mut sim = builder.build().unwrap();
+
+    for (i, &(input_state, expected)) in TEST_DATA.iter().enumerate() {
+        let input_state = LogicState::parse(&input_state.replace('_', "")).unwrap();
+        sim.set_wire_drive(input, &input_state).unwrap();
+        assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+        assert_eq!(
+            sim.get_wire_state(output).unwrap().to_bool(),
+            expected,
+            "[TEST {i}]"
+        );
+    }
+}
+
+#[test]
+fn driver_width_mismatches() {
+    let mut builder = SimulatorBuilder::default();
+    let a = builder.add_wire(4).unwrap();
+    let b = builder.add_wire(4).unwrap();
+    let bus = builder.add_wire(8).unwrap();
+    let low = builder.add_wire_slice(bus, 0, 4).unwrap();
+    let high = builder.add_wire_slice(bus, 4, 4).unwrap();
+    add_and_gate(&mut builder, &[a, b], low).unwrap();
+    add_or_gate(&mut builder, &[a, b], high).unwrap();
+    let (gate, output) = builder.add_xor_gate(&[a, b]).unwrap();
+    assert_eq!(builder.driver_width_mismatches(), []);
+
+    // The public API cannot create a mismatch, so widen the wire behind the driver's back.
+    builder.wires.get_mut(output.0).unwrap().width = 8;
+    assert_eq!(builder.driver_width_mismatches(), [(output, gate)]);
+}
+
+#[test]
+fn debug_dump() {
+    let mut builder = SimulatorBuilder::default();
+    let a = builder.add_wire(4).unwrap();
+    let b = builder.add_wire(4).unwrap();
+    let bus = builder.add_wire(8).unwrap();
+    let low = builder.add_wire_slice(bus, 0, 4).unwrap();
+    let alias = builder.add_wire(4).unwrap();
+    builder.alias_wire(a, alias).unwrap();
+    add_and_gate(&mut builder, &[a, b], low).unwrap();
+    let sum = builder.add_wire(4).unwrap();
+    let zero = builder.add_wire(1).unwrap();
+    let negative = builder.add_wire(1).unwrap();
+    let overflow = builder.add_wire(1).unwrap();
+    builder
+        .add_component(AddWithFlagsPorts {
+            input_lhs: alias,
+            input_rhs: b,
+            output: sum,
+            zero,
+            negative,
+            overflow,
+        })
+        .unwrap();
+    add_not_gate(&mut builder, b, alias).unwrap();
+
+    // Drivers of slices and aliases count for the wires they belong to.
+    let expected = "\
+wire 0: width 4, 1 driver
+wire 1: width 4, 0 drivers
+wire 2: width 8, 1 driver
+wire 3: width 4, 1 driver, slice of wire 2 at bit 0
+wire 4: width 4, 1 driver, alias of wire 0
+wire 5: width 4, 1 driver
+wire 6: width 1, 1 driver
+wire 7: width 1, 1 driver
+wire 8: width 1, 1 driver
+component 0: And, inputs [0, 1], outputs [3]
+component 1: AddFlags, inputs [4, 1], outputs [5, 6, 7, 8]
+component 2: Not, inputs [1], outputs [4]
+";
+    assert_eq!(builder.debug_dump(), expected);
+}
+
+#[test]
+fn set_component_constant() {
+    let mut builder = SimulatorBuilder::default();
+
+    let input = builder.add_wire(8).unwrap();
+    let output = builder.add_wire(1).unwrap();
+    let compare = builder
+        .add_component(CompareConstPorts {
+            input,
+            constant: LogicState::from_int(0xA5),
+            output,
+        })
+        .unwrap();
+    let not_output = builder.add_wire(8).unwrap();
+    let not = builder
+        .add_component(NotGatePorts {
+            input,
+            output: not_output,
+        })
+        .unwrap();
+
+    let mut sim = builder.build().unwrap();
+    sim.set_wire_drive(input, &LogicState::from_int(0x5A))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_bool(), Some(false));
+
+    sim.set_component_constant(compare, &LogicState::from_int(0x5A))
+        .unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_bool(), Some(true));
+
+    // The new constant survives a reset.
+    sim.reset();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(output).unwrap().to_bool(), Some(true));
+
+    assert!(matches!(
+        sim.set_component_constant(not, &LogicState::LOGIC_0),
+        Err(SetComponentConstantError::NoConstant)
+    ));
+    assert!(matches!(
+        sim.set_component_constant(ComponentId::INVALID, &LogicState::LOGIC_0),
+        Err(SetComponentConstantError::InvalidComponentId)
+    ));
+}
+
+#[test]
+fn snapshot_bytes() {
+    let mut builder = SimulatorBuilder::default();
+    builder.set_debug_label("snapshot");
+    let a = builder.add_wire(4).unwrap();
+    let b = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_and_gate(&mut builder, &[a, b], output).unwrap();
+    builder
+        .set_wire_drive(a, &LogicState::from_int(0x5))
+        .unwrap();
+
+    let bytes = builder.snapshot().unwrap().to_bytes();
+    let snapshot = CircuitSnapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(snapshot.to_bytes(), bytes);
+
+    let mut future_version = bytes.clone();
+    future_version[4..8].copy_from_slice(&(CircuitSnapshot::FORMAT_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&future_version),
+        Err(DeserializeError::UnsupportedVersion { version })
+            if version == CircuitSnapshot::FORMAT_VERSION + 1
+    ));
+
+    let mut other_width = bytes.clone();
+    other_width[8..12].copy_from_slice(&(MAX_WIRE_WIDTH * 2).to_le_bytes());
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&other_width),
+        Err(DeserializeError::MaxWireWidthMismatch { .. })
+    ));
+
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(DeserializeError::InvalidFormat)
+    ));
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(b"not a snapshot"),
+        Err(DeserializeError::InvalidFormat)
+    ));
+
+    let mut other_byte_order = bytes.clone();
+    other_byte_order[12..16].reverse();
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&other_byte_order),
+        Err(DeserializeError::ByteOrderMismatch)
+    ));
+}
+
+#[test]
+fn snapshot_offsets_validated() {
+    use std::mem::size_of;
+
+    /// Finds the first byte of the data of buffer `section` in a snapshot without a debug label
+    fn section_start(bytes: &[u8], section: usize) -> usize {
+        const ELEMENT_SIZES: [usize; 5] = [
+            size_of::<LogicStateAtom>(),
+            size_of::<LogicStateAtom>(),
+            size_of::<LogicStateAtom>(),
+            size_of::<WireDriver>(),
+            size_of::<Wire>(),
+        ];
+
+        let mut start = 28;
+        for element_size in &ELEMENT_SIZES[..section] {
+            let len = u32::from_le_bytes(bytes[start..(start + 4)].try_into().unwrap());
+            start += 4 + (len as usize) * element_size;
+        }
+        start + 4
+    }
+
+    let mut builder = SimulatorBuilder::default();
+    let a = builder.add_wire(4).unwrap();
+    let b = builder.add_wire(4).unwrap();
+    let output = builder.add_wire(4).unwrap();
+    add_and_gate(&mut builder, &[a, b], output).unwrap();
+    let bytes = builder.snapshot().unwrap().to_bytes();
+    assert!(CircuitSnapshot::from_bytes(&bytes).is_ok());
+
+    // The state offset of the last wire, which is the second field of `Wire`.
+    let wires_start = section_start(&bytes, 4);
+    let state_offset = wires_start + 2 * size_of::<Wire>() + 4;
+    assert_eq!(bytes[state_offset..(state_offset + 4)], 2u32.to_ne_bytes());
+    let mut out_of_range = bytes.clone();
+    out_of_range[state_offset..(state_offset + 4)].copy_from_slice(&1000u32.to_ne_bytes());
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&out_of_range),
+        Err(DeserializeError::InvalidFormat)
+    ));
+
+    // The last entry of the evaluation order.
+    let mut out_of_range = bytes.clone();
+    let len = out_of_range.len();
+    assert_eq!(out_of_range[(len - 4)..], 0u32.to_ne_bytes());
+    out_of_range[(len - 4)..].copy_from_slice(&5u32.to_ne_bytes());
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&out_of_range),
+        Err(DeserializeError::InvalidFormat)
+    ));
+
+    // The conflict list size follows the magic, version, width, byte order mark and flags.
+    let mut too_many_conflicts = bytes.clone();
+    assert_eq!(
+        too_many_conflicts[20..24],
+        DEFAULT_MAX_REPORTED_CONFLICTS.to_le_bytes()
+    );
+    too_many_conflicts[20..24].copy_from_slice(&(MAX_REPORTED_CONFLICTS_LIMIT + 1).to_le_bytes());
+    assert!(matches!(
+        CircuitSnapshot::from_bytes(&too_many_conflicts),
+        Err(DeserializeError::InvalidFormat)
+    ));
+}
+
+#[test]
+fn from_snapshot() {
+    let mut builder = SimulatorBuilder::default();
+    let a = builder.add_wire(8).unwrap();
+    let b = builder.add_wire(8).unwrap();
+    let bus = builder.add_wire(16).unwrap();
+    let low = builder.add_wire_slice(bus, 0, 8).unwrap();
+    let high = builder.add_wire_slice(bus, 8, 8).unwrap();
+    add_and_gate(&mut builder, &[a, b], low).unwrap();
+    add_or_gate(&mut builder, &[a, b], high).unwrap();
+    builder
+        .set_wire_drive(a, &LogicState::from_int(0x0F))
+        .unwrap();
+    builder
+        .set_wire_drive(b, &LogicState::from_int(0x3C))
+        .unwrap();
+
+    let bytes = builder.snapshot().unwrap().to_bytes();
+    let snapshot = CircuitSnapshot::from_bytes(&bytes).unwrap();
+    let mut sim = Simulator::from_snapshot(&snapshot, GpuOptions::default()).unwrap();
+
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(bus).unwrap().to_int(16), Ok(0x3F0C));
+
+    sim.set_wire_drive(b, &LogicState::from_int(0xFF)).unwrap();
+    assert!(matches!(sim.run(8), SimulationRunResult::Ok));
+    assert_eq!(sim.get_wire_state(bus).unwrap().to_int(16), Ok(0xFF0F));
+}
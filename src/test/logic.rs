@@ -0,0 +1,333 @@
+use super::*;
+
+/// Widths at which the masks computed from the width switch between partial and full atoms
+const BOUNDARY_WIDTHS: &[u32] = &[
+    1,
+    31,
+    32,
+    33,
+    63,
+    64,
+    65,
+    MAX_WIRE_WIDTH - 1,
+    MAX_WIRE_WIDTH,
+];
+
+/// A state with every bit set to 1, except for the given bits
+fn ones_except(overrides: &[(u32, LogicBitState)]) -> LogicState {
+    let mut bits = vec![LogicBitState::Logic1; MAX_WIRE_WIDTH as usize];
+    for &(index, bit) in overrides {
+        bits[(MAX_WIRE_WIDTH - 1 - index) as usize] = bit;
+    }
+    LogicState::from_bits(&bits).unwrap()
+}
+
+/// A state with every bit set to 1, except for bit `index`, which is 0
+fn single_zero(index: u32) -> LogicState {
+    ones_except(&[(index, LogicBitState::Logic0)])
+}
+
+#[test]
+fn to_int_width_boundaries() {
+    assert_eq!(LogicState::LOGIC_1.to_int(1), Ok(0x1));
+    assert_eq!(LogicState::LOGIC_1.to_int(31), Ok(0x7FFF_FFFF));
+    assert_eq!(LogicState::LOGIC_1.to_int(32), Ok(u32::MAX));
+    assert_eq!(
+        LogicState::LOGIC_1.to_int(33),
+        Err(ToIntError::InvalidWidth)
+    );
+    assert_eq!(LogicState::LOGIC_1.to_int(0), Err(ToIntError::InvalidWidth));
+
+    // Bits past the width must not be checked.
+    let state = LogicState::parse("X").unwrap();
+    assert_eq!(state.to_int(32), Err(ToIntError::Unrepresentable));
+    let state = single_zero(32);
+    assert_eq!(state.to_int(32), Ok(u32::MAX));
+}
+
+#[test]
+fn to_big_int_width_boundaries() {
+    for &width in BOUNDARY_WIDTHS {
+        let words = LogicState::LOGIC_1.to_big_int::<Vec<_>>(width).unwrap();
+        assert_eq!(words.len(), width.div_ceil(32) as usize, "width {width}");
+
+        let (last, full) = words.split_last().unwrap();
+        assert!(full.iter().all(|&word| word == u32::MAX), "width {width}");
+        assert_eq!(*last, u32::MAX >> ((32 - width % 32) % 32), "width {width}");
+
+        let words = LogicState::LOGIC_1.to_words(width).unwrap();
+        assert_eq!(
+            words[..width.div_ceil(32) as usize],
+            LogicState::LOGIC_1.to_big_int::<Vec<_>>(width).unwrap(),
+            "width {width}"
+        );
+    }
+
+    assert_eq!(
+        LogicState::LOGIC_1.to_big_int::<Vec<_>>(MAX_WIRE_WIDTH + 1),
+        Err(ToIntError::InvalidWidth)
+    );
+}
+
+#[test]
+fn from_big_int_word_count() {
+    let words = [u32::MAX; LOGIC_STATE_WORD_COUNT + 1];
+    let state = LogicState::from_big_int(&words[..LOGIC_STATE_WORD_COUNT]).unwrap();
+    assert!(state.eq(&LogicState::LOGIC_1, MAX_WIRE_WIDTH));
+    assert_eq!(
+        state.to_words(MAX_WIRE_WIDTH).unwrap(),
+        words[..LOGIC_STATE_WORD_COUNT]
+    );
+
+    assert!(matches!(
+        LogicState::from_big_int(&words),
+        Err(FromBigIntError::InvalidWordCount)
+    ));
+    assert!(matches!(
+        LogicState::from_big_int(&[]),
+        Err(FromBigIntError::InvalidWordCount)
+    ));
+}
+
+#[test]
+fn eq_width_boundaries() {
+    for &width in BOUNDARY_WIDTHS {
+        // Differs from all ones only in the first bit past the width.
+        if width < MAX_WIRE_WIDTH {
+            let state = single_zero(width);
+            assert!(state.eq(&LogicState::LOGIC_1, width), "width {width}");
+            assert_eq!(state.first_difference(&LogicState::LOGIC_1, width), None);
+        }
+
+        // Differs from all ones only in the last bit of the width.
+        let state = single_zero(width - 1);
+        assert!(!state.eq(&LogicState::LOGIC_1, width), "width {width}");
+        assert_eq!(
+            state.first_difference(&LogicState::LOGIC_1, width),
+            Some(width - 1)
+        );
+    }
+}
+
+#[test]
+fn from_u128_width_boundaries() {
+    for width in [1, 32, 64, 127, 128, MAX_WIRE_WIDTH] {
+        let state = LogicState::from_u128(u128::MAX, width).unwrap();
+        let expected_ones = width.min(u128::BITS);
+        assert_eq!(
+            state.first_difference(&LogicState::LOGIC_1, MAX_WIRE_WIDTH),
+            (expected_ones < MAX_WIRE_WIDTH).then_some(expected_ones),
+            "width {width}"
+        );
+    }
+}
+
+#[test]
+fn splat_width_boundaries() {
+    for &width in BOUNDARY_WIDTHS {
+        let state = LogicState::splat(LogicBitState::Logic1, width);
+        assert!(state.eq(&LogicState::LOGIC_1, width), "width {width}");
+
+        if width < MAX_WIRE_WIDTH {
+            assert_eq!(
+                state.first_difference(&LogicState::LOGIC_1, MAX_WIRE_WIDTH),
+                Some(width),
+                "width {width}"
+            );
+            assert_eq!(
+                state.get_bit_state(width),
+                LogicBitState::HighZ,
+                "width {width}"
+            );
+        }
+    }
+}
+
+#[test]
+fn lsb_first_matches_reversed_msb_first() {
+    const PATTERN: &[u8] = b"10XZ0110";
+
+    for &width in BOUNDARY_WIDTHS {
+        let msb_first: String = PATTERN
+            .iter()
+            .cycle()
+            .take(width as usize)
+            .map(|&c| c as char)
+            .collect();
+        let lsb_first: String = msb_first.chars().rev().collect();
+
+        let expected = LogicState::parse(&msb_first).unwrap();
+        let state = LogicState::parse_lsb_first(&lsb_first).unwrap();
+        assert_eq!(
+            state.first_difference(&expected, MAX_WIRE_WIDTH),
+            None,
+            "width {width}"
+        );
+
+        let bits: Vec<_> = lsb_first
+            .chars()
+            .map(|c| LogicBitState::parse(c).unwrap())
+            .collect();
+        let state = LogicState::from_bits_lsb_first(&bits).unwrap();
+        assert_eq!(
+            state.first_difference(&expected, MAX_WIRE_WIDTH),
+            None,
+            "width {width}"
+        );
+    }
+
+    assert!(matches!(
+        LogicState::parse_lsb_first("01a"),
+        Err(ParseError::IllegalCharacter(b'a'))
+    ));
+    assert!(matches!(
+        LogicState::parse_lsb_first(""),
+        Err(ParseError::InvalidWidth)
+    ));
+}
+
+#[test]
+fn compare_eq_width_boundaries() {
+    for &width in BOUNDARY_WIDTHS {
+        assert_eq!(
+            LogicState::LOGIC_1.compare_eq(&LogicState::LOGIC_1, width),
+            LogicBitState::Logic1,
+            "width {width}"
+        );
+
+        // Unknown only in the first bit past the width.
+        if width < MAX_WIRE_WIDTH {
+            let state = ones_except(&[(width, LogicBitState::Undefined)]);
+            assert_eq!(
+                state.compare_eq(&LogicState::LOGIC_1, width),
+                LogicBitState::Logic1,
+                "width {width}"
+            );
+        }
+
+        // Unknown in the last bit of the width.
+        let state = ones_except(&[(width - 1, LogicBitState::HighZ)]);
+        assert_eq!(
+            state.compare_eq(&LogicState::LOGIC_1, width),
+            LogicBitState::Undefined,
+            "width {width}"
+        );
+
+        // A known difference takes priority over unknown bits.
+        let state = if width > 1 {
+            ones_except(&[
+                (width - 1, LogicBitState::Logic0),
+                (0, LogicBitState::Undefined),
+            ])
+        } else {
+            single_zero(0)
+        };
+        assert_eq!(
+            state.compare_eq(&LogicState::LOGIC_1, width),
+            LogicBitState::Logic0,
+            "width {width}"
+        );
+    }
+}
+
+#[test]
+fn to_usize_width_boundaries() {
+    for &width in BOUNDARY_WIDTHS {
+        let result = LogicState::LOGIC_1.to_usize(width);
+        if width <= usize::BITS {
+            assert_eq!(
+                result,
+                Ok(usize::MAX >> (usize::BITS - width)),
+                "width {width}"
+            );
+        } else {
+            assert_eq!(result, Err(ToIntError::Overflow), "width {width}");
+        }
+
+        // Only the lowest bit is set, all higher bits within the width are 0.
+        let state = LogicState::from_u128(1, MAX_WIRE_WIDTH).unwrap();
+        assert_eq!(state.to_usize(width), Ok(1), "width {width}");
+    }
+
+    assert_eq!(
+        LogicState::LOGIC_0.to_usize(MAX_WIRE_WIDTH + 1),
+        Err(ToIntError::InvalidWidth)
+    );
+    assert_eq!(
+        LogicState::HIGH_Z.to_usize(1),
+        Err(ToIntError::Unrepresentable)
+    );
+}
+
+#[test]
+fn planes_width_boundaries() {
+    const PATTERN: &[u8] = b"10XZ0110";
+
+    for &width in BOUNDARY_WIDTHS {
+        let s: String = PATTERN
+            .iter()
+            .cycle()
+            .take(width as usize)
+            .map(|&c| c as char)
+            .collect();
+        let state = LogicState::parse(&s).unwrap();
+
+        let (state_plane, valid_plane) = state.to_planes(width);
+        assert_eq!(
+            state_plane.len(),
+            width.div_ceil(8) as usize,
+            "width {width}"
+        );
+        assert_eq!(valid_plane.len(), state_plane.len(), "width {width}");
+
+        let round_trip = LogicState::from_planes(&state_plane, &valid_plane).unwrap();
+        assert_eq!(
+            round_trip.first_difference(&state, MAX_WIRE_WIDTH),
+            None,
+            "width {width}"
+        );
+    }
+
+    assert!(matches!(
+        LogicState::from_planes(&[0; 2], &[0; 1]),
+        Err(FromPlanesError::LengthMismatch)
+    ));
+    assert!(matches!(
+        LogicState::from_planes(&[], &[]),
+        Err(FromPlanesError::InvalidByteCount)
+    ));
+    let too_long = [0; (MAX_WIRE_WIDTH / 8) as usize + 1];
+    assert!(matches!(
+        LogicState::from_planes(&too_long, &too_long),
+        Err(FromPlanesError::InvalidByteCount)
+    ));
+}
+
+#[test]
+fn zero_width() {
+    let a = LogicState::LOGIC_0;
+    let b = LogicState::UNDEFINED;
+
+    assert!(a.eq(&b, 0));
+    assert!(a.matches(&b, 0));
+    assert_eq!(a.compare_eq(&b, 0), LogicBitState::Logic1);
+    assert_eq!(a.first_difference(&b, 0), None);
+    assert_eq!(a.to_string(0), "");
+    assert_eq!(a.display(0).to_string(), "");
+    assert_eq!(a.to_planes(0), (Vec::new(), Vec::new()));
+    assert!(LogicState::splat(LogicBitState::Logic1, 0).eq(&LogicState::HIGH_Z, MAX_WIRE_WIDTH));
+    assert!(a
+        .wrapping_add(&a, 0)
+        .eq(&LogicState::HIGH_Z, MAX_WIRE_WIDTH));
+
+    // Conversions have no value to return.
+    assert_eq!(a.to_int(0), Err(ToIntError::InvalidWidth));
+    assert_eq!(a.to_big_int::<Vec<_>>(0), Err(ToIntError::InvalidWidth));
+    assert_eq!(a.to_usize(0), Err(ToIntError::InvalidWidth));
+}
+
+#[test]
+#[should_panic(expected = "invalid bit width")]
+fn too_wide() {
+    LogicState::LOGIC_0.eq(&LogicState::LOGIC_0, MAX_WIRE_WIDTH + 1);
+}